@@ -0,0 +1,9 @@
+use serde::{Deserialize, Serialize};
+
+/// An executable declared with `[[bin]]`, linked from its own sources and the project's.
+#[derive(Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct BinaryConfiguration {
+    pub(crate) name: String,
+    pub(crate) sources: Vec<String>,
+}
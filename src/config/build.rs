@@ -1,7 +1,10 @@
 use crate::config::{
-    CConfiguration, CppConfiguration, GccConfiguration, GppConfiguration, ProjectConfiguration,
+    BinaryConfiguration, CConfiguration, CppConfiguration, GccConfiguration, GppConfiguration,
+    ProfileConfiguration, ProjectConfiguration, ProjectToolchainConfiguration, RunConfiguration,
+    SourceOverrideConfiguration, TargetConfiguration, TestConfiguration,
 };
 use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
 
 #[derive(Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
@@ -11,4 +14,20 @@ pub(crate) struct BuildConfiguration {
     pub(crate) cpp: Option<CppConfiguration>,
     pub(crate) gcc: Option<GccConfiguration>,
     pub(crate) gpp: Option<GppConfiguration>,
+    #[serde(default, rename = "profile")]
+    pub(crate) profiles: HashMap<String, ProfileConfiguration>,
+    #[serde(default)]
+    pub(crate) overrides: Vec<SourceOverrideConfiguration>,
+    #[serde(default, rename = "bin")]
+    pub(crate) binaries: Vec<BinaryConfiguration>,
+    #[serde(default)]
+    pub(crate) test: TestConfiguration,
+    #[serde(default)]
+    pub(crate) run: RunConfiguration,
+    /// Tools used in place of the ones from the global configuration.
+    #[serde(default)]
+    pub(crate) toolchain: ProjectToolchainConfiguration,
+    /// Tables such as `[target.'cfg(windows)']`, keyed by their predicate.
+    #[serde(default)]
+    pub(crate) target: BTreeMap<String, TargetConfiguration>,
 }
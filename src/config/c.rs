@@ -4,6 +4,8 @@ use serde::{Deserialize, Serialize};
 #[serde(rename_all = "camelCase")]
 pub(crate) struct CConfiguration {
     pub(crate) standard: Option<CStandard>,
+    /// Compiles with e.g. `-std=gnu17` instead of `-std=c17`, which many system headers expect.
+    pub(crate) gnu_extensions: Option<bool>,
 }
 
 #[derive(Clone, Deserialize, Serialize)]
@@ -26,4 +28,35 @@ impl CStandard {
     pub(crate) fn latest() -> CStandard {
         CStandard::TwentyThree
     }
+
+    pub(crate) fn name(&self) -> &'static str {
+        match self {
+            CStandard::EightyNine => "c89",
+            CStandard::NinetyNine => "c99",
+            CStandard::Eleven => "c11",
+            CStandard::Seventeen => "c17",
+            CStandard::Twenty => "c20",
+            CStandard::TwentyThree => "c23",
+        }
+    }
+
+    pub(crate) fn gnu_name(&self) -> &'static str {
+        match self {
+            CStandard::EightyNine => "gnu89",
+            CStandard::NinetyNine => "gnu99",
+            CStandard::Eleven => "gnu11",
+            CStandard::Seventeen => "gnu17",
+            CStandard::Twenty => "gnu20",
+            CStandard::TwentyThree => "gnu23",
+        }
+    }
+
+    /// The name passed to `-std=`.
+    pub(crate) fn dialect_name(&self, gnu_extensions: bool) -> &'static str {
+        if gnu_extensions {
+            self.gnu_name()
+        } else {
+            self.name()
+        }
+    }
 }
@@ -4,6 +4,8 @@ use serde::{Deserialize, Serialize};
 #[serde(rename_all = "camelCase")]
 pub(crate) struct CppConfiguration {
     pub(crate) standard: Option<CppStandard>,
+    /// Compiles with e.g. `-std=gnu++17` instead of `-std=c++17`.
+    pub(crate) gnu_extensions: Option<bool>,
 }
 
 #[derive(Clone, Deserialize, Serialize)]
@@ -30,4 +32,39 @@ impl CppStandard {
     pub(crate) fn latest() -> CppStandard {
         CppStandard::TwentySix
     }
+
+    pub(crate) fn name(&self) -> &'static str {
+        match self {
+            CppStandard::NinetyEight => "c++98",
+            CppStandard::Three => "c++3",
+            CppStandard::Eleven => "c++11",
+            CppStandard::Fourteen => "c++14",
+            CppStandard::Seventeen => "c++17",
+            CppStandard::Twenty => "c++20",
+            CppStandard::TwentyThree => "c++23",
+            CppStandard::TwentySix => "c++26",
+        }
+    }
+
+    pub(crate) fn gnu_name(&self) -> &'static str {
+        match self {
+            CppStandard::NinetyEight => "gnu++98",
+            CppStandard::Three => "gnu++03",
+            CppStandard::Eleven => "gnu++11",
+            CppStandard::Fourteen => "gnu++14",
+            CppStandard::Seventeen => "gnu++17",
+            CppStandard::Twenty => "gnu++20",
+            CppStandard::TwentyThree => "gnu++23",
+            CppStandard::TwentySix => "gnu++26",
+        }
+    }
+
+    /// The name passed to `-std=`.
+    pub(crate) fn dialect_name(&self, gnu_extensions: bool) -> &'static str {
+        if gnu_extensions {
+            self.gnu_name()
+        } else {
+            self.name()
+        }
+    }
 }
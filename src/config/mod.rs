@@ -1,15 +1,29 @@
+mod binary;
 mod build;
 mod c;
 mod cpp;
 mod gcc;
 mod gpp;
+mod profile;
 mod project;
+mod run;
+mod source_override;
+mod target;
+mod test;
 mod toolchain;
+mod workspace;
 
+pub(crate) use binary::*;
 pub(crate) use build::*;
 pub(crate) use c::*;
 pub(crate) use cpp::*;
 pub(crate) use gcc::*;
 pub(crate) use gpp::*;
+pub(crate) use profile::*;
 pub(crate) use project::*;
+pub(crate) use run::*;
+pub(crate) use source_override::*;
+pub(crate) use target::*;
+pub(crate) use test::*;
 pub(crate) use toolchain::*;
+pub(crate) use workspace::*;
@@ -0,0 +1,43 @@
+use crate::config::{
+    DebugInfoLevel, GccConfiguration, GppConfiguration, Lto, OptimizationLevel, Sanitizer,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+#[derive(Clone, Default, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct ProfileConfiguration {
+    pub(crate) optimization: Option<OptimizationLevel>,
+    pub(crate) debug_info: Option<DebugInfoLevel>,
+    pub(crate) enable_all_warnings: Option<bool>,
+    pub(crate) treat_all_warnings_as_errors: Option<bool>,
+    pub(crate) strip: Option<bool>,
+    pub(crate) lto: Option<Lto>,
+    pub(crate) target_cpu: Option<String>,
+    pub(crate) tune_cpu: Option<String>,
+    #[serde(default)]
+    pub(crate) defines: BTreeMap<String, String>,
+    #[serde(default)]
+    pub(crate) sanitizers: Vec<Sanitizer>,
+    pub(crate) coverage: Option<bool>,
+    pub(crate) gcc: Option<GccConfiguration>,
+    pub(crate) gpp: Option<GppConfiguration>,
+}
+
+impl ProfileConfiguration {
+    pub(crate) fn builtin(name: &str) -> Option<ProfileConfiguration> {
+        let (optimization, coverage) = match name {
+            "release" => (OptimizationLevel::Three, None),
+            "debug" => (OptimizationLevel::Debug, None),
+            // Optimizations blur the line counts
+            "coverage" => (OptimizationLevel::Zero, Some(true)),
+            _ => return None,
+        };
+
+        Some(ProfileConfiguration {
+            optimization: Some(optimization),
+            coverage,
+            ..Default::default()
+        })
+    }
+}
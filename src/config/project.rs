@@ -1,4 +1,5 @@
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 
 #[derive(Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
@@ -6,24 +7,104 @@ pub(crate) struct ProjectConfiguration {
     pub(crate) name: String,
     pub(crate) description: Option<String>,
     pub(crate) author: Option<String>,
+    pub(crate) version: Option<String>,
     pub(crate) language: Language,
     #[serde(default)]
     pub(crate) distribution: Distribution,
     #[serde(default)]
     pub(crate) sources: Vec<String>,
     #[serde(default)]
+    pub(crate) exclude: Vec<String>,
+    /// Exported to dependents, the same as `public_includes`.
+    #[serde(default)]
     pub(crate) includes: Vec<String>,
     #[serde(default)]
+    pub(crate) public_includes: Vec<String>,
+    /// Only used to compile the project itself.
+    #[serde(default)]
+    pub(crate) private_includes: Vec<String>,
+    #[serde(default)]
     pub(crate) dependencies: Vec<Dependency>,
     #[serde(default)]
+    pub(crate) install_headers: Vec<String>,
+    pub(crate) precompiled_header: Option<String>,
+    #[serde(default)]
+    pub(crate) defines: BTreeMap<String, String>,
+    #[serde(default)]
+    pub(crate) libraries: Vec<String>,
+    #[serde(default)]
+    pub(crate) library_search_paths: Vec<String>,
+    #[serde(default)]
+    pub(crate) link_flags: Vec<String>,
+    /// macOS frameworks linked with `-framework <name>`.
+    #[serde(default)]
+    pub(crate) frameworks: Vec<String>,
+    #[serde(default)]
     pub(crate) optimization: OptimizationLevel,
+    pub(crate) debug_info: Option<DebugInfoLevel>,
+    pub(crate) target_cpu: Option<String>,
+    pub(crate) tune_cpu: Option<String>,
     #[serde(default)]
     pub(crate) enable_all_warnings: bool,
     #[serde(default)]
     pub(crate) treat_all_warnings_as_errors: bool,
+    #[serde(default)]
+    pub(crate) strip: bool,
+    pub(crate) rpath: Option<bool>,
+    /// Compiles and links with `-pthread`.
+    #[serde(default)]
+    pub(crate) threads: bool,
+    /// Links the math library with `-lm`.
+    #[serde(default)]
+    pub(crate) math: bool,
+    /// Links libgcc and libstdc++ statically with `-static-libgcc -static-libstdc++`.
+    #[serde(default)]
+    pub(crate) static_runtime: bool,
+    /// Links executables with `-static`, which macOS doesn't support.
+    #[serde(default)]
+    pub(crate) fully_static: bool,
+    #[serde(default)]
+    pub(crate) lto: Lto,
+    #[serde(default)]
+    pub(crate) unity: bool,
+    pub(crate) unity_batch_size: Option<usize>,
+    #[serde(default)]
+    pub(crate) warnings: WarningsConfiguration,
+    #[serde(default)]
+    pub(crate) sanitizers: Vec<Sanitizer>,
+    /// Instruments compilation and linking with `--coverage` for gcov.
+    #[serde(default)]
+    pub(crate) coverage: bool,
+    /// Rejects additional compiler arguments that duplicate flags managed by bakery instead of
+    /// only warning about them.
+    #[serde(default)]
+    pub(crate) strict_arguments: bool,
+    /// Rejects source patterns that match no files instead of only warning about them.
+    #[serde(default)]
+    pub(crate) strict_sources: bool,
+    /// Lets sources and includes lie outside of the project's directory, such as code shared
+    /// with sibling projects.
+    #[serde(default)]
+    pub(crate) allow_external_paths: bool,
+    /// Follows symlinked sources instead of rejecting them.
+    #[serde(default)]
+    pub(crate) allow_symlinks: bool,
+    pub(crate) output_directory: Option<String>,
+    pub(crate) linker: Option<String>,
 }
 
-#[derive(Deserialize, Serialize)]
+#[derive(Clone, Default, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct WarningsConfiguration {
+    #[serde(default)]
+    pub(crate) enable: Vec<String>,
+    #[serde(default)]
+    pub(crate) disable: Vec<String>,
+    #[serde(default)]
+    pub(crate) errors: Vec<String>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Deserialize, Serialize)]
 pub(crate) enum Language {
     #[serde(alias = "c")]
     C,
@@ -31,29 +112,65 @@ pub(crate) enum Language {
     Cpp,
 }
 
-#[derive(Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[derive(Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub(crate) enum Distribution {
+    #[default]
     Executable,
     StaticLibrary,
     DynamicLibrary,
 }
 
-impl Default for Distribution {
-    fn default() -> Self {
-        Self::Executable
-    }
-}
-
 #[derive(Deserialize, Serialize)]
 #[serde(untagged)]
 pub(crate) enum Dependency {
-    Local { path: String },
-    System { name: String },
+    Local {
+        path: String,
+    },
+    PkgConfig {
+        #[serde(rename = "pkgConfig")]
+        name: String,
+    },
+    /// Another member of the workspace, by its name.
+    Member {
+        #[serde(rename = "member")]
+        name: String,
+    },
+    /// A project at the root of a git repository, checked out at a commit or tag.
+    Git {
+        #[serde(rename = "git")]
+        url: String,
+        rev: String,
+    },
+    /// Libraries shipped already built, along with their headers.
+    Prebuilt {
+        #[serde(rename = "includeDir")]
+        include_directory: String,
+        #[serde(rename = "libraryDir")]
+        library_directory: String,
+        libraries: Vec<String>,
+    },
+    System {
+        name: String,
+        kind: Option<LinkKind>,
+    },
 }
 
-#[derive(Clone, Deserialize, Serialize)]
+/// How a system library is linked. Linking one statically only affects that library, the
+/// ones following it are linked dynamically again. A static library is only searched for the
+/// symbols still undefined where it appears, so it has to come after the dependencies using it.
+#[derive(Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) enum LinkKind {
+    Static,
+    /// Lets the linker pick the shared library if there is one.
+    #[default]
+    Dynamic,
+}
+
+#[derive(Clone, Default, Deserialize, Serialize)]
 pub(crate) enum OptimizationLevel {
+    #[default]
     #[serde(rename = "0")]
     Zero,
     #[serde(rename = "1")]
@@ -62,16 +179,108 @@ pub(crate) enum OptimizationLevel {
     Two,
     #[serde(rename = "3")]
     Three,
-    #[serde(rename = "4")]
-    Four,
-    #[serde(alias = "size")]
+    /// `-Ofast`, which disregards strict standards compliance.
+    #[serde(rename = "4", alias = "fast")]
+    Fast,
+    #[serde(alias = "size", alias = "s")]
     Size,
-    #[serde(alias = "debug")]
+    /// `-Oz`, which optimizes for size even at the expense of speed.
+    #[serde(rename = "z")]
+    SizeAggressive,
+    /// `-Og`, which only enables optimizations that don't get in the way of debugging. Debug
+    /// info is still configured separately.
+    #[serde(alias = "debug", alias = "g")]
     Debug,
 }
 
-impl Default for OptimizationLevel {
-    fn default() -> Self {
-        Self::Zero
+impl OptimizationLevel {
+    /// The part of the flag following `-O`.
+    pub(crate) fn name(&self) -> &'static str {
+        match self {
+            OptimizationLevel::Zero => "0",
+            OptimizationLevel::One => "1",
+            OptimizationLevel::Two => "2",
+            OptimizationLevel::Three => "3",
+            OptimizationLevel::Fast => "fast",
+            OptimizationLevel::Size => "s",
+            OptimizationLevel::SizeAggressive => "z",
+            OptimizationLevel::Debug => "g",
+        }
+    }
+}
+
+#[derive(Clone, Deserialize, Serialize)]
+pub(crate) enum DebugInfoLevel {
+    #[serde(rename = "0")]
+    Zero,
+    #[serde(rename = "1")]
+    One,
+    #[serde(rename = "2")]
+    Two,
+    #[serde(rename = "3")]
+    Three,
+}
+
+#[derive(Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(from = "LtoValue", rename_all = "camelCase")]
+pub(crate) enum Lto {
+    #[default]
+    Off,
+    Thin,
+    Full,
+}
+
+/// Accepts `lto = false` as well as `lto = "thin"` or `lto = "full"`.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum LtoValue {
+    Enabled(bool),
+    Mode(LtoMode),
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+enum LtoMode {
+    Thin,
+    Full,
+}
+
+impl From<LtoValue> for Lto {
+    fn from(value: LtoValue) -> Lto {
+        match value {
+            LtoValue::Enabled(false) => Lto::Off,
+            LtoValue::Enabled(true) | LtoValue::Mode(LtoMode::Full) => Lto::Full,
+            LtoValue::Mode(LtoMode::Thin) => Lto::Thin,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) enum Sanitizer {
+    Address,
+    Undefined,
+    Thread,
+    Leak,
+}
+
+impl Sanitizer {
+    pub(crate) fn name(&self) -> &'static str {
+        match self {
+            Sanitizer::Address => "address",
+            Sanitizer::Undefined => "undefined",
+            Sanitizer::Thread => "thread",
+            Sanitizer::Leak => "leak",
+        }
+    }
+
+    pub(crate) fn conflicts_with(&self, other: &Sanitizer) -> bool {
+        matches!(
+            (self, other),
+            (Sanitizer::Thread, Sanitizer::Address)
+                | (Sanitizer::Address, Sanitizer::Thread)
+                | (Sanitizer::Thread, Sanitizer::Leak)
+                | (Sanitizer::Leak, Sanitizer::Thread)
+        )
     }
 }
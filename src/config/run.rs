@@ -0,0 +1,10 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+#[derive(Default, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct RunConfiguration {
+    /// Environment variables set for the executable on top of the inherited ones.
+    #[serde(default)]
+    pub(crate) env: BTreeMap<String, String>,
+}
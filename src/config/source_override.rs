@@ -0,0 +1,15 @@
+use crate::config::{CConfiguration, CppConfiguration, OptimizationLevel};
+use serde::{Deserialize, Serialize};
+
+#[derive(Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct SourceOverrideConfiguration {
+    pub(crate) sources: String,
+    pub(crate) c: Option<CConfiguration>,
+    pub(crate) cpp: Option<CppConfiguration>,
+    pub(crate) optimization: Option<OptimizationLevel>,
+    #[serde(default)]
+    pub(crate) additional_pre_arguments: Vec<String>,
+    #[serde(default)]
+    pub(crate) additional_post_arguments: Vec<String>,
+}
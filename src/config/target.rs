@@ -0,0 +1,52 @@
+use crate::config::Dependency;
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, env};
+
+/// Overrides the operating system `[target.'cfg(...)']` tables are matched against, for cross
+/// compilation.
+const TARGET_OS_VARIABLE: &str = "BAKERY_TARGET_OS";
+
+const WINDOWS: &str = "windows";
+const UNIX: &str = "unix";
+pub(crate) const MACOS: &str = "macos";
+
+#[derive(Default, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct TargetConfiguration {
+    #[serde(default)]
+    pub(crate) sources: Vec<String>,
+    #[serde(default)]
+    pub(crate) includes: Vec<String>,
+    #[serde(default)]
+    pub(crate) dependencies: Vec<Dependency>,
+    #[serde(default)]
+    pub(crate) defines: BTreeMap<String, String>,
+    #[serde(default)]
+    pub(crate) frameworks: Vec<String>,
+}
+
+pub(crate) fn target_os() -> String {
+    env::var(TARGET_OS_VARIABLE)
+        .ok()
+        .filter(|target_os| !target_os.is_empty())
+        .unwrap_or_else(|| String::from(env::consts::OS))
+}
+
+/// Evaluates `cfg(<name>)`, where the name is an operating system like `linux` or one of the
+/// `unix`/`windows` families, returning `None` for anything else.
+pub(crate) fn matches_target(predicate: &str, target_os: &str) -> Option<bool> {
+    let name = predicate
+        .trim()
+        .strip_prefix("cfg(")?
+        .strip_suffix(')')?
+        .trim();
+
+    if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+        return None;
+    }
+
+    Some(match name {
+        UNIX => target_os != WINDOWS,
+        name => name == target_os,
+    })
+}
@@ -0,0 +1,13 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+#[derive(Default, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct TestConfiguration {
+    #[serde(default)]
+    pub(crate) sources: Vec<String>,
+    /// Arguments passed to a test executable, keyed by the test's name (its source's path without
+    /// the extension, e.g. `tests/parser`).
+    #[serde(default)]
+    pub(crate) arguments: BTreeMap<String, Vec<String>>,
+}
@@ -1,8 +1,116 @@
+use crate::tools;
 use serde::{Deserialize, Serialize};
+use std::path::Path;
 
-#[derive(Default, Deserialize, Serialize)]
+pub(crate) const SUPPORTED_LINKERS: [&str; 4] = ["bfd", "gold", "lld", "mold"];
+
+/// A linker is either one of the well-known ones selected via `-fuse-ld`, or an absolute path to
+/// a directory containing `ld` passed via `-B`.
+pub(crate) fn is_valid_linker(linker: &str) -> bool {
+    SUPPORTED_LINKERS.contains(&linker) || Path::new(linker).is_absolute()
+}
+
+#[derive(Clone, Default, Deserialize, Serialize)]
 pub(crate) struct ToolchainConfiguration {
     pub(crate) gcc_location: Option<String>,
     pub(crate) gpp_location: Option<String>,
     pub(crate) ar_location: Option<String>,
+    pub(crate) compiler_launcher: Option<String>,
+    pub(crate) strip_location: Option<String>,
+    pub(crate) linker: Option<String>,
+    pub(crate) clang_format_location: Option<String>,
+    pub(crate) clang_tidy_location: Option<String>,
+    /// A directory of objects shared between builds, such as those of several worktrees, which
+    /// reuse each other's objects instead of compiling identical sources again.
+    pub(crate) object_cache_location: Option<String>,
+    /// Set by `--frozen`, which only allows tools with an explicit location.
+    #[serde(skip)]
+    pub(crate) frozen: bool,
+}
+
+impl ToolchainConfiguration {
+    /// Looks a tool up on PATH, which `--frozen` forbids.
+    pub(crate) fn locate(&self, name: &str) -> Option<String> {
+        if self.frozen {
+            return None;
+        }
+
+        tools::locate(name)
+    }
+
+    /// Explains why a tool is missing, naming where to set its location under `--frozen`.
+    pub(crate) fn tool_not_found(&self, tool: &str, setting: &str) -> String {
+        if self.frozen {
+            format!(
+                "{} has no explicit location, which --frozen requires, set {}",
+                tool, setting
+            )
+        } else {
+            format!("{} not found", tool)
+        }
+    }
+
+    /// The tools a project's `[toolchain]` section points at take the place of the global ones,
+    /// environment variables still take precedence over both.
+    pub(crate) fn with_project_toolchain(
+        &self,
+        toolchain: &ProjectToolchainConfiguration,
+    ) -> ToolchainConfiguration {
+        ToolchainConfiguration {
+            gcc_location: toolchain
+                .gcc_location
+                .clone()
+                .or_else(|| self.gcc_location.clone()),
+            gpp_location: toolchain
+                .gpp_location
+                .clone()
+                .or_else(|| self.gpp_location.clone()),
+            ar_location: toolchain
+                .ar_location
+                .clone()
+                .or_else(|| self.ar_location.clone()),
+            ..self.clone()
+        }
+    }
+}
+
+/// The `[toolchain]` section of a build configuration.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct ProjectToolchainConfiguration {
+    pub(crate) gcc_location: Option<String>,
+    pub(crate) gpp_location: Option<String>,
+    pub(crate) ar_location: Option<String>,
+    /// Whether the dependencies without a toolchain of their own use this one as well.
+    #[serde(default)]
+    pub(crate) apply_to_dependencies: bool,
+}
+
+impl ProjectToolchainConfiguration {
+    /// Layers this toolchain over the one inherited from the project that depends on it.
+    pub(crate) fn inherit(
+        &self,
+        inherited: Option<&ProjectToolchainConfiguration>,
+    ) -> ProjectToolchainConfiguration {
+        let Some(inherited) = inherited else {
+            return self.clone();
+        };
+
+        ProjectToolchainConfiguration {
+            gcc_location: self
+                .gcc_location
+                .clone()
+                .or_else(|| inherited.gcc_location.clone()),
+            gpp_location: self
+                .gpp_location
+                .clone()
+                .or_else(|| inherited.gpp_location.clone()),
+            ar_location: self
+                .ar_location
+                .clone()
+                .or_else(|| inherited.ar_location.clone()),
+            // Whatever a project hands down reaches its whole subtree
+            apply_to_dependencies: true,
+        }
+    }
 }
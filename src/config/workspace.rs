@@ -0,0 +1,14 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct WorkspaceBuildConfiguration {
+    pub(crate) workspace: WorkspaceConfiguration,
+}
+
+#[derive(Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct WorkspaceConfiguration {
+    /// Directories of the member projects, relative to the workspace.
+    pub(crate) members: Vec<String>,
+}
@@ -1,4 +1,4 @@
-use std::path::{Path, PathBuf};
+use std::path::{Component, Path, PathBuf};
 
 pub trait PathExtension {
     fn relative_to(&self, other: &Self) -> Option<PathBuf>;
@@ -18,15 +18,70 @@ impl PathExtension for Path {
             other.to_path_buf()
         };
 
-        // TODO: Support cases where from isn't to + something
-        if !from.starts_with(&to) {
+        let from_components = from.components().collect::<Vec<_>>();
+        let to_components = to.components().collect::<Vec<_>>();
+
+        let common_components = from_components
+            .iter()
+            .zip(&to_components)
+            .take_while(|(from_component, to_component)| from_component == to_component)
+            .count();
+
+        // Paths on different roots (e.g. different drives on Windows) have no relative path
+        if common_components == 0 {
             return None;
         }
 
         Some(
-            from.components()
-                .skip(to.components().count())
+            to_components[common_components..]
+                .iter()
+                .map(|_| Component::ParentDir)
+                .chain(from_components[common_components..].iter().copied())
                 .collect::<PathBuf>(),
         )
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_relative_to_descendant() {
+        let root = tempfile::tempdir().unwrap();
+        let source = root.path().join("src/main.c");
+
+        assert_eq!(
+            source.relative_to(root.path()),
+            Some(PathBuf::from("src/main.c"))
+        );
+    }
+
+    #[test]
+    fn test_relative_to_sibling_directory() {
+        let root = tempfile::tempdir().unwrap();
+        let source = root.path().join("shared/foo.cpp");
+        let project = root.path().join("project");
+
+        assert_eq!(
+            source.relative_to(&project),
+            Some(PathBuf::from("../shared/foo.cpp"))
+        );
+    }
+
+    #[test]
+    fn test_relative_to_ancestor_traversal() {
+        let root = tempfile::tempdir().unwrap();
+        let source = root.path().join("a/foo.cpp");
+        let project = root.path().join("b/c/d");
+
+        assert_eq!(
+            source.relative_to(&project),
+            Some(PathBuf::from("../../../a/foo.cpp"))
+        );
+        assert_eq!(
+            root.path().relative_to(&project),
+            Some(PathBuf::from("../../.."))
+        );
+    }
+}
@@ -0,0 +1,141 @@
+use crate::{Dependency, PathExtension, Project};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet},
+    fs,
+    path::Path,
+    sync::Arc,
+};
+
+pub(crate) const LOCKFILE_EXTENSION: &str = "lock";
+
+const LOCKFILE_VERSION: u32 = 1;
+
+const LOCKFILE_HEADER: &str = "# Generated by bakery, do not edit it by hand.\n\n";
+
+/// What `pkg-config` reported for a package, reused instead of querying it again while the
+/// lockfile is up to date.
+#[derive(Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct PkgConfigPackage {
+    pub(crate) includes: Vec<String>,
+    pub(crate) compile_flags: Vec<String>,
+    pub(crate) link_flags: Vec<String>,
+}
+
+#[derive(PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct LockedProject {
+    name: String,
+    /// Relative to the root project, so that the lockfile can be committed.
+    path: String,
+    /// Hash of the project's build configuration.
+    hash: String,
+}
+
+/// The resolved dependency graph, written next to the build configuration as `bakery.lock`.
+#[derive(PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct Lockfile {
+    version: u32,
+    #[serde(default, skip_serializing_if = "BTreeSet::is_empty")]
+    system: BTreeSet<String>,
+    #[serde(default, rename = "project")]
+    projects: Vec<LockedProject>,
+    #[serde(
+        default,
+        rename = "pkgConfig",
+        skip_serializing_if = "BTreeMap::is_empty"
+    )]
+    pkg_config: BTreeMap<String, PkgConfigPackage>,
+}
+
+impl Lockfile {
+    pub(crate) fn new(project: &Project) -> Lockfile {
+        let mut lockfile = Lockfile {
+            version: LOCKFILE_VERSION,
+            system: BTreeSet::new(),
+            projects: Vec::new(),
+            pkg_config: BTreeMap::new(),
+        };
+
+        lockfile.collect(project, &project.base_path, &mut HashSet::new());
+
+        lockfile
+            .projects
+            .sort_by(|first, second| first.path.cmp(&second.path));
+
+        lockfile
+    }
+
+    fn collect(&mut self, project: &Project, root_path: &Path, visited: &mut HashSet<String>) {
+        let path = project
+            .base_path
+            .relative_to(root_path)
+            .filter(|path| !path.as_os_str().is_empty())
+            .unwrap_or_else(|| project.base_path.clone())
+            .to_string_lossy()
+            .replace('\\', "/");
+
+        if !visited.insert(path.clone()) {
+            return;
+        }
+
+        self.projects.push(LockedProject {
+            name: project.name.clone(),
+            path,
+            hash: fs::read(&project.manifest)
+                .map(|content| blake3::hash(&content).to_hex().to_string())
+                .unwrap_or_default(),
+        });
+
+        for dependency in &project.dependencies {
+            match dependency {
+                Dependency::System { name, .. } => {
+                    self.system.insert(name.clone());
+                }
+                Dependency::PkgConfig {
+                    name,
+                    includes,
+                    compile_flags,
+                    link_flags,
+                } => {
+                    self.pkg_config.insert(
+                        name.clone(),
+                        PkgConfigPackage {
+                            includes: includes.clone(),
+                            compile_flags: compile_flags.clone(),
+                            link_flags: link_flags.clone(),
+                        },
+                    );
+                }
+                Dependency::Project(subproject) => {
+                    self.collect(Arc::as_ref(subproject), root_path, visited)
+                }
+                // Nothing about them is resolved, the build configuration already says it all
+                Dependency::Prebuilt { .. } => {}
+            }
+        }
+    }
+
+    /// Returns `None` when the lockfile is missing, unreadable or from another version of bakery.
+    pub(crate) fn read(path: &Path) -> Option<Lockfile> {
+        fs::read_to_string(path)
+            .ok()
+            .and_then(|content| toml::from_str::<Lockfile>(&content).ok())
+            .filter(|lockfile| lockfile.version == LOCKFILE_VERSION)
+    }
+
+    pub(crate) fn write(&self, path: &Path) -> Result<(), String> {
+        let content = toml::to_string_pretty(self).map_err(|err| err.to_string())?;
+
+        fs::write(path, format!("{}{}", LOCKFILE_HEADER, content)).map_err(|err| err.to_string())
+    }
+
+    pub(crate) fn pkg_config_packages(&self) -> HashMap<String, PkgConfigPackage> {
+        self.pkg_config
+            .iter()
+            .map(|(name, package)| (name.clone(), package.clone()))
+            .collect()
+    }
+}
@@ -0,0 +1,204 @@
+use indicatif::MultiProgress;
+use lazy_static::lazy_static;
+use regex::Regex;
+use std::{
+    fs::File,
+    io::{self, Write},
+    path::Path,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Mutex,
+    },
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+lazy_static! {
+    static ref LOG_FILE: Mutex<Option<File>> = Mutex::new(None);
+    static ref PROGRESS: Mutex<Option<MultiProgress>> = Mutex::new(None);
+    static ref COLOR_CODE_REGEX: Regex = Regex::new("\x1b\\[[0-9;]*[A-Za-z]").unwrap();
+}
+
+/// ANSI colors projects are told apart by, leaving out red, which errors stand out with.
+const PROJECT_COLORS: [u8; 10] = [36, 35, 33, 32, 34, 96, 95, 93, 92, 94];
+
+static INFO_ON_STDERR: AtomicBool = AtomicBool::new(false);
+static PROJECT_PREFIXES: AtomicBool = AtomicBool::new(false);
+static COLORED_PROJECT_PREFIXES: AtomicBool = AtomicBool::new(false);
+
+pub(crate) fn set_info_on_stderr(info_on_stderr: bool) {
+    INFO_ON_STDERR.store(info_on_stderr, Ordering::Relaxed);
+}
+
+fn is_info_on_stderr() -> bool {
+    INFO_ON_STDERR.load(Ordering::Relaxed)
+}
+
+/// Runs involving several projects prefix their lines with the project they belong to, as the
+/// projects are built in parallel. Decided once per run, so that all of its lines look alike.
+pub(crate) fn set_project_prefixes(enabled: bool, colored: bool) {
+    PROJECT_PREFIXES.store(enabled, Ordering::Relaxed);
+    COLORED_PROJECT_PREFIXES.store(colored, Ordering::Relaxed);
+}
+
+pub(crate) fn with_project_prefix(project: &str, message: &str) -> String {
+    if !PROJECT_PREFIXES.load(Ordering::Relaxed) {
+        return message.to_owned();
+    }
+
+    prefix_lines(
+        project,
+        message,
+        COLORED_PROJECT_PREFIXES.load(Ordering::Relaxed),
+    )
+}
+
+fn prefix_lines(project: &str, message: &str, colored: bool) -> String {
+    let prefix = if colored {
+        // The color only has to stay the same for a project across lines and runs
+        let hash = project.bytes().fold(0usize, |hash, byte| {
+            hash.wrapping_mul(31).wrapping_add(byte as usize)
+        });
+
+        format!(
+            "\x1b[{}m[{}]\x1b[0m",
+            PROJECT_COLORS[hash % PROJECT_COLORS.len()],
+            project
+        )
+    } else {
+        format!("[{}]", project)
+    };
+
+    message
+        .lines()
+        .map(|line| format!("{} {}", prefix, line))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// While progress bars are shown, logged lines are printed above them instead of through them.
+pub(crate) fn set_progress(progress: Option<MultiProgress>) {
+    *PROGRESS.lock().unwrap() = progress;
+}
+
+pub(crate) fn print_info(message: &str) {
+    match PROGRESS.lock().unwrap().as_ref() {
+        Some(progress) => progress.suspend(|| print_info_line(message)),
+        None => print_info_line(message),
+    }
+}
+
+pub(crate) fn print_error(message: &str) {
+    match PROGRESS.lock().unwrap().as_ref() {
+        Some(progress) => progress.suspend(|| eprintln!("{}", message)),
+        None => eprintln!("{}", message),
+    }
+}
+
+fn print_info_line(message: &str) {
+    if is_info_on_stderr() {
+        eprintln!("{}", message);
+    } else {
+        println!("{}", message);
+    }
+}
+
+pub(crate) fn strip_color_codes(text: &str) -> String {
+    COLOR_CODE_REGEX.replace_all(text, "").into_owned()
+}
+
+/// Starts teeing logged lines into the file, truncating whatever the previous run left there.
+pub(crate) fn open_log_file(path: &Path) -> Result<(), io::Error> {
+    let file = File::create(path)?;
+
+    *LOG_FILE.lock().unwrap() = Some(file);
+
+    Ok(())
+}
+
+pub(crate) fn write_to_log_file(message: &str) {
+    let mut log_file = LOG_FILE.lock().unwrap();
+
+    if let Some(file) = log_file.as_mut() {
+        let timestamp = timestamp();
+
+        for line in strip_color_codes(message).lines() {
+            // Losing a line of the log is not worth failing the build over
+            let _ = writeln!(file, "[{}] {}", timestamp, line);
+        }
+    }
+}
+
+/// Formats the current UTC time of day as `hh:mm:ss.mmm`.
+fn timestamp() -> String {
+    let since_epoch = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default();
+    let seconds = since_epoch.as_secs() % 86400;
+
+    format!(
+        "{:02}:{:02}:{:02}.{:03}",
+        seconds / 3600,
+        seconds / 60 % 60,
+        seconds % 60,
+        since_epoch.subsec_millis()
+    )
+}
+
+#[macro_export]
+macro_rules! log_info {
+    ($($argument:tt)*) => {{
+        let message = format!($($argument)*);
+
+        $crate::log::print_info(&message);
+        $crate::log::write_to_log_file(&message);
+    }};
+}
+
+/// Logs a line of a project's build, prefixed with the project's name when several are built.
+#[macro_export]
+macro_rules! log_project_info {
+    ($project:expr, $($argument:tt)*) => {{
+        let message = $crate::log::with_project_prefix($project, &format!($($argument)*));
+
+        $crate::log::print_info(&message);
+        $crate::log::write_to_log_file(&message);
+    }};
+}
+
+#[macro_export]
+macro_rules! log_error {
+    ($($argument:tt)*) => {{
+        let message = format!($($argument)*);
+
+        $crate::log::print_error(&message);
+        $crate::log::write_to_log_file(&message);
+    }};
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_prefix_lines_prefixes_every_line() {
+        let message = "Compiling a.c\nCompiled a.c";
+
+        assert_eq!(
+            prefix_lines("lib", message, false),
+            "[lib] Compiling a.c\n[lib] Compiled a.c"
+        );
+
+        let colored = prefix_lines("lib", message, true);
+        let prefixes = colored
+            .lines()
+            .map(|line| line.split_once(' ').unwrap().0)
+            .collect::<Vec<_>>();
+
+        assert!(prefixes[0].starts_with("\x1b[") && prefixes[0].ends_with("[lib]\x1b[0m"));
+        assert_eq!(prefixes[0], prefixes[1]);
+        assert_eq!(
+            strip_color_codes(&colored),
+            "[lib] Compiling a.c\n[lib] Compiled a.c"
+        );
+    }
+}
@@ -1,14 +1,22 @@
 mod config;
 mod extensions;
+mod lockfile;
+mod log;
+mod object_cache;
 mod project;
 mod task;
 mod tools;
+mod workspace;
 
 pub(crate) use extensions::*;
+pub(crate) use lockfile::*;
+pub(crate) use object_cache::*;
 pub(crate) use project::*;
 pub(crate) use task::*;
+pub(crate) use workspace::*;
 
-use clap::Command;
+use clap::{builder::RangedU64ValueParser, value_parser, Arg, ArgAction, ArgMatches, Command};
+use clap_complete::Shell;
 use config::ToolchainConfiguration;
 use eyre::Context;
 use itertools::Itertools;
@@ -16,82 +24,932 @@ use std::{
     collections::{HashMap, VecDeque},
     env,
     fs::{self, File},
-    io::Write,
-    path,
+    io::{self, IsTerminal, Write},
+    path::{self, PathBuf},
+    process,
+    sync::Arc,
 };
 
 pub const BUILD_CONFIGURATION_FILE: &str = "bakery.toml";
-pub const BAKERY_BUILD_DIRECTORY: &str = ".bakery/build";
-pub const BAKERY_CACHE_DIRECTORY: &str = ".bakery/cache";
-pub const BAKERY_HASHES_FILE: &str = ".bakery/cache/hashes.json";
+pub const WORKSPACE_CONFIGURATION_FILE: &str = "bakery-workspace.toml";
+pub const BAKERY_DIRECTORY: &str = ".bakery";
+pub const BAKERY_BUILD_DIRECTORY: &str = "build";
+pub const BAKERY_CACHE_DIRECTORY: &str = "cache";
+pub const BAKERY_HASHES_FILE: &str = "hashes.json";
+pub const BAKERY_FILE_HASHES_FILE: &str = "files.json";
+pub const BAKERY_BUILD_LOG_FILE: &str = "build.log";
+pub const BAKERY_SARIF_FILE: &str = "diagnostics.sarif";
 
 fn main() -> eyre::Result<()> {
-    let toolchain_configuration = deserialize_toolchain_configuration()
+    let mut toolchain_configuration = deserialize_toolchain_configuration()
         .context("Failed to deserialize toolchain configuration")?;
 
     let mut tasks: HashMap<&str, Box<dyn Task>> = HashMap::new();
 
-    tasks.insert("build", Box::new(Build::new()));
-    tasks.insert("run", Box::new(Run::new()));
+    for task in [
+        Box::new(Build::new()) as Box<dyn Task>,
+        Box::new(Run::new()),
+        Box::new(Fmt::new()),
+        Box::new(Tidy::new()),
+        Box::new(Test::new()),
+        Box::new(Graph::new()),
+        Box::new(Install::new()),
+        Box::new(Package::new()),
+        Box::new(Watch::new()),
+        Box::new(Asm::new()),
+        Box::new(Preprocess::new()),
+    ] {
+        tasks.insert(task.id(), task);
+    }
 
-    let matches = Command::new("bakery")
-        .version("0.1")
-        .author("Bakamono")
-        .about("Build system for C/C++")
-        .subcommand(Command::new("build"))
-        .subcommand(Command::new("run"))
-        .get_matches();
-
-    match Project::open(".") {
-        Ok(project) => {
-            if let Some((subcommand, _parameters)) = matches.subcommand() {
-                if tasks.contains_key(subcommand) {
-                    let context = TaskContext {
-                        project,
-                        toolchain_configuration,
-                    };
-
-                    execute_task_and_its_dependencies(&mut tasks, subcommand, &context);
-                }
+    let matches = build_command().get_matches();
+
+    // Everything, including relative dependency and include paths, resolves against the
+    // current directory, so moving there is all it takes to act on another project
+    if let Some(directory) = matches.get_one::<String>("directory") {
+        env::set_current_dir(directory)
+            .with_context(|| format!("Failed to change directory to {}", directory))?;
+    }
+
+    let manifest = matches.get_one::<String>("manifest").map(PathBuf::from);
+    let member = matches.get_one::<String>("member").map(String::as_str);
+    let locked = is_flag_set(&matches, "locked");
+
+    toolchain_configuration.frozen = is_flag_set(&matches, "frozen");
+
+    let Some((subcommand, parameters)) = matches.subcommand() else {
+        return Ok(());
+    };
+
+    if subcommand == "completions" {
+        if let Some(shell) = parameters.get_one::<Shell>("shell") {
+            clap_complete::generate(*shell, &mut build_command(), "bakery", &mut io::stdout());
+        }
+
+        return Ok(());
+    }
+
+    // Runs without opening the project, as reporting why it can't be opened is part of its job
+    if subcommand == "doctor" {
+        if !Doctor::new().diagnose(&toolchain_configuration, manifest.as_deref()) {
+            process::exit(1);
+        }
+
+        return Ok(());
+    }
+
+    if subcommand == "watch" {
+        let task_id = parameters
+            .get_one::<String>("task")
+            .map(String::as_str)
+            .unwrap_or("build");
+
+        // Ctrl-C terminates the process between builds, there is no state to flush
+        loop {
+            if open_project_and_execute_tasks(
+                &mut tasks,
+                &[task_id, "watch"],
+                parameters,
+                &toolchain_configuration,
+                manifest.as_deref(),
+                member,
+                locked,
+            )
+            .is_none()
+            {
+                Watch::new().wait_for_build_configuration_change(manifest.as_deref().unwrap_or(
+                    if is_workspace(manifest.as_deref()) {
+                        path::Path::new(WORKSPACE_CONFIGURATION_FILE)
+                    } else {
+                        path::Path::new(BUILD_CONFIGURATION_FILE)
+                    },
+                ));
             }
         }
-        Err(error) => match error {
-            ProjectOpenError::InvalidProjectPath(_error) => {
-                eprintln!("There is no bakery.toml in the current directory")
+    }
+
+    let exit_code = open_project_and_execute_tasks(
+        &mut tasks,
+        &[subcommand],
+        parameters,
+        &toolchain_configuration,
+        manifest.as_deref(),
+        member,
+        locked,
+    );
+
+    if exit_code != Some(0) {
+        process::exit(exit_code.unwrap_or(1));
+    }
+
+    Ok(())
+}
+
+/// Returns the code bakery exits with, 0 when every task succeeded, or `None` if the project
+/// couldn't be opened. A failed `run` passes on the exit code of the executable.
+fn open_project_and_execute_tasks(
+    tasks: &mut HashMap<&str, Box<dyn Task>>,
+    task_ids: &[&str],
+    parameters: &ArgMatches,
+    toolchain_configuration: &ToolchainConfiguration,
+    manifest: Option<&path::Path>,
+    member: Option<&str>,
+    locked: bool,
+) -> Option<i32> {
+    if !task_ids.iter().all(|task_id| tasks.contains_key(task_id)) {
+        return Some(0);
+    }
+
+    let output_directory = parameters
+        .try_get_one::<String>("target-dir")
+        .ok()
+        .flatten()
+        .map(path::Path::new);
+    let mut binary = parameters
+        .try_get_one::<String>("binary")
+        .ok()
+        .flatten()
+        .cloned();
+
+    let mut workspace = None;
+
+    let projects = if is_workspace(manifest) {
+        let opened_workspace = match Workspace::open(parse_profile(parameters), output_directory) {
+            Ok(workspace) => workspace,
+            Err(error) => {
+                log_workspace_open_error(error);
+
+                return None;
             }
-            ProjectOpenError::InvalidBuildConfiguration(build_configuration_error) => {
-                match build_configuration_error {
-                    BuildConfigurationError::SyntaxError(error) => {
-                        eprintln!("Syntax error occured:");
-
-                        for line in error.split(path::is_separator) {
-                            eprintln!("{line}");
-                        }
+        };
+
+        // Inside a workspace `run <name>` picks the member, which has to run its only binary
+        let member = member
+            .map(String::from)
+            .or_else(|| task_ids.contains(&"run").then(|| binary.take()).flatten());
+
+        let members = select_members(&opened_workspace, member.as_deref(), task_ids)?;
+
+        workspace = Some(opened_workspace);
+
+        members
+    } else {
+        match Project::open_with_lockfile(
+            manifest,
+            parse_profile(parameters),
+            output_directory,
+            locked,
+        ) {
+            Ok(project) => vec![Arc::new(project)],
+            Err(error) => {
+                log_project_open_error(error);
+
+                return None;
+            }
+        }
+    };
+
+    // Several projects are built in parallel when any of them has project dependencies
+    log::set_project_prefixes(
+        projects
+            .iter()
+            .any(|project| has_project_dependencies(project)),
+        is_color_enabled(parameters),
+    );
+
+    let mut exit_code = 0;
+    let mut failed_projects: Vec<String> = Vec::new();
+
+    for (index, project) in projects.into_iter().enumerate() {
+        if let Some(failed_project) = failed_projects
+            .iter()
+            .find(|failed_project| Workspace::depends_on(&project, failed_project))
+        {
+            log_error!("Skipping {} as {} failed", project.name, failed_project);
+
+            continue;
+        }
+
+        // Hashes are read when a project is opened, so without reopening the member a
+        // dependency shared with an earlier member would be compiled again
+        let project = match &workspace {
+            Some(workspace) if index > 0 && has_project_dependencies(&project) => {
+                match workspace.reopen_member(&project.name, parse_profile(parameters)) {
+                    Ok(project) => project,
+                    Err(error) => {
+                        log_workspace_open_error(error);
+
+                        return Some(1);
                     }
-                    BuildConfigurationError::InvalidName => eprintln!("Project's name consists of invalid characters. Valid characters are: {NAME_PATTERN}"),
-                    BuildConfigurationError::IncorrectWildcard(wildcard) => eprintln!("Incorrect wildcard: {wildcard}"),
-                    BuildConfigurationError::IncorrectSource(source) => {
-                        eprintln!("Incorrect source: {}", source);
+                }
+            }
+            _ => project,
+        };
+
+        let context = create_task_context(
+            Arc::clone(&project),
+            parameters,
+            toolchain_configuration,
+            binary.clone(),
+        );
+        let mut project_succeeded = true;
+
+        // Watch still has to run after a failed build, so only a task's own chain stops
+        for task_id in task_ids {
+            if let Err(err) = execute_task_and_its_dependencies(tasks, task_id, &context) {
+                log_error!("{}", err);
+
+                exit_code = err
+                    .downcast_ref::<RunFailed>()
+                    .map_or(1, |run_failed| run_failed.code);
+                project_succeeded = false;
+            }
+        }
+
+        if !project_succeeded {
+            failed_projects.push(project.name.clone());
+
+            // Members that don't depend on the failed one are still built with --keep-going
+            if !is_flag_set(parameters, "keep-going") {
+                break;
+            }
+        }
+    }
+
+    Some(exit_code)
+}
+
+fn has_project_dependencies(project: &Project) -> bool {
+    project
+        .dependencies
+        .iter()
+        .any(|dependency| matches!(dependency, Dependency::Project(_)))
+}
+
+/// Whether bakery acts on the workspace in the current directory rather than a single project.
+fn is_workspace(manifest: Option<&path::Path>) -> bool {
+    manifest.is_none() && path::Path::new(WORKSPACE_CONFIGURATION_FILE).is_file()
+}
+
+/// Narrows the workspace down to the selected member, or the only executable one for `run`.
+fn select_members(
+    workspace: &Workspace,
+    member: Option<&str>,
+    task_ids: &[&str],
+) -> Option<Vec<Arc<Project>>> {
+    let names = || {
+        workspace
+            .members
+            .iter()
+            .map(|member| member.name.as_str())
+            .join(", ")
+    };
+
+    if let Some(member) = member {
+        return match workspace.member(member) {
+            Some(project) => Some(vec![Arc::clone(project)]),
+            None => {
+                eprintln!(
+                    "There is no member named {} in the workspace, members are: {}",
+                    member,
+                    names()
+                );
+
+                None
+            }
+        };
+    }
+
+    if task_ids.contains(&"watch") && workspace.members.len() > 1 {
+        eprintln!(
+            "Pick the member to watch with --member, members are: {}",
+            names()
+        );
+
+        return None;
+    }
+
+    if task_ids.contains(&"run") {
+        let executables = workspace
+            .members
+            .iter()
+            .filter(|member| member.distribution == config::Distribution::Executable)
+            .collect::<Vec<_>>();
+
+        return match executables.as_slice() {
+            [executable] => Some(vec![Arc::clone(executable)]),
+            _ => {
+                eprintln!(
+                    "Pick the member to run with bakery run <member>, executables are: {}",
+                    executables
+                        .iter()
+                        .map(|member| member.name.as_str())
+                        .join(", ")
+                );
+
+                None
+            }
+        };
+    }
+
+    Some(workspace.members.clone())
+}
+
+/// Colors are only used on a terminal, unless turned off with --no-color or `NO_COLOR`.
+fn is_color_enabled(parameters: &ArgMatches) -> bool {
+    !is_flag_set(parameters, "no-color")
+        && env::var_os("NO_COLOR").is_none_or(|no_color| no_color.is_empty())
+        && io::stdout().is_terminal()
+}
+
+fn create_task_context(
+    project: Arc<Project>,
+    parameters: &ArgMatches,
+    toolchain_configuration: &ToolchainConfiguration,
+    binary: Option<String>,
+) -> TaskContext {
+    let run_arguments = parameters
+        .try_get_many::<String>("arguments")
+        .ok()
+        .flatten()
+        .map(|arguments| arguments.cloned().collect::<Vec<_>>())
+        .unwrap_or_default();
+
+    TaskContext {
+        project,
+        toolchain_configuration: toolchain_configuration.clone(),
+        run_arguments,
+        binary,
+        run_environment: parameters
+            .try_get_many::<(String, String)>("env")
+            .ok()
+            .flatten()
+            .map(|variables| variables.cloned().collect::<Vec<_>>())
+            .unwrap_or_default(),
+        clear_environment: is_flag_set(parameters, "env-clear"),
+        jobs: parameters
+            .try_get_one::<usize>("jobs")
+            .ok()
+            .flatten()
+            .copied(),
+        timings: is_flag_set(parameters, "timings"),
+        verbose: is_flag_set(parameters, "verbose"),
+        keep_going: is_flag_set(parameters, "keep-going"),
+        force: is_flag_set(parameters, "force"),
+        emit_deps: parameters
+            .try_get_one::<String>("emit-deps")
+            .ok()
+            .flatten()
+            .map(PathBuf::from),
+        diagnostics_color: is_color_enabled(parameters),
+        message_format: match parameters
+            .try_get_one::<String>("message-format")
+            .ok()
+            .flatten()
+            .map(String::as_str)
+        {
+            Some("json") => MessageFormat::Json,
+            Some("sarif") => MessageFormat::Sarif,
+            _ => MessageFormat::Human,
+        },
+        check: is_flag_set(parameters, "check"),
+        graph_format: match parameters
+            .try_get_one::<String>("format")
+            .ok()
+            .flatten()
+            .map(String::as_str)
+        {
+            Some("mermaid") => GraphFormat::Mermaid,
+            _ => GraphFormat::Dot,
+        },
+        prefix: parameters
+            .try_get_one::<String>("prefix")
+            .ok()
+            .flatten()
+            .map(PathBuf::from),
+        package_format: match parameters
+            .try_get_one::<String>("format")
+            .ok()
+            .flatten()
+            .map(String::as_str)
+        {
+            Some("tar") => Some(PackageFormat::TarGz),
+            Some("zip") => Some(PackageFormat::Zip),
+            _ => None,
+        },
+        source: parameters
+            .try_get_one::<String>("source")
+            .ok()
+            .flatten()
+            .cloned(),
+        intel_syntax: is_flag_set(parameters, "intel"),
+        output: parameters
+            .try_get_one::<String>("output")
+            .ok()
+            .flatten()
+            .map(PathBuf::from),
+    }
+}
+
+fn log_workspace_open_error(error: WorkspaceOpenError) {
+    match error {
+        WorkspaceOpenError::InvalidWorkspacePath(error) => {
+            eprintln!("Failed to read {}: {}", WORKSPACE_CONFIGURATION_FILE, error)
+        }
+        WorkspaceOpenError::SyntaxError(error) => {
+            eprintln!("Syntax error occured in {}:", WORKSPACE_CONFIGURATION_FILE);
+
+            for line in error.split(path::is_separator) {
+                eprintln!("{line}");
+            }
+        }
+        WorkspaceOpenError::DuplicateMember(name) => {
+            eprintln!("Two members of the workspace are named {}", name)
+        }
+        WorkspaceOpenError::InvalidMember(path, error) => {
+            eprintln!("Failed to open member {}:", path);
+            log_project_open_error(error);
+        }
+        WorkspaceOpenError::MissingMember(name) => {
+            eprintln!("There is no member named {} in the workspace", name)
+        }
+    }
+}
+
+fn log_project_open_error(error: ProjectOpenError) {
+    match error {
+        ProjectOpenError::InvalidProjectPath(_error) => {
+            eprintln!("There is no bakery.toml in the current directory")
+        }
+        ProjectOpenError::ManifestNotFound(manifest) => {
+            eprintln!("Manifest {} doesn't exist", manifest.display())
+        }
+        ProjectOpenError::OutdatedLockfile(lockfile) => {
+            eprintln!(
+                "{} is missing or out of date, run without --locked to update it",
+                lockfile.display()
+            )
+        }
+        ProjectOpenError::PkgConfigError(name, message) => {
+            eprintln!("Failed to query pkg-config for {}:", name);
+            eprintln!("{}", message);
+        }
+        ProjectOpenError::GitError(url, message) => {
+            eprintln!("Failed to fetch {}:", url);
+            eprintln!("{}", message);
+        }
+        ProjectOpenError::DependencyCycle(cycle) => {
+            eprintln!("Dependency cycle detected:");
+
+            for (index, path) in cycle.iter().enumerate() {
+                if index == 0 {
+                    eprintln!("    {path}");
+                } else {
+                    eprintln!("    -> {path}");
+                }
+            }
+        }
+        ProjectOpenError::InvalidBuildConfiguration(build_configuration_error) => {
+            match build_configuration_error {
+                BuildConfigurationError::SyntaxError(error) => {
+                    eprintln!("Syntax error occured:");
+
+                    for line in error.split(path::is_separator) {
+                        eprintln!("{line}");
                     }
-                    BuildConfigurationError::IncorrectInclude(include) => {
-                        eprintln!("Incorrect include: {}", include);
+                }
+                BuildConfigurationError::InvalidName => eprintln!("Project's name consists of invalid characters. Valid characters are: {NAME_PATTERN}"),
+                BuildConfigurationError::IncorrectWildcard(wildcard) => eprintln!("Incorrect wildcard: {wildcard}"),
+                BuildConfigurationError::IncorrectSource(source) => {
+                    eprintln!("Incorrect source: {}", source);
+                }
+                BuildConfigurationError::IncorrectInclude(include) => {
+                    eprintln!("Incorrect include: {}", include);
+                }
+                BuildConfigurationError::DependencyIsNotALibrary(dependency) => {
+                    eprintln!("Dependency is not a library: {}", dependency);
+                }
+                BuildConfigurationError::UnknownProfile(profile) => {
+                    eprintln!("Unknown profile: {}", profile);
+                }
+                BuildConfigurationError::ConflictingSanitizers(first, second) => {
+                    eprintln!("Sanitizers {} and {} can't be used together", first, second);
+                }
+                BuildConfigurationError::InvalidVersion(version) => {
+                    eprintln!("Version {} is not a valid semantic version", version);
+                }
+                BuildConfigurationError::InvalidLinker(linker) => {
+                    eprintln!(
+                        "Linker {} is not supported, use one of {} or an absolute path",
+                        linker,
+                        config::SUPPORTED_LINKERS.join(", ")
+                    );
+                }
+                BuildConfigurationError::InvalidTargetPredicate(predicate) => {
+                    eprintln!(
+                        "Target predicate {} is not supported, use cfg(<os>), cfg(unix) or cfg(windows)",
+                        predicate
+                    );
+                }
+                BuildConfigurationError::FrameworksRequireMacos => {
+                    eprintln!(
+                        "Frameworks can only be linked on macOS, move them to a [target.'cfg(macos)'] table"
+                    );
+                }
+                BuildConfigurationError::BinariesRequireExecutable => {
+                    eprintln!("Only executables can declare [[bin]] targets");
+                }
+                BuildConfigurationError::InvalidBinaryName(name) => {
+                    eprintln!(
+                        "Binary name {} is used twice or contains invalid characters. Valid characters are: {NAME_PATTERN}",
+                        name
+                    );
+                }
+                BuildConfigurationError::UnknownMember(name) => {
+                    eprintln!("Dependency {} is not a member of the workspace", name);
+                }
+                BuildConfigurationError::EmptySourcePatterns(patterns) => {
+                    eprintln!("Source patterns don't match any files:");
+
+                    for pattern in patterns {
+                        eprintln!("    {pattern}");
                     }
-                    BuildConfigurationError::DependencyIsNotALibrary(dependency) => {
-                        eprintln!("Dependency is not a library: {}", dependency);
+                }
+                BuildConfigurationError::ManagedArguments(conflicts) => {
+                    eprintln!("Additional arguments conflict with flags managed by bakery:");
+
+                    for conflict in conflicts {
+                        eprintln!("    {conflict}");
                     }
                 }
+                BuildConfigurationError::MissingPrebuiltDirectory(directory) => {
+                    eprintln!("Prebuilt dependency directory {} doesn't exist", directory);
+                }
+                BuildConfigurationError::DuplicateTestName(name) => {
+                    eprintln!(
+                        "Two tests are named {}, as their sources differ only in extension",
+                        name
+                    );
+                }
+                BuildConfigurationError::UndefinedVariable(name) => {
+                    eprintln!(
+                        "Variable {} is not defined, set it in the environment or escape it as $${{{}}}",
+                        name, name
+                    );
+                }
             }
-        },
+        }
     }
+}
 
-    Ok(())
+fn build_command() -> Command {
+    Command::new("bakery")
+        .version("0.1")
+        .author("Bakamono")
+        .about("Build system for C/C++")
+        .arg(
+            Arg::new("directory")
+                .short('C')
+                .long("directory")
+                .value_name("PATH")
+                .help("Run as if bakery was started in PATH"),
+        )
+        .arg(
+            Arg::new("manifest")
+                .long("manifest")
+                .value_name("PATH")
+                .help("Build configuration to use instead of bakery.toml"),
+        )
+        .arg(
+            Arg::new("member")
+                .long("member")
+                .value_name("NAME")
+                .help("Act on a single member when run in a workspace"),
+        )
+        .arg(
+            Arg::new("locked")
+                .long("locked")
+                .action(ArgAction::SetTrue)
+                .help("Fail instead of updating a missing or outdated bakery.lock"),
+        )
+        .arg(
+            Arg::new("frozen")
+                .long("frozen")
+                .action(ArgAction::SetTrue)
+                .help(
+                    "Only use tools with an explicit location instead of looking them up on PATH",
+                ),
+        )
+        .subcommand(
+            Command::new("build")
+                .about("Compile the project and its dependencies")
+                .args(profile_arguments())
+                .arg(jobs_argument())
+                .arg(target_directory_argument())
+                .arg(no_color_argument())
+                .arg(verbose_argument())
+                .arg(message_format_argument())
+                .arg(
+                    Arg::new("keep-going")
+                        .short('k')
+                        .long("keep-going")
+                        .action(ArgAction::SetTrue)
+                        .help("Keep building the dependencies that don't depend on a failed one"),
+                )
+                .arg(
+                    Arg::new("force")
+                        .long("force")
+                        .action(ArgAction::SetTrue)
+                        .help("Compile every source, even those whose objects are up to date"),
+                )
+                .arg(
+                    Arg::new("emit-deps")
+                        .long("emit-deps")
+                        .value_name("FILE")
+                        .help(
+                        "Write the artifacts and the files they were built from to FILE as JSON",
+                    ),
+                )
+                .arg(
+                    Arg::new("timings")
+                        .long("timings")
+                        .action(ArgAction::SetTrue)
+                        .help("Print how long each source file took to compile"),
+                ),
+        )
+        .subcommand(
+            Command::new("run")
+                .about("Build and run the executable")
+                .args(profile_arguments())
+                .arg(jobs_argument())
+                .arg(target_directory_argument())
+                .arg(no_color_argument())
+                .arg(verbose_argument())
+                .arg(message_format_argument())
+                .arg(
+                    Arg::new("binary")
+                        .value_name("NAME")
+                        .help("Binary to run when the project declares several [[bin]] targets"),
+                )
+                .arg(
+                    Arg::new("env")
+                        .long("env")
+                        .value_name("KEY=VALUE")
+                        .action(ArgAction::Append)
+                        .value_parser(parse_environment_variable)
+                        .help("Set an environment variable for the executable, can be repeated"),
+                )
+                .arg(
+                    Arg::new("env-clear")
+                        .long("env-clear")
+                        .action(ArgAction::SetTrue)
+                        .help("Don't pass bakery's own environment on to the executable"),
+                )
+                .arg(
+                    Arg::new("arguments")
+                        .num_args(0..)
+                        .last(true)
+                        .allow_hyphen_values(true)
+                        .help("Arguments passed to the executable"),
+                ),
+        )
+        .subcommand(
+            Command::new("fmt")
+                .about("Format the sources with clang-format")
+                .arg(
+                    Arg::new("check")
+                        .long("check")
+                        .action(ArgAction::SetTrue)
+                        .help("Check formatting without modifying files"),
+                ),
+        )
+        .subcommand(
+            Command::new("tidy")
+                .about("Run clang-tidy over the sources")
+                .args(profile_arguments()),
+        )
+        .subcommand(
+            Command::new("asm")
+                .about("Print the assembly generated for one of the sources")
+                .args(profile_arguments())
+                .arg(target_directory_argument())
+                .arg(no_color_argument())
+                .arg(
+                    Arg::new("source")
+                        .required(true)
+                        .value_name("SOURCE")
+                        .help("Source to compile, one of the project's sources"),
+                )
+                .arg(
+                    Arg::new("intel")
+                        .long("intel")
+                        .action(ArgAction::SetTrue)
+                        .help("Use Intel instead of AT&T syntax, x86 only"),
+                )
+                .arg(
+                    Arg::new("output")
+                        .short('o')
+                        .long("output")
+                        .value_name("PATH")
+                        .help("Write the assembly to PATH instead of printing it"),
+                ),
+        )
+        .subcommand(
+            Command::new("preprocess")
+                .about("Print one of the sources after preprocessing")
+                .args(profile_arguments())
+                .arg(target_directory_argument())
+                .arg(
+                    Arg::new("source")
+                        .required(true)
+                        .value_name("SOURCE")
+                        .help("Source to preprocess, one of the project's sources"),
+                )
+                .arg(
+                    Arg::new("output")
+                        .short('o')
+                        .long("output")
+                        .value_name("PATH")
+                        .help("Write the preprocessed source to PATH instead of printing it"),
+                ),
+        )
+        .subcommand(
+            Command::new("test")
+                .about("Build and run the tests")
+                .args(profile_arguments())
+                .arg(jobs_argument())
+                .arg(target_directory_argument())
+                .arg(no_color_argument())
+                .arg(verbose_argument())
+                .arg(message_format_argument()),
+        )
+        .subcommand(
+            Command::new("install")
+                .about("Build and install the project under a prefix")
+                .args(profile_arguments())
+                .arg(jobs_argument())
+                .arg(target_directory_argument())
+                .arg(no_color_argument())
+                .arg(verbose_argument())
+                .arg(
+                    Arg::new("prefix")
+                        .long("prefix")
+                        .value_name("PATH")
+                        .help("Installation prefix, defaults to /usr/local"),
+                ),
+        )
+        .subcommand(
+            Command::new("graph")
+                .about("Print the project's dependency graph")
+                .arg(
+                    Arg::new("format")
+                        .long("format")
+                        .value_parser(["dot", "mermaid"])
+                        .default_value("dot")
+                        .help("Output format of the dependency graph"),
+                ),
+        )
+        .subcommand(
+            Command::new("package")
+                .about("Build and archive the project for distribution")
+                .args(profile_arguments())
+                .arg(jobs_argument())
+                .arg(target_directory_argument())
+                .arg(no_color_argument())
+                .arg(verbose_argument())
+                .arg(
+                    Arg::new("format")
+                        .long("format")
+                        .value_parser(["tar", "zip"])
+                        .help("Archive format, defaults to tar on Unix and zip on Windows"),
+                ),
+        )
+        .subcommand(
+            Command::new("watch")
+                .about("Rebuild whenever a source changes")
+                .args(profile_arguments())
+                .arg(jobs_argument())
+                .arg(target_directory_argument())
+                .arg(no_color_argument())
+                .arg(verbose_argument())
+                .arg(
+                    Arg::new("task")
+                        .value_parser(["build", "run"])
+                        .default_value("build")
+                        .help("Task to re-run whenever a source changes"),
+                ),
+        )
+        .subcommand(
+            Command::new("doctor").about("Check the toolchain and the project's configuration"),
+        )
+        .subcommand(
+            Command::new("completions")
+                .about("Print a shell completion script")
+                .arg(
+                    Arg::new("shell")
+                        .required(true)
+                        .value_parser(value_parser!(Shell))
+                        .help("Shell to generate the completion script for"),
+                ),
+        )
+}
+
+fn profile_arguments() -> [Arg; 3] {
+    [
+        Arg::new("profile")
+            .long("profile")
+            .value_name("NAME")
+            .conflicts_with_all(["release", "debug"])
+            .help("Build with the named profile from bakery.toml"),
+        Arg::new("release")
+            .long("release")
+            .action(ArgAction::SetTrue)
+            .conflicts_with("debug")
+            .help("Build with the release profile"),
+        Arg::new("debug")
+            .long("debug")
+            .action(ArgAction::SetTrue)
+            .help("Build with the debug profile"),
+    ]
+}
+
+fn target_directory_argument() -> Arg {
+    Arg::new("target-dir")
+        .long("target-dir")
+        .value_name("PATH")
+        .help("Directory for build outputs and caches, defaults to .bakery")
 }
 
+fn message_format_argument() -> Arg {
+    Arg::new("message-format")
+        .long("message-format")
+        .value_parser(["human", "json", "sarif"])
+        .default_value("human")
+        .help(
+            "Format of compiler diagnostics, json prints one diagnostic object per line, sarif \
+             writes them to .bakery/diagnostics.sarif",
+        )
+}
+
+fn no_color_argument() -> Arg {
+    Arg::new("no-color")
+        .long("no-color")
+        .action(ArgAction::SetTrue)
+        .help("Disable colored compiler diagnostics")
+}
+
+fn verbose_argument() -> Arg {
+    Arg::new("verbose")
+        .short('v')
+        .long("verbose")
+        .action(ArgAction::SetTrue)
+        .help("Explain why each source file is recompiled")
+}
+
+fn jobs_argument() -> Arg {
+    Arg::new("jobs")
+        .short('j')
+        .long("jobs")
+        .value_name("N")
+        .value_parser(RangedU64ValueParser::<usize>::new().range(1..))
+        .help("Number of parallel compilation jobs, defaults to the number of cores")
+}
+
+fn parse_environment_variable(variable: &str) -> Result<(String, String), String> {
+    match variable.split_once('=') {
+        Some((key, value)) if !key.is_empty() => Ok((String::from(key), String::from(value))),
+        _ => Err(String::from("expected KEY=VALUE")),
+    }
+}
+
+fn is_flag_set(parameters: &ArgMatches, id: &str) -> bool {
+    parameters
+        .try_get_one::<bool>(id)
+        .ok()
+        .flatten()
+        .copied()
+        .unwrap_or_default()
+}
+
+fn parse_profile(parameters: &ArgMatches) -> Option<&str> {
+    if let Ok(Some(profile)) = parameters.try_get_one::<String>("profile") {
+        Some(profile)
+    } else if is_flag_set(parameters, "release") {
+        Some("release")
+    } else if is_flag_set(parameters, "debug") {
+        Some("debug")
+    } else {
+        None
+    }
+}
+
+/// Runs the task after its dependencies, stopping at the first one that fails, so that e.g. `run`
+/// never launches a stale executable after a failed build.
 fn execute_task_and_its_dependencies(
     tasks: &mut HashMap<&str, Box<dyn Task>>,
     task_id: &str,
     context: &TaskContext,
-) {
+) -> eyre::Result<()> {
     let mut processing_stack = VecDeque::new();
     let mut result_stack = VecDeque::new();
 
@@ -106,8 +964,10 @@ fn execute_task_and_its_dependencies(
     }
 
     for task_id in result_stack.into_iter().unique() {
-        tasks.get_mut(task_id).unwrap().on_execute(context);
+        tasks.get_mut(task_id).unwrap().on_execute(context)?;
     }
+
+    Ok(())
 }
 
 fn deserialize_toolchain_configuration() -> eyre::Result<ToolchainConfiguration> {
@@ -140,7 +1000,90 @@ fn deserialize_toolchain_configuration() -> eyre::Result<ToolchainConfiguration>
 #[cfg(test)]
 mod tests {
     use super::*;
-    use std::{cell::RefCell, path::PathBuf, rc::Rc};
+    use std::{cell::RefCell, collections::BTreeMap, path::PathBuf, rc::Rc, sync::Mutex};
+
+    fn create_context() -> TaskContext {
+        TaskContext {
+            project: Arc::new(Project {
+                base_path: PathBuf::new(),
+                manifest: PathBuf::from(BUILD_CONFIGURATION_FILE),
+                name: String::new(),
+                description: None,
+                author: None,
+                version: None,
+                language: config::Language::Cpp,
+                distribution: config::Distribution::Executable,
+                sources: vec![],
+                binaries: vec![],
+                tests: vec![],
+                exclude: vec![],
+                test_arguments: BTreeMap::new(),
+                run_environment: BTreeMap::new(),
+                includes: vec![],
+                public_includes: vec![],
+                dependencies: vec![],
+                install_headers: vec![],
+                precompiled_header: None,
+                defines: BTreeMap::new(),
+                libraries: vec![],
+                library_search_paths: vec![],
+                link_flags: vec![],
+                frameworks: vec![],
+                optimization: config::OptimizationLevel::Zero,
+                debug_info: None,
+                target_cpu: None,
+                tune_cpu: None,
+                enable_all_warnings: false,
+                treat_all_warnings_as_errors: false,
+                strip: false,
+                coverage: false,
+                rpath: true,
+                threads: false,
+                math: false,
+                static_runtime: false,
+                fully_static: false,
+                lto: config::Lto::Off,
+                unity: false,
+                unity_batch_size: 1,
+                warnings: config::WarningsConfiguration::default(),
+                sanitizers: vec![],
+                has_project_configuration_changed: false,
+                configuration_hash: blake3::hash(b""),
+                toolchain: config::ProjectToolchainConfiguration::default(),
+                hashes: HashMap::new(),
+                file_hashes: HashMap::new(),
+                current_file_hashes: Mutex::new(HashMap::new()),
+                c: None,
+                cpp: None,
+                gcc: None,
+                gpp: None,
+                profile: None,
+                overrides: vec![],
+                linker: None,
+                output_directory: PathBuf::from(BAKERY_DIRECTORY),
+            }),
+            toolchain_configuration: ToolchainConfiguration::default(),
+            run_arguments: vec![],
+            binary: None,
+            run_environment: vec![],
+            clear_environment: false,
+            jobs: None,
+            timings: false,
+            verbose: false,
+            keep_going: false,
+            force: false,
+            emit_deps: None,
+            diagnostics_color: false,
+            message_format: MessageFormat::default(),
+            check: false,
+            graph_format: GraphFormat::default(),
+            prefix: None,
+            package_format: None,
+            source: None,
+            intel_syntax: false,
+            output: None,
+        }
+    }
 
     #[test]
     fn test_execute_dependencies() {
@@ -157,8 +1100,10 @@ mod tests {
                 &["task2", "task3"]
             }
 
-            fn on_execute(&mut self, _context: &TaskContext) {
+            fn on_execute(&mut self, _context: &TaskContext) -> eyre::Result<()> {
                 self.steps.borrow_mut().push(1);
+
+                Ok(())
             }
         }
 
@@ -175,8 +1120,10 @@ mod tests {
                 &["task3"]
             }
 
-            fn on_execute(&mut self, _context: &TaskContext) {
+            fn on_execute(&mut self, _context: &TaskContext) -> eyre::Result<()> {
                 self.steps.borrow_mut().push(2);
+
+                Ok(())
             }
         }
 
@@ -193,8 +1140,10 @@ mod tests {
                 &["task4"]
             }
 
-            fn on_execute(&mut self, _context: &TaskContext) {
+            fn on_execute(&mut self, _context: &TaskContext) -> eyre::Result<()> {
                 self.steps.borrow_mut().push(3);
+
+                Ok(())
             }
         }
 
@@ -211,8 +1160,10 @@ mod tests {
                 &[]
             }
 
-            fn on_execute(&mut self, _context: &TaskContext) {
+            fn on_execute(&mut self, _context: &TaskContext) -> eyre::Result<()> {
                 self.steps.borrow_mut().push(4);
+
+                Ok(())
             }
         }
 
@@ -245,32 +1196,65 @@ mod tests {
             }),
         );
 
-        let context = TaskContext {
-            project: Project {
-                base_path: PathBuf::new(),
-                name: String::new(),
-                description: None,
-                author: None,
-                language: config::Language::Cpp,
-                distribution: config::Distribution::Executable,
-                sources: vec![],
-                includes: vec![],
-                dependencies: vec![],
-                optimization: config::OptimizationLevel::Zero,
-                enable_all_warnings: false,
-                treat_all_warnings_as_errors: false,
-                has_project_configuration_changed: false,
-                hashes: HashMap::new(),
-                c: None,
-                cpp: None,
-                gcc: None,
-                gpp: None,
-            },
-            toolchain_configuration: ToolchainConfiguration::default(),
-        };
+        let context = create_context();
 
-        execute_task_and_its_dependencies(&mut tasks, "task1", &context);
+        execute_task_and_its_dependencies(&mut tasks, "task1", &context).unwrap();
 
         assert_eq!(*steps.borrow(), vec![4, 3, 2, 1]);
     }
+
+    #[test]
+    fn test_failed_dependency_stops_dependents() {
+        struct StepTask {
+            id: &'static str,
+            dependencies: &'static [&'static str],
+            fails: bool,
+            steps: Rc<RefCell<Vec<&'static str>>>,
+        }
+
+        impl Task for StepTask {
+            fn id(&self) -> &'static str {
+                self.id
+            }
+
+            fn dependencies(&self) -> &[&'static str] {
+                self.dependencies
+            }
+
+            fn on_execute(&mut self, _context: &TaskContext) -> eyre::Result<()> {
+                self.steps.borrow_mut().push(self.id);
+
+                if self.fails {
+                    eyre::bail!("{} failed", self.id);
+                }
+
+                Ok(())
+            }
+        }
+
+        let mut tasks: HashMap<&str, Box<dyn Task>> = HashMap::new();
+
+        let steps = Rc::new(RefCell::new(vec![]));
+
+        for (id, dependencies, fails) in [
+            ("prepare", &[][..], false),
+            ("build", &["prepare"][..], true),
+            ("run", &["build"][..], false),
+        ] {
+            tasks.insert(
+                id,
+                Box::new(StepTask {
+                    id,
+                    dependencies,
+                    fails,
+                    steps: Rc::clone(&steps),
+                }),
+            );
+        }
+
+        let context = create_context();
+
+        assert!(execute_task_and_its_dependencies(&mut tasks, "run", &context).is_err());
+        assert_eq!(*steps.borrow(), vec!["prepare", "build"]);
+    }
 }
@@ -0,0 +1,118 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    process,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+const OBJECT_FILE_EXTENSION: &str = "o";
+const DEPENDENCY_FILE_EXTENSION: &str = "d";
+
+/// Entries are spread over directories named after the first characters of their keys, which
+/// keeps any single directory from growing too large.
+const SHARD_LENGTH: usize = 2;
+
+static TEMPORARY_FILE_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+/// Object files shared by every build pointing at the same directory, keyed by everything that
+/// went into compiling them.
+///
+/// Entries are written under a temporary name and renamed into place, so concurrent builds only
+/// ever see complete ones. The dependency file is stored before the object, so an entry whose
+/// object exists also has its dependency file, unless the compiler didn't write one.
+pub(crate) struct ObjectCache {
+    directory: PathBuf,
+}
+
+impl ObjectCache {
+    pub(crate) fn new(directory: PathBuf) -> ObjectCache {
+        ObjectCache { directory }
+    }
+
+    /// Copies the cached object and its dependency file into place, returning whether there was
+    /// one to copy.
+    pub(crate) fn restore(&self, key: &str, object_file: &Path, dependency_file: &Path) -> bool {
+        let cached_object_file = self.entry(key, OBJECT_FILE_EXTENSION);
+
+        if !cached_object_file.exists() {
+            return false;
+        }
+
+        let cached_dependency_file = self.entry(key, DEPENDENCY_FILE_EXTENSION);
+
+        if cached_dependency_file.exists()
+            && fs::copy(&cached_dependency_file, dependency_file).is_err()
+        {
+            return false;
+        }
+
+        fs::copy(&cached_object_file, object_file).is_ok()
+    }
+
+    pub(crate) fn store(
+        &self,
+        key: &str,
+        object_file: &Path,
+        dependency_file: &Path,
+    ) -> Result<(), io::Error> {
+        fs::create_dir_all(self.entry(key, OBJECT_FILE_EXTENSION).parent().unwrap())?;
+
+        if dependency_file.exists() {
+            self.store_file(dependency_file, &self.entry(key, DEPENDENCY_FILE_EXTENSION))?;
+        }
+
+        self.store_file(object_file, &self.entry(key, OBJECT_FILE_EXTENSION))
+    }
+
+    fn store_file(&self, file: &Path, entry: &Path) -> Result<(), io::Error> {
+        let temporary_file = entry.with_file_name(format!(
+            "tmp-{}-{}",
+            process::id(),
+            TEMPORARY_FILE_COUNTER.fetch_add(1, Ordering::Relaxed)
+        ));
+
+        fs::copy(file, &temporary_file)
+            .and_then(|_| fs::rename(&temporary_file, entry))
+            .inspect_err(|_| {
+                let _ = fs::remove_file(&temporary_file);
+            })
+    }
+
+    fn entry(&self, key: &str, extension: &str) -> PathBuf {
+        self.directory
+            .join(&key[..SHARD_LENGTH])
+            .join(key)
+            .with_extension(extension)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_restores_stored_objects() {
+        let root = tempfile::tempdir().unwrap();
+        let object_cache = ObjectCache::new(root.path().join("cache"));
+        let object_file = root.path().join("main.o");
+        let dependency_file = root.path().join("main.d");
+        let key = blake3::hash(b"main").to_hex().to_string();
+
+        assert!(!object_cache.restore(&key, &object_file, &dependency_file));
+
+        fs::write(&object_file, "object").unwrap();
+        fs::write(&dependency_file, "main.o: main.c").unwrap();
+        object_cache
+            .store(&key, &object_file, &dependency_file)
+            .unwrap();
+        fs::remove_file(&object_file).unwrap();
+        fs::remove_file(&dependency_file).unwrap();
+
+        assert!(object_cache.restore(&key, &object_file, &dependency_file));
+        assert_eq!(fs::read_to_string(&object_file).unwrap(), "object");
+        assert_eq!(
+            fs::read_to_string(&dependency_file).unwrap(),
+            "main.o: main.c"
+        );
+    }
+}
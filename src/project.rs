@@ -1,23 +1,57 @@
 use crate::{
     config::{
-        self, BuildConfiguration, CConfiguration, CppConfiguration, Distribution, GccConfiguration,
-        GppConfiguration, Language, OptimizationLevel,
+        self, BuildConfiguration, CConfiguration, CppConfiguration, DebugInfoLevel, Distribution,
+        GccConfiguration, GppConfiguration, Language, LinkKind, Lto, OptimizationLevel,
+        ProfileConfiguration, ProjectToolchainConfiguration, Sanitizer,
+        SourceOverrideConfiguration, WarningsConfiguration,
     },
-    PathExtension, BAKERY_HASHES_FILE, BUILD_CONFIGURATION_FILE,
+    log_error, Lockfile, PathExtension, PkgConfigPackage, WorkspaceMember, BAKERY_BUILD_DIRECTORY,
+    BAKERY_CACHE_DIRECTORY, BAKERY_DIRECTORY, BAKERY_FILE_HASHES_FILE, BAKERY_HASHES_FILE,
+    BUILD_CONFIGURATION_FILE, LOCKFILE_EXTENSION,
 };
 use blake3::Hash;
-use glob::glob;
+use glob::{glob, MatchOptions, Pattern};
+use itertools::Itertools;
 use lazy_static::lazy_static;
 use regex::Regex;
-use std::collections::HashMap;
+use semver::Version;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
 use std::fs::File;
 use std::io::Read;
 use std::path::{Path, PathBuf};
-use std::{fs, io};
+use std::process::Command;
+use std::sync::{Arc, Mutex};
+use std::{env, fs, io, mem};
 use thiserror::Error;
 
 pub(crate) const NAME_PATTERN: &str = "[a-zA-Z][a-zA-Z0-9]+";
 
+const DEFAULT_UNITY_BATCH_SIZE: usize = 8;
+
+/// Git dependencies are cloned into the cache, each in a directory named after a hash of its URL.
+const GIT_DIRECTORY: &str = "git";
+
+const GIT_DIRECTORY_HASH_LENGTH: usize = 16;
+
+/// Fields of `[project]` that don't affect what gets built.
+const COSMETIC_PROJECT_FIELDS: [&str; 2] = ["description", "author"];
+
+/// Fields holding paths or extra arguments, the only ones `${NAME}` is expanded in, wherever
+/// they appear, e.g. in `[project]`, `[target.*]`, `[[bin]]` or `[gcc]`.
+const INTERPOLATED_FIELDS: [&str; 10] = [
+    "sources",
+    "includes",
+    "publicIncludes",
+    "privateIncludes",
+    "librarySearchPaths",
+    "includeDir",
+    "libraryDir",
+    "linkFlags",
+    "additionalPreArguments",
+    "additionalPostArguments",
+];
+
 lazy_static! {
     static ref NAME_REGEX: Regex = Regex::new(NAME_PATTERN).unwrap();
 }
@@ -25,36 +59,321 @@ lazy_static! {
 #[allow(dead_code)]
 pub(crate) struct Project {
     pub(crate) base_path: PathBuf,
+    /// The build configuration file, `bakery.toml` unless another one was given.
+    pub(crate) manifest: PathBuf,
     pub(crate) name: String,
     pub(crate) description: Option<String>,
     pub(crate) author: Option<String>,
+    pub(crate) version: Option<String>,
     pub(crate) language: Language,
     pub(crate) distribution: Distribution,
+    /// Every source of the project, including those only compiled into one of its binaries.
     pub(crate) sources: Vec<String>,
+    /// Without any, an executable project links all of its sources into a single executable
+    /// named after itself.
+    pub(crate) binaries: Vec<Binary>,
+    pub(crate) tests: Vec<String>,
+    pub(crate) exclude: Vec<Pattern>,
+    pub(crate) test_arguments: BTreeMap<String, Vec<String>>,
+    pub(crate) run_environment: BTreeMap<String, String>,
+    /// Every include directory the project is compiled with.
     pub(crate) includes: Vec<String>,
+    /// The include directories dependents are compiled with, including those the project's
+    /// own dependencies export.
+    pub(crate) public_includes: Vec<String>,
     pub(crate) dependencies: Vec<Dependency>,
+    pub(crate) install_headers: Vec<String>,
+    pub(crate) precompiled_header: Option<String>,
+    pub(crate) defines: BTreeMap<String, String>,
+    pub(crate) libraries: Vec<String>,
+    pub(crate) library_search_paths: Vec<String>,
+    pub(crate) link_flags: Vec<String>,
+    pub(crate) frameworks: Vec<String>,
     pub(crate) optimization: OptimizationLevel,
+    pub(crate) debug_info: Option<DebugInfoLevel>,
+    pub(crate) target_cpu: Option<String>,
+    pub(crate) tune_cpu: Option<String>,
     pub(crate) enable_all_warnings: bool,
     pub(crate) treat_all_warnings_as_errors: bool,
+    pub(crate) strip: bool,
+    pub(crate) coverage: bool,
+    pub(crate) rpath: bool,
+    pub(crate) threads: bool,
+    pub(crate) math: bool,
+    pub(crate) static_runtime: bool,
+    pub(crate) fully_static: bool,
+    pub(crate) lto: Lto,
+    pub(crate) unity: bool,
+    pub(crate) unity_batch_size: usize,
+    pub(crate) warnings: WarningsConfiguration,
+    pub(crate) sanitizers: Vec<Sanitizer>,
     pub(crate) has_project_configuration_changed: bool,
+    /// Hash of the build-relevant parts of the build configuration.
+    pub(crate) configuration_hash: Hash,
+    /// The project's `[toolchain]` section, along with the one it inherited.
+    pub(crate) toolchain: ProjectToolchainConfiguration,
     pub(crate) hashes: HashMap<String, Hash>,
+    /// Content hashes of the files read by the previous build, keyed by path.
+    pub(crate) file_hashes: HashMap<String, FileHash>,
+    /// Content hashes of the files read by this build, saved in place of the previous ones.
+    pub(crate) current_file_hashes: Mutex<HashMap<String, FileHash>>,
     pub(crate) c: Option<CConfiguration>,
     pub(crate) cpp: Option<CppConfiguration>,
     pub(crate) gcc: Option<GccConfiguration>,
     pub(crate) gpp: Option<GppConfiguration>,
+    pub(crate) profile: Option<Profile>,
+    pub(crate) overrides: Vec<SourceOverride>,
+    pub(crate) output_directory: PathBuf,
+    pub(crate) linker: Option<String>,
 }
 
+/// An executable linked from its own sources and the sources shared by the whole project.
+pub(crate) struct Binary {
+    pub(crate) name: String,
+    pub(crate) sources: Vec<String>,
+}
+
+/// A file's content hash along with the modification time and size it was taken at.
+#[derive(Clone, Deserialize, Serialize)]
+pub(crate) struct FileHash {
+    /// Nanoseconds since the Unix epoch, missing when the file was modified too close to the
+    /// build for it to tell whether the content changed since.
+    pub(crate) modified: Option<u64>,
+    pub(crate) size: u64,
+    pub(crate) hash: String,
+}
+
+pub(crate) struct SourceOverride {
+    pub(crate) pattern: Pattern,
+    pub(crate) configuration: SourceOverrideConfiguration,
+}
+
+impl SourceOverride {
+    pub(crate) fn matches(&self, source: &str) -> bool {
+        self.pattern.matches_path_with(
+            Path::new(source),
+            MatchOptions {
+                require_literal_separator: true,
+                ..Default::default()
+            },
+        )
+    }
+}
+
+pub(crate) struct Profile {
+    pub(crate) name: String,
+    pub(crate) configuration: ProfileConfiguration,
+}
+
+#[allow(dead_code)]
 pub(crate) enum Dependency {
-    System { name: String },
-    Project(Box<Project>),
+    System {
+        name: String,
+        kind: LinkKind,
+    },
+    PkgConfig {
+        name: String,
+        includes: Vec<String>,
+        compile_flags: Vec<String>,
+        link_flags: Vec<String>,
+    },
+    /// Both directories are already joined with the path of the project declaring them.
+    Prebuilt {
+        include_directory: String,
+        library_directory: String,
+        libraries: Vec<String>,
+    },
+    Project(Arc<Project>),
 }
 
 impl Project {
-    pub(crate) fn open(path: impl AsRef<Path>) -> Result<Project, ProjectOpenError> {
+    /// The output directory, when given, only relocates the opened project, its dependencies
+    /// keep the one from their own configuration.
+    pub(crate) fn open(
+        path: impl AsRef<Path>,
+        profile: Option<&str>,
+        output_directory: Option<&Path>,
+    ) -> Result<Project, ProjectOpenError> {
+        Self::open_with_visited(
+            path,
+            Path::new(BUILD_CONFIGURATION_FILE),
+            profile,
+            output_directory,
+            &mut Vec::new(),
+            &mut HashMap::new(),
+            &mut HashMap::new(),
+            &HashMap::new(),
+            None,
+        )
+    }
+
+    /// Opens the project described by a build configuration with a name other than
+    /// `bakery.toml`, rooted at the directory containing it.
+    pub(crate) fn open_manifest(
+        manifest: &Path,
+        profile: Option<&str>,
+        output_directory: Option<&Path>,
+    ) -> Result<Project, ProjectOpenError> {
+        let (base_path, manifest_name) = Self::split_manifest(manifest)?;
+
+        Self::open_with_visited(
+            base_path,
+            manifest_name,
+            profile,
+            output_directory,
+            &mut Vec::new(),
+            &mut HashMap::new(),
+            &mut HashMap::new(),
+            &HashMap::new(),
+            None,
+        )
+    }
+
+    /// Opens the project like [`Project::open_manifest`], reusing the pkg-config results recorded
+    /// in its lockfile while the lockfile matches the resolved dependencies and regenerating it
+    /// otherwise. When `locked`, a missing or outdated lockfile is an error instead.
+    pub(crate) fn open_with_lockfile(
+        manifest: Option<&Path>,
+        profile: Option<&str>,
+        output_directory: Option<&Path>,
+        locked: bool,
+    ) -> Result<Project, ProjectOpenError> {
+        let (base_path, manifest_name) = match manifest {
+            Some(manifest) => Self::split_manifest(manifest)?,
+            None => (Path::new("."), Path::new(BUILD_CONFIGURATION_FILE)),
+        };
+        let lockfile_path = base_path
+            .join(manifest_name)
+            .with_extension(LOCKFILE_EXTENSION);
+        let lockfile = Lockfile::read(&lockfile_path);
+
+        let open = |pkg_config_packages: &mut HashMap<String, PkgConfigPackage>| {
+            Self::open_with_visited(
+                base_path,
+                manifest_name,
+                profile,
+                output_directory,
+                &mut Vec::new(),
+                &mut HashMap::new(),
+                pkg_config_packages,
+                &HashMap::new(),
+                None,
+            )
+        };
+
+        let project = open(
+            &mut lockfile
+                .as_ref()
+                .map(Lockfile::pkg_config_packages)
+                .unwrap_or_default(),
+        )?;
+
+        if lockfile.as_ref() == Some(&Lockfile::new(&project)) {
+            return Ok(project);
+        }
+
+        if locked {
+            return Err(ProjectOpenError::OutdatedLockfile(lockfile_path));
+        }
+
+        // The previous pkg-config results may be just as stale as the rest of the lockfile
+        let project = match lockfile {
+            Some(_) => {
+                eprintln!(
+                    "{} is out of date, regenerating it",
+                    lockfile_path.display()
+                );
+
+                open(&mut HashMap::new())?
+            }
+            None => project,
+        };
+
+        if let Err(err) = Lockfile::new(&project).write(&lockfile_path) {
+            eprintln!("Failed to write {}: {}", lockfile_path.display(), err);
+        }
+
+        Ok(project)
+    }
+
+    /// Opens a member of a workspace unless one of the members opened before depends on it, in
+    /// which case that project is reused.
+    pub(crate) fn open_member(
+        member: &WorkspaceMember,
+        profile: Option<&str>,
+        members: &HashMap<String, WorkspaceMember>,
+        opened: &mut HashMap<PathBuf, Arc<Project>>,
+        pkg_config_packages: &mut HashMap<String, PkgConfigPackage>,
+    ) -> Result<Arc<Project>, ProjectOpenError> {
+        if let Some(project) = opened.get(&member.manifest) {
+            return Ok(Arc::clone(project));
+        }
+
+        let project = Arc::new(Self::open_with_visited(
+            &member.path,
+            Path::new(BUILD_CONFIGURATION_FILE),
+            profile,
+            Some(&member.output_directory),
+            &mut Vec::new(),
+            opened,
+            pkg_config_packages,
+            members,
+            None,
+        )?);
+
+        opened.insert(member.manifest.clone(), Arc::clone(&project));
+
+        Ok(project)
+    }
+
+    fn split_manifest(manifest: &Path) -> Result<(&Path, &Path), ProjectOpenError> {
+        let (Some(manifest_name), true) = (manifest.file_name(), manifest.is_file()) else {
+            return Err(ProjectOpenError::ManifestNotFound(manifest.to_path_buf()));
+        };
+
+        let base_path = manifest
+            .parent()
+            .filter(|parent| !parent.as_os_str().is_empty())
+            .unwrap_or(Path::new("."));
+
+        Ok((base_path, Path::new(manifest_name)))
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn open_with_visited(
+        path: impl AsRef<Path>,
+        manifest_name: &Path,
+        profile: Option<&str>,
+        output_directory: Option<&Path>,
+        visited: &mut Vec<PathBuf>,
+        opened: &mut HashMap<PathBuf, Arc<Project>>,
+        pkg_config_packages: &mut HashMap<String, PkgConfigPackage>,
+        members: &HashMap<String, WorkspaceMember>,
+        inherited_toolchain: Option<&ProjectToolchainConfiguration>,
+    ) -> Result<Project, ProjectOpenError> {
         let base_path = path.as_ref();
-        let build_configuration_file_path = base_path.join(BUILD_CONFIGURATION_FILE);
+        // Projects are identified by their manifest, as a directory may hold several
+        let canonical_manifest_path = base_path
+            .canonicalize()
+            .map_err(ProjectOpenError::InvalidProjectPath)?
+            .join(manifest_name);
 
-        let (build_configuration_content, build_configuration_hash) = {
+        if let Some(index) = visited
+            .iter()
+            .position(|path| *path == canonical_manifest_path)
+        {
+            return Err(ProjectOpenError::DependencyCycle(
+                visited[index..]
+                    .iter()
+                    .chain([&canonical_manifest_path])
+                    .map(|path| path.to_string_lossy().into_owned())
+                    .collect(),
+            ));
+        }
+
+        let build_configuration_file_path = base_path.join(manifest_name);
+
+        let build_configuration_content = {
             let mut build_configuration_file = File::open(&build_configuration_file_path)
                 .map_err(ProjectOpenError::InvalidProjectPath)?;
             let build_configuration_file_size = build_configuration_file
@@ -67,24 +386,41 @@ impl Project {
                 .read(&mut build_configuration_binary_content)
                 .map_err(ProjectOpenError::InvalidProjectPath)?;
 
-            let build_configuration_content =
-                String::from_utf8(build_configuration_binary_content.clone()).map_err(|err| {
-                    ProjectOpenError::InvalidProjectPath(io::Error::new(
-                        io::ErrorKind::InvalidData,
-                        err.to_string(),
-                    ))
-                })?;
-            let build_configuration_hash = blake3::hash(&build_configuration_binary_content);
+            String::from_utf8(build_configuration_binary_content).map_err(|err| {
+                ProjectOpenError::InvalidProjectPath(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    err.to_string(),
+                ))
+            })?
+        };
 
-            (build_configuration_content, build_configuration_hash)
+        let syntax_error = |err: toml::de::Error| {
+            ProjectOpenError::InvalidBuildConfiguration(BuildConfigurationError::SyntaxError(
+                err.to_string(),
+            ))
         };
+        // Deserialized from the text first, as only then errors point at the offending line
+        let mut build_configuration =
+            toml::from_str::<BuildConfiguration>(&build_configuration_content)
+                .map_err(syntax_error)?;
+        let mut build_configuration_table =
+            toml::from_str::<toml::Table>(&build_configuration_content).map_err(syntax_error)?;
 
-        let build_configuration =
-            toml::from_str::<BuildConfiguration>(&build_configuration_content).map_err(|err| {
-                ProjectOpenError::InvalidBuildConfiguration(BuildConfigurationError::SyntaxError(
-                    err.to_string(),
-                ))
-            })?;
+        if build_configuration_content.contains("${") {
+            Self::interpolate_variables(
+                &mut build_configuration_table,
+                &Self::builtin_variables(base_path),
+            )?;
+
+            build_configuration = toml::Value::Table(build_configuration_table.clone())
+                .try_into::<BuildConfiguration>()
+                .map_err(syntax_error)?;
+        }
+
+        // Hashed after interpolation, so a changed environment variable counts as a change
+        let build_configuration_hash = Self::hash_build_configuration(build_configuration_table);
+
+        let excluded_source_patterns = Self::apply_target_configurations(&mut build_configuration)?;
 
         if !NAME_REGEX.is_match(&build_configuration.project.name) {
             return Err(ProjectOpenError::InvalidBuildConfiguration(
@@ -92,16 +428,187 @@ impl Project {
             ));
         }
 
-        let hashes = Self::read_hashes(base_path);
+        if let Some(version) = &build_configuration.project.version {
+            if Version::parse(version).is_err() {
+                return Err(ProjectOpenError::InvalidBuildConfiguration(
+                    BuildConfigurationError::InvalidVersion(version.clone()),
+                ));
+            }
+        }
+
+        let selected_profile = Self::resolve_profile(&build_configuration, profile)?;
+
+        Self::validate_sanitizers(&build_configuration, selected_profile.as_ref())?;
+        Self::validate_additional_arguments(&build_configuration, selected_profile.as_ref())?;
+
+        if let Some(linker) = &build_configuration.project.linker {
+            if !config::is_valid_linker(linker) {
+                return Err(ProjectOpenError::InvalidBuildConfiguration(
+                    BuildConfigurationError::InvalidLinker(linker.clone()),
+                ));
+            }
+        }
+
+        if !build_configuration.project.frameworks.is_empty()
+            && config::target_os() != config::MACOS
+        {
+            return Err(ProjectOpenError::InvalidBuildConfiguration(
+                BuildConfigurationError::FrameworksRequireMacos,
+            ));
+        }
+
+        if build_configuration.project.fully_static && config::target_os() == config::MACOS {
+            log_error!(
+                "Warning: {} sets fullyStatic, which is ignored as macOS doesn't support fully static executables",
+                build_configuration.project.name
+            );
+        }
+
+        // Relative paths resolve against the project, absolute ones are used verbatim
+        let output_directory = base_path.join(
+            output_directory
+                .or(build_configuration
+                    .project
+                    .output_directory
+                    .as_deref()
+                    .map(Path::new))
+                .unwrap_or(Path::new(BAKERY_DIRECTORY)),
+        );
+
+        let hashes = Self::read_hashes(
+            &Self::profile_directory(
+                output_directory.join(BAKERY_CACHE_DIRECTORY),
+                selected_profile.as_ref(),
+            )
+            .join(BAKERY_HASHES_FILE),
+        );
+        let file_hashes = Self::read_file_hashes(
+            &Self::profile_directory(
+                output_directory.join(BAKERY_CACHE_DIRECTORY),
+                selected_profile.as_ref(),
+            )
+            .join(BAKERY_FILE_HASHES_FILE),
+        );
 
         let has_project_configuration_changed = hashes
             .get(BUILD_CONFIGURATION_FILE)
             .map(|hash| *hash != build_configuration_hash)
             .unwrap_or_default();
 
-        let dependencies = Self::resolve_dependencies(base_path, &build_configuration)?;
-        let sources = Self::resolve_sources(base_path, &build_configuration)?;
-        let includes = Self::resolve_includes(base_path, &build_configuration, &dependencies)?;
+        let toolchain = Self::resolve_toolchain(base_path, build_configuration.toolchain.clone())
+            .inherit(inherited_toolchain);
+
+        visited.push(canonical_manifest_path);
+
+        let dependencies = Self::resolve_dependencies(
+            base_path,
+            &output_directory,
+            &build_configuration,
+            profile,
+            visited,
+            opened,
+            pkg_config_packages,
+            members,
+            Some(&toolchain).filter(|toolchain| toolchain.apply_to_dependencies),
+        )?;
+
+        visited.pop();
+
+        let exclude = build_configuration
+            .project
+            .exclude
+            .iter()
+            .map(|pattern| {
+                Pattern::new(pattern).map_err(|err| {
+                    ProjectOpenError::InvalidBuildConfiguration(
+                        BuildConfigurationError::IncorrectWildcard(String::from(err.msg)),
+                    )
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let mut empty_source_patterns = Vec::new();
+        let allow_external_paths = build_configuration.project.allow_external_paths;
+        let allow_symlinks = build_configuration.project.allow_symlinks;
+        let excluded_sources =
+            Self::resolve_sources(base_path, &excluded_source_patterns, true, true, None)?;
+        let sources = Self::resolve_sources(
+            base_path,
+            &build_configuration.project.sources,
+            allow_external_paths,
+            allow_symlinks,
+            Some(&mut empty_source_patterns),
+        )?
+        .into_iter()
+        .filter(|source| !excluded_sources.contains(source) && !Self::matches_any(&exclude, source))
+        .collect::<Vec<_>>();
+        let binaries = Self::resolve_binaries(
+            base_path,
+            &build_configuration,
+            &excluded_sources,
+            &exclude,
+            &mut empty_source_patterns,
+        )?;
+        // A source claimed by a binary is only linked into that binary
+        let sources = sources
+            .into_iter()
+            .filter(|source| {
+                !binaries
+                    .iter()
+                    .any(|binary| binary.sources.contains(source))
+            })
+            .chain(
+                binaries
+                    .iter()
+                    .flat_map(|binary| binary.sources.iter().cloned()),
+            )
+            .unique()
+            .collect::<Vec<_>>();
+        let tests = Self::resolve_sources(
+            base_path,
+            &build_configuration.test.sources,
+            allow_external_paths,
+            allow_symlinks,
+            Some(&mut empty_source_patterns),
+        )?
+        .into_iter()
+        .filter(|test| !Self::matches_any(&exclude, test))
+        .collect::<Vec<_>>();
+
+        if let Some(name) = tests
+            .iter()
+            .map(|test| Self::test_name(test))
+            .duplicates()
+            .next()
+        {
+            return Err(ProjectOpenError::InvalidBuildConfiguration(
+                BuildConfigurationError::DuplicateTestName(name),
+            ));
+        }
+
+        Self::validate_source_patterns(&build_configuration, empty_source_patterns)?;
+
+        let (includes, public_includes) =
+            Self::resolve_includes(base_path, &build_configuration, &dependencies)?;
+        let install_headers = Self::resolve_install_headers(base_path, &build_configuration)?
+            .into_iter()
+            .filter(|header| !Self::matches_any(&exclude, header))
+            .collect::<Vec<_>>();
+        let overrides = build_configuration
+            .overrides
+            .into_iter()
+            .map(|configuration| {
+                Pattern::new(&configuration.sources)
+                    .map(|pattern| SourceOverride {
+                        pattern,
+                        configuration,
+                    })
+                    .map_err(|err| {
+                        ProjectOpenError::InvalidBuildConfiguration(
+                            BuildConfigurationError::IncorrectWildcard(String::from(err.msg)),
+                        )
+                    })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
 
         for dependency in &dependencies {
             if let Dependency::Project(project) = dependency {
@@ -115,97 +622,894 @@ impl Project {
 
         Ok(Project {
             base_path: PathBuf::from(path.as_ref()),
+            manifest: build_configuration_file_path,
             name: build_configuration.project.name,
             description: build_configuration.project.description,
             author: build_configuration.project.author,
+            version: build_configuration.project.version,
             language: build_configuration.project.language,
             distribution: build_configuration.project.distribution,
             sources,
+            binaries,
+            tests,
+            exclude,
+            test_arguments: build_configuration.test.arguments,
+            run_environment: build_configuration.run.env,
             includes,
+            public_includes,
             dependencies,
+            install_headers,
+            precompiled_header: build_configuration.project.precompiled_header,
+            defines: build_configuration.project.defines,
+            libraries: build_configuration.project.libraries,
+            library_search_paths: build_configuration
+                .project
+                .library_search_paths
+                .into_iter()
+                .map(|library_search_path| {
+                    base_path
+                        .join(library_search_path)
+                        .to_string_lossy()
+                        .into_owned()
+                })
+                .collect(),
+            link_flags: build_configuration.project.link_flags,
+            frameworks: build_configuration.project.frameworks,
             optimization: build_configuration.project.optimization,
+            debug_info: build_configuration.project.debug_info,
+            target_cpu: build_configuration.project.target_cpu,
+            tune_cpu: build_configuration.project.tune_cpu,
             enable_all_warnings: build_configuration.project.enable_all_warnings,
             treat_all_warnings_as_errors: build_configuration.project.treat_all_warnings_as_errors,
+            strip: build_configuration.project.strip,
+            coverage: build_configuration.project.coverage,
+            rpath: build_configuration.project.rpath.unwrap_or(true),
+            threads: build_configuration.project.threads,
+            math: build_configuration.project.math,
+            static_runtime: build_configuration.project.static_runtime,
+            fully_static: build_configuration.project.fully_static,
+            lto: build_configuration.project.lto,
+            unity: build_configuration.project.unity,
+            unity_batch_size: build_configuration
+                .project
+                .unity_batch_size
+                .unwrap_or(DEFAULT_UNITY_BATCH_SIZE)
+                .max(1),
+            warnings: build_configuration.project.warnings,
+            sanitizers: build_configuration.project.sanitizers,
             has_project_configuration_changed,
+            configuration_hash: build_configuration_hash,
+            toolchain,
             hashes,
+            file_hashes,
+            current_file_hashes: Mutex::new(HashMap::new()),
             c: build_configuration.c,
             cpp: build_configuration.cpp,
             gcc: build_configuration.gcc,
             gpp: build_configuration.gpp,
+            profile: selected_profile,
+            overrides,
+            output_directory,
+            linker: build_configuration.project.linker,
+        })
+    }
+
+    /// The source may be given relative to the current directory or to the project, as long as
+    /// it resolves to one of the project's sources.
+    pub(crate) fn find_source(&self, source: &str) -> Option<&str> {
+        let canonical_source = Path::new(source)
+            .canonicalize()
+            .or_else(|_| self.base_path.join(source).canonicalize())
+            .ok()?;
+
+        self.sources
+            .iter()
+            .find(|project_source| {
+                self.base_path
+                    .join(project_source)
+                    .canonicalize()
+                    .is_ok_and(|path| path == canonical_source)
+            })
+            .map(String::as_str)
+    }
+
+    pub(crate) fn is_binary_source(&self, source: &str) -> bool {
+        self.binaries.iter().any(|binary| {
+            binary
+                .sources
+                .iter()
+                .any(|binary_source| binary_source == source)
+        })
+    }
+
+    /// Takes a path relative to the project, like the `exclude` patterns themselves.
+    pub(crate) fn is_excluded(&self, path: &str) -> bool {
+        Self::matches_any(&self.exclude, path)
+    }
+
+    /// A test is named after its source's path without the extension, e.g. `tests/a/check`, so
+    /// that tests in different directories can share a file name.
+    pub(crate) fn test_name(test: &str) -> String {
+        Path::new(test)
+            .with_extension("")
+            .components()
+            .map(|component| component.as_os_str().to_string_lossy())
+            .join("/")
+    }
+
+    fn matches_any(patterns: &[Pattern], path: &str) -> bool {
+        patterns.iter().any(|pattern| {
+            pattern.matches_path_with(
+                Path::new(path),
+                MatchOptions {
+                    require_literal_separator: true,
+                    ..Default::default()
+                },
+            )
         })
     }
 
-    fn read_hashes(base_path: &Path) -> HashMap<String, Hash> {
-        fs::read_to_string(base_path.join(BAKERY_HASHES_FILE))
-            .map(|hashes_content| {
-                serde_json::from_str::<HashMap<String, String>>(&hashes_content)
+    pub(crate) fn build_directory(&self) -> PathBuf {
+        Self::profile_directory(
+            self.output_directory.join(BAKERY_BUILD_DIRECTORY),
+            self.profile.as_ref(),
+        )
+    }
+
+    pub(crate) fn cache_directory(&self) -> PathBuf {
+        Self::profile_directory(
+            self.output_directory.join(BAKERY_CACHE_DIRECTORY),
+            self.profile.as_ref(),
+        )
+    }
+
+    pub(crate) fn hashes_file(&self) -> PathBuf {
+        self.cache_directory().join(BAKERY_HASHES_FILE)
+    }
+
+    pub(crate) fn file_hashes_file(&self) -> PathBuf {
+        self.cache_directory().join(BAKERY_FILE_HASHES_FILE)
+    }
+
+    fn profile_directory(directory: PathBuf, profile: Option<&Profile>) -> PathBuf {
+        match profile {
+            Some(profile) => directory.join(&profile.name),
+            None => directory,
+        }
+    }
+
+    /// Merges the target tables matching the current target into the project configuration and
+    /// returns the source patterns of the others, which must not be built even when a broader
+    /// pattern matches them.
+    fn apply_target_configurations(
+        build_configuration: &mut BuildConfiguration,
+    ) -> Result<Vec<String>, ProjectOpenError> {
+        let target_os = config::target_os();
+        let mut excluded_source_patterns = Vec::new();
+
+        for (predicate, target) in mem::take(&mut build_configuration.target) {
+            let matches = config::matches_target(&predicate, &target_os).ok_or_else(|| {
+                ProjectOpenError::InvalidBuildConfiguration(
+                    BuildConfigurationError::InvalidTargetPredicate(predicate.clone()),
+                )
+            })?;
+
+            if !matches {
+                excluded_source_patterns.extend(target.sources);
+
+                continue;
+            }
+
+            let project = &mut build_configuration.project;
+
+            project.sources.extend(target.sources);
+            project.includes.extend(target.includes);
+            project.dependencies.extend(target.dependencies);
+            project.defines.extend(target.defines);
+            project.frameworks.extend(target.frameworks);
+        }
+
+        Ok(excluded_source_patterns)
+    }
+
+    fn resolve_profile(
+        build_configuration: &BuildConfiguration,
+        profile: Option<&str>,
+    ) -> Result<Option<Profile>, ProjectOpenError> {
+        let Some(name) = profile else {
+            return Ok(None);
+        };
+
+        let configuration = match (
+            build_configuration.profiles.get(name).cloned(),
+            ProfileConfiguration::builtin(name),
+        ) {
+            (Some(mut configuration), Some(builtin)) => {
+                configuration.optimization = configuration.optimization.or(builtin.optimization);
+                configuration.coverage = configuration.coverage.or(builtin.coverage);
+
+                configuration
+            }
+            (Some(configuration), None) | (None, Some(configuration)) => configuration,
+            (None, None) => {
+                return Err(ProjectOpenError::InvalidBuildConfiguration(
+                    BuildConfigurationError::UnknownProfile(String::from(name)),
+                ))
+            }
+        };
+
+        Ok(Some(Profile {
+            name: String::from(name),
+            configuration,
+        }))
+    }
+
+    /// Hashes the build configuration without its cosmetic fields, comments and formatting, so
+    /// that editing those doesn't count as a configuration change.
+    fn hash_build_configuration(mut build_configuration: toml::Table) -> Hash {
+        if let Some(toml::Value::Table(project)) = build_configuration.get_mut("project") {
+            for field in COSMETIC_PROJECT_FIELDS {
+                project.remove(field);
+            }
+        }
+
+        // Only affects how the executable is run
+        build_configuration.remove("run");
+
+        blake3::hash(build_configuration.to_string().as_bytes())
+    }
+
+    /// Variables that are defined no matter the environment, and take precedence over it.
+    fn builtin_variables(base_path: &Path) -> HashMap<&'static str, String> {
+        HashMap::from([
+            (
+                "PROJECT_ROOT",
+                base_path
+                    .canonicalize()
+                    .unwrap_or_else(|_| base_path.to_path_buf())
+                    .to_string_lossy()
+                    .into_owned(),
+            ),
+            ("TARGET_OS", config::target_os()),
+        ])
+    }
+
+    /// Expands `${NAME}` in the strings of [`INTERPOLATED_FIELDS`] with a built-in variable or
+    /// otherwise an environment variable. `$${` stands for a literal `${`.
+    fn interpolate_variables(
+        table: &mut toml::Table,
+        variables: &HashMap<&'static str, String>,
+    ) -> Result<(), ProjectOpenError> {
+        fn interpolate(
+            field: &str,
+            value: &mut toml::Value,
+            variables: &HashMap<&'static str, String>,
+        ) -> Result<(), ProjectOpenError> {
+            match value {
+                toml::Value::String(string) if INTERPOLATED_FIELDS.contains(&field) => {
+                    *string = expand(string, variables)?
+                }
+                toml::Value::Array(values) => {
+                    for value in values {
+                        interpolate(field, value, variables)?;
+                    }
+                }
+                toml::Value::Table(table) => {
+                    for (field, value) in table.iter_mut() {
+                        interpolate(field, value, variables)?;
+                    }
+                }
+                _ => {}
+            }
+
+            Ok(())
+        }
+
+        fn expand(
+            string: &str,
+            variables: &HashMap<&'static str, String>,
+        ) -> Result<String, ProjectOpenError> {
+            let mut expanded = String::new();
+            let mut rest = string;
+
+            while let Some(index) = rest.find('$') {
+                expanded.push_str(&rest[..index]);
+                rest = &rest[index..];
+
+                if let Some(escaped) = rest.strip_prefix("$${") {
+                    expanded.push_str("${");
+                    rest = escaped;
+                } else if let Some((name, remainder)) = rest
+                    .strip_prefix("${")
+                    .and_then(|variable| variable.split_once('}'))
+                {
+                    let value = match variables.get(name) {
+                        Some(value) => value.clone(),
+                        None => env::var(name).map_err(|_| {
+                            ProjectOpenError::InvalidBuildConfiguration(
+                                BuildConfigurationError::UndefinedVariable(name.to_owned()),
+                            )
+                        })?,
+                    };
+
+                    expanded.push_str(&value);
+                    rest = remainder;
+                } else {
+                    expanded.push('$');
+                    rest = &rest[1..];
+                }
+            }
+
+            expanded.push_str(rest);
+
+            Ok(expanded)
+        }
+
+        for (field, value) in table.iter_mut() {
+            interpolate(field, value, variables)?;
+        }
+
+        Ok(())
+    }
+
+    /// A corrupt hashes file only costs a rebuild, of everything when it can't be parsed at all
+    /// or of the sources whose hashes are malformed.
+    fn read_hashes(hashes_file_path: &Path) -> HashMap<String, Hash> {
+        let Ok(hashes_content) = fs::read_to_string(hashes_file_path) else {
+            return HashMap::new();
+        };
+        let Ok(hashes) = serde_json::from_str::<HashMap<String, String>>(&hashes_content) else {
+            log_error!(
+                "Warning: {} is corrupt, rebuilding everything",
+                hashes_file_path.display()
+            );
+
+            return HashMap::new();
+        };
+
+        hashes
+            .into_iter()
+            .filter_map(|(key, value)| match Hash::from_hex(&value) {
+                Ok(hash) => Some((key, hash)),
+                Err(_) => {
+                    log_error!(
+                        "Warning: ignoring the malformed hash of {} in {}",
+                        key,
+                        hashes_file_path.display()
+                    );
+
+                    None
+                }
+            })
+            .collect::<HashMap<_, _>>()
+    }
+
+    fn read_file_hashes(file_hashes_file_path: &Path) -> HashMap<String, FileHash> {
+        fs::read_to_string(file_hashes_file_path)
+            .map(|file_hashes_content| {
+                serde_json::from_str::<HashMap<String, FileHash>>(&file_hashes_content)
                     .unwrap_or_default()
-                    .into_iter()
-                    .map(|(key, value)| (key, Hash::from_hex(value).unwrap()))
-                    .collect::<HashMap<_, _>>()
             })
             .unwrap_or_default()
     }
 
+    fn validate_sanitizers(
+        build_configuration: &BuildConfiguration,
+        profile: Option<&Profile>,
+    ) -> Result<(), ProjectOpenError> {
+        let sanitizers = build_configuration
+            .project
+            .sanitizers
+            .iter()
+            .chain(
+                profile
+                    .map(|profile| profile.configuration.sanitizers.as_slice())
+                    .unwrap_or_default(),
+            )
+            .collect::<Vec<_>>();
+
+        for (index, sanitizer) in sanitizers.iter().enumerate() {
+            if let Some(conflicting_sanitizer) = sanitizers[index + 1..]
+                .iter()
+                .find(|other| sanitizer.conflicts_with(other))
+            {
+                return Err(ProjectOpenError::InvalidBuildConfiguration(
+                    BuildConfigurationError::ConflictingSanitizers(
+                        String::from(sanitizer.name()),
+                        String::from(conflicting_sanitizer.name()),
+                    ),
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Bakery's own flags are emitted alongside the additional arguments, so duplicating them
+    /// there makes the outcome depend on argument order.
+    fn validate_additional_arguments(
+        build_configuration: &BuildConfiguration,
+        profile: Option<&Profile>,
+    ) -> Result<(), ProjectOpenError> {
+        let profile_configuration = profile.map(|profile| &profile.configuration);
+        let gcc_configurations = [
+            build_configuration.gcc.as_ref(),
+            profile_configuration.and_then(|configuration| configuration.gcc.as_ref()),
+        ];
+        let gpp_configurations = [
+            build_configuration.gpp.as_ref(),
+            profile_configuration.and_then(|configuration| configuration.gpp.as_ref()),
+        ];
+        let conflicts = gcc_configurations
+            .into_iter()
+            .flatten()
+            .flat_map(|gcc| {
+                gcc.additional_pre_arguments
+                    .iter()
+                    .chain(&gcc.additional_post_arguments)
+            })
+            .chain(gpp_configurations.into_iter().flatten().flat_map(|gpp| {
+                gpp.additional_pre_arguments
+                    .iter()
+                    .chain(&gpp.additional_post_arguments)
+            }))
+            .chain(
+                build_configuration
+                    .overrides
+                    .iter()
+                    .flat_map(|configuration| {
+                        configuration
+                            .additional_pre_arguments
+                            .iter()
+                            .chain(&configuration.additional_post_arguments)
+                    }),
+            )
+            .filter_map(|argument| {
+                Self::managed_equivalent(argument).map(|equivalent| {
+                    format!("{argument} is managed by bakery, use {equivalent} instead")
+                })
+            })
+            .unique()
+            .collect::<Vec<_>>();
+
+        if conflicts.is_empty() {
+            return Ok(());
+        }
+
+        if build_configuration.project.strict_arguments {
+            return Err(ProjectOpenError::InvalidBuildConfiguration(
+                BuildConfigurationError::ManagedArguments(conflicts),
+            ));
+        }
+
+        for conflict in conflicts {
+            log_error!("Warning: additional argument {conflict}");
+        }
+
+        Ok(())
+    }
+
+    /// A pattern matching nothing usually means a typo, which would otherwise go unnoticed as
+    /// the build happily compiles whatever the other patterns matched.
+    fn validate_source_patterns(
+        build_configuration: &BuildConfiguration,
+        empty_patterns: Vec<String>,
+    ) -> Result<(), ProjectOpenError> {
+        if empty_patterns.is_empty() {
+            return Ok(());
+        }
+
+        if build_configuration.project.strict_sources {
+            return Err(ProjectOpenError::InvalidBuildConfiguration(
+                BuildConfigurationError::EmptySourcePatterns(empty_patterns),
+            ));
+        }
+
+        for pattern in empty_patterns {
+            log_error!("Warning: source pattern {pattern} doesn't match any files");
+        }
+
+        Ok(())
+    }
+
+    fn managed_equivalent(argument: &str) -> Option<&'static str> {
+        if argument.starts_with("-std=") {
+            Some("the standard of [c] or [cpp]")
+        } else if argument.starts_with("-O") {
+            Some("optimization")
+        } else if argument.starts_with("-W")
+            && !["-Wl,", "-Wa,", "-Wp,"]
+                .iter()
+                .any(|prefix| argument.starts_with(prefix))
+        {
+            Some("enableAllWarnings, treatAllWarningsAsErrors or warnings")
+        } else if argument.starts_with("-I") {
+            Some("includes")
+        } else if argument == "-c" {
+            Some("sources, which are always compiled separately")
+        } else if argument.starts_with("-o") {
+            Some("outputDirectory")
+        } else {
+            None
+        }
+    }
+
+    #[allow(clippy::too_many_arguments)]
     fn resolve_dependencies(
         base_path: &Path,
+        output_directory: &Path,
         build_configuration: &BuildConfiguration,
+        profile: Option<&str>,
+        visited: &mut Vec<PathBuf>,
+        opened: &mut HashMap<PathBuf, Arc<Project>>,
+        pkg_config_packages: &mut HashMap<String, PkgConfigPackage>,
+        members: &HashMap<String, WorkspaceMember>,
+        inherited_toolchain: Option<&ProjectToolchainConfiguration>,
     ) -> Result<Vec<Dependency>, ProjectOpenError> {
         build_configuration
             .project
             .dependencies
             .iter()
             .map(|dependency| match dependency {
-                config::Dependency::System { name } => {
-                    Ok(Dependency::System { name: name.clone() })
+                config::Dependency::System { name, kind } => Ok(Dependency::System {
+                    name: name.clone(),
+                    kind: kind.unwrap_or_default(),
+                }),
+                config::Dependency::Prebuilt {
+                    include_directory,
+                    library_directory,
+                    libraries,
+                } => {
+                    let resolve = |directory: &str| {
+                        let path = base_path.join(directory);
+
+                        if path.is_dir() {
+                            Ok(path.to_string_lossy().into_owned())
+                        } else {
+                            Err(ProjectOpenError::InvalidBuildConfiguration(
+                                BuildConfigurationError::MissingPrebuiltDirectory(
+                                    path.to_string_lossy().into_owned(),
+                                ),
+                            ))
+                        }
+                    };
+
+                    Ok(Dependency::Prebuilt {
+                        include_directory: resolve(include_directory)?,
+                        library_directory: resolve(library_directory)?,
+                        libraries: libraries.clone(),
+                    })
+                }
+                config::Dependency::PkgConfig { name } => {
+                    let package = match pkg_config_packages.get(name) {
+                        Some(package) => package.clone(),
+                        None => {
+                            let package = PkgConfigPackage {
+                                includes: Self::query_pkg_config(name, "--cflags-only-I")?
+                                    .into_iter()
+                                    .map(|include| include.trim_start_matches("-I").to_owned())
+                                    .collect(),
+                                compile_flags: Self::query_pkg_config(name, "--cflags-only-other")?,
+                                link_flags: Self::query_pkg_config(name, "--libs")?,
+                            };
+
+                            pkg_config_packages.insert(name.clone(), package.clone());
+
+                            package
+                        }
+                    };
+
+                    Ok(Dependency::PkgConfig {
+                        name: name.clone(),
+                        includes: package.includes,
+                        compile_flags: package.compile_flags,
+                        link_flags: package.link_flags,
+                    })
+                }
+                config::Dependency::Local { .. }
+                | config::Dependency::Member { .. }
+                | config::Dependency::Git { .. } => {
+                    let (dependency_path, manifest_name) = match dependency {
+                        config::Dependency::Member { name } => {
+                            let member = members.get(name).ok_or_else(|| {
+                                ProjectOpenError::InvalidBuildConfiguration(
+                                    BuildConfigurationError::UnknownMember(name.clone()),
+                                )
+                            })?;
+
+                            (member.path.clone(), PathBuf::from(BUILD_CONFIGURATION_FILE))
+                        }
+                        // A path to a file names the dependency's manifest, a directory uses
+                        // the default one
+                        config::Dependency::Local { path } if base_path.join(path).is_file() => {
+                            let mut dependency_path = base_path.join(path);
+                            let manifest_name = PathBuf::from(dependency_path.file_name().unwrap());
+
+                            dependency_path.pop();
+
+                            (dependency_path, manifest_name)
+                        }
+                        config::Dependency::Local { path } => (
+                            base_path.join(path),
+                            PathBuf::from(BUILD_CONFIGURATION_FILE),
+                        ),
+                        config::Dependency::Git { url, rev } => (
+                            Self::checkout_git_dependency(
+                                url,
+                                rev,
+                                &output_directory.join(BAKERY_CACHE_DIRECTORY),
+                            )?,
+                            PathBuf::from(BUILD_CONFIGURATION_FILE),
+                        ),
+                        _ => unreachable!(),
+                    };
+                    let canonical_manifest_path = dependency_path
+                        .canonicalize()
+                        .map_err(ProjectOpenError::InvalidProjectPath)?
+                        .join(&manifest_name);
+
+                    if let Some(project) = opened.get(&canonical_manifest_path) {
+                        return Ok(Dependency::Project(Arc::clone(project)));
+                    }
+
+                    // Members keep their artifacts in the workspace however they're reached
+                    let output_directory = members
+                        .values()
+                        .find(|member| member.manifest == canonical_manifest_path)
+                        .map(|member| member.output_directory.as_path());
+
+                    let project = Arc::new(Project::open_with_visited(
+                        dependency_path,
+                        &manifest_name,
+                        profile,
+                        output_directory,
+                        visited,
+                        opened,
+                        pkg_config_packages,
+                        members,
+                        inherited_toolchain,
+                    )?);
+
+                    opened.insert(canonical_manifest_path, Arc::clone(&project));
+
+                    Ok(Dependency::Project(project))
                 }
-                config::Dependency::Local { path } => Project::open(base_path.join(path))
-                    .map(|project| Dependency::Project(Box::new(project))),
             })
             .collect::<Result<Vec<_>, _>>()
     }
 
-    fn resolve_sources(
+    /// Clones the repository into the cache the first time, and only fetches it again once the
+    /// revision isn't part of the clone yet. A branch name is therefore resolved against the
+    /// last fetch, only commits and tags pin the dependency.
+    fn checkout_git_dependency(
+        url: &str,
+        rev: &str,
+        cache_directory: &Path,
+    ) -> Result<PathBuf, ProjectOpenError> {
+        let checkout = cache_directory
+            .join(GIT_DIRECTORY)
+            .join(&blake3::hash(url.as_bytes()).to_hex()[..GIT_DIRECTORY_HASH_LENGTH]);
+        let commit = format!("{}^{{commit}}", rev);
+
+        if !checkout.join(".git").exists() {
+            fs::create_dir_all(&checkout)
+                .map_err(|err| ProjectOpenError::GitError(url.to_owned(), err.to_string()))?;
+
+            Self::run_git(
+                url,
+                &checkout,
+                &["clone", "--quiet", "--no-checkout", url, "."],
+            )?;
+        }
+
+        let commit = match Self::run_git(url, &checkout, &["rev-parse", "--verify", &commit]) {
+            Ok(commit) => commit,
+            Err(_) => {
+                Self::run_git(url, &checkout, &["fetch", "--quiet", "--tags", "origin"])?;
+                Self::run_git(url, &checkout, &["rev-parse", "--verify", &commit]).map_err(
+                    |_| {
+                        ProjectOpenError::GitError(
+                            url.to_owned(),
+                            format!("revision {} doesn't exist", rev),
+                        )
+                    },
+                )?
+            }
+        };
+
+        // Only rewrites the files that differ from the commit, so unchanged ones keep their hashes
+        Self::run_git(
+            url,
+            &checkout,
+            &["checkout", "--quiet", "--force", "--detach", &commit],
+        )?;
+
+        Ok(checkout)
+    }
+
+    /// Runs git in the given directory, returning what it printed.
+    fn run_git(
+        url: &str,
+        directory: &Path,
+        arguments: &[&str],
+    ) -> Result<String, ProjectOpenError> {
+        let output = Command::new("git")
+            .arg("-C")
+            .arg(directory)
+            .args(arguments)
+            .output()
+            .map_err(|err| ProjectOpenError::GitError(url.to_owned(), err.to_string()))?;
+
+        if !output.status.success() {
+            return Err(ProjectOpenError::GitError(
+                url.to_owned(),
+                String::from_utf8_lossy(&output.stderr).trim().to_owned(),
+            ));
+        }
+
+        Ok(String::from_utf8_lossy(&output.stdout).trim().to_owned())
+    }
+
+    /// Relative paths to tools are relative to the project, plain names are still looked up.
+    fn resolve_toolchain(
+        base_path: &Path,
+        mut toolchain: ProjectToolchainConfiguration,
+    ) -> ProjectToolchainConfiguration {
+        for location in [
+            &mut toolchain.gcc_location,
+            &mut toolchain.gpp_location,
+            &mut toolchain.ar_location,
+        ]
+        .into_iter()
+        .flatten()
+        {
+            if Path::new(location).is_relative() && Path::new(location).components().count() > 1 {
+                *location = base_path.join(&*location).to_string_lossy().into_owned();
+            }
+        }
+
+        toolchain
+    }
+
+    fn query_pkg_config(name: &str, flag: &str) -> Result<Vec<String>, ProjectOpenError> {
+        let pkg_config_location =
+            env::var("PKG_CONFIG").unwrap_or_else(|_| String::from("pkg-config"));
+
+        let output = Command::new(pkg_config_location)
+            .arg(flag)
+            .arg(name)
+            .output()
+            .map_err(|err| ProjectOpenError::PkgConfigError(name.to_owned(), err.to_string()))?;
+
+        if !output.status.success() {
+            return Err(ProjectOpenError::PkgConfigError(
+                name.to_owned(),
+                String::from_utf8_lossy(&output.stderr).trim().to_owned(),
+            ));
+        }
+
+        Ok(String::from_utf8_lossy(&output.stdout)
+            .split_whitespace()
+            .map(String::from)
+            .collect())
+    }
+
+    fn resolve_binaries(
         base_path: &Path,
         build_configuration: &BuildConfiguration,
+        excluded_sources: &[String],
+        exclude: &[Pattern],
+        empty_patterns: &mut Vec<String>,
+    ) -> Result<Vec<Binary>, ProjectOpenError> {
+        if !build_configuration.binaries.is_empty()
+            && build_configuration.project.distribution != Distribution::Executable
+        {
+            return Err(ProjectOpenError::InvalidBuildConfiguration(
+                BuildConfigurationError::BinariesRequireExecutable,
+            ));
+        }
+
+        let mut binaries = Vec::<Binary>::new();
+
+        for binary in &build_configuration.binaries {
+            if !NAME_REGEX.is_match(&binary.name)
+                || binaries.iter().any(|other| other.name == binary.name)
+            {
+                return Err(ProjectOpenError::InvalidBuildConfiguration(
+                    BuildConfigurationError::InvalidBinaryName(binary.name.clone()),
+                ));
+            }
+
+            let sources = Self::resolve_sources(
+                base_path,
+                &binary.sources,
+                build_configuration.project.allow_external_paths,
+                build_configuration.project.allow_symlinks,
+                Some(empty_patterns),
+            )?
+            .into_iter()
+            .filter(|source| {
+                !excluded_sources.contains(source) && !Self::matches_any(exclude, source)
+            })
+            .collect();
+
+            binaries.push(Binary {
+                name: binary.name.clone(),
+                sources,
+            });
+        }
+
+        Ok(binaries)
+    }
+
+    /// Patterns matching no files are collected into `empty_patterns` when it's given, except
+    /// for literal paths, which are reported as incorrect sources right away.
+    /// Sources have to lie inside the project once symlinks and `..` are resolved, unless
+    /// external paths are allowed. Allowed symlinks only have to be placed inside the project,
+    /// wherever they point to, as linking shared files into a project is what they're used for.
+    fn resolve_sources(
+        base_path: &Path,
+        patterns: &[String],
+        allow_external_paths: bool,
+        allow_symlinks: bool,
+        mut empty_patterns: Option<&mut Vec<String>>,
     ) -> Result<Vec<String>, ProjectOpenError> {
-        build_configuration
-            .project
-            .sources
+        patterns
             .iter()
-            .flat_map(|source| {
-                glob(&base_path.join(source).to_string_lossy())
-                    .map(|paths| {
-                        paths
-                            .into_iter()
-                            .map(|path| {
-                                path.map(|path| {
-                                    path.relative_to(base_path)
-                                        .unwrap()
-                                        .to_string_lossy()
-                                        .into_owned()
-                                })
-                                .map_err(|_| {
-                                    ProjectOpenError::InvalidBuildConfiguration(
-                                        BuildConfigurationError::IncorrectSource(source.clone()),
-                                    )
-                                })
-                            })
-                            .collect::<Result<Vec<_>, _>>()
-                    })
+            .map(|source| {
+                let sources = glob(&base_path.join(source).to_string_lossy())
                     .map_err(|err| {
                         ProjectOpenError::InvalidBuildConfiguration(
                             BuildConfigurationError::IncorrectWildcard(String::from(err.msg)),
                         )
+                    })?
+                    .map(|path| {
+                        path.ok()
+                            .and_then(|path| Self::relative_source(&path, base_path))
+                            .map(|path| path.to_string_lossy().into_owned())
+                            .ok_or_else(|| {
+                                ProjectOpenError::InvalidBuildConfiguration(
+                                    BuildConfigurationError::IncorrectSource(source.clone()),
+                                )
+                            })
                     })
+                    .collect::<Result<Vec<_>, _>>()?;
+
+                if let (true, Some(empty_patterns)) = (sources.is_empty(), &mut empty_patterns) {
+                    if Pattern::escape(source) == *source {
+                        return Err(ProjectOpenError::InvalidBuildConfiguration(
+                            BuildConfigurationError::IncorrectSource(source.clone()),
+                        ));
+                    }
+
+                    empty_patterns.push(source.clone());
+                }
+
+                Ok(sources)
             })
             .collect::<Result<Vec<_>, _>>()?
             .into_iter()
             .flatten()
+            // Overlapping patterns (e.g. `src/*.c` and `src/**/*.c`) must not compile a source twice
+            .unique()
             .map(|source| {
                 let path = base_path.join(&source);
+                let is_inside_project = if path.is_symlink() {
+                    allow_symlinks
+                        && path
+                            .parent()
+                            .is_some_and(|parent| Self::is_inside_project(base_path, parent))
+                } else {
+                    Self::is_inside_project(base_path, &path)
+                };
 
-                if path.exists() && path.is_file() && path.is_relative() && !path.is_symlink() {
+                // `exists` and `is_file` follow symlinks, so the target has to be a regular file
+                if path.exists()
+                    && path.is_file()
+                    && Path::new(&source).is_relative()
+                    && (!path.is_symlink() || allow_symlinks)
+                    && (allow_external_paths || is_inside_project)
+                {
                     Ok(source)
                 } else {
                     Err(ProjectOpenError::InvalidBuildConfiguration(
@@ -216,38 +1520,132 @@ impl Project {
             .collect::<Result<Vec<String>, _>>()
     }
 
+    fn resolve_install_headers(
+        base_path: &Path,
+        build_configuration: &BuildConfiguration,
+    ) -> Result<Vec<String>, ProjectOpenError> {
+        let mut install_headers = Vec::new();
+
+        for install_header in &build_configuration.project.install_headers {
+            let paths = glob(&base_path.join(install_header).to_string_lossy()).map_err(|err| {
+                ProjectOpenError::InvalidBuildConfiguration(
+                    BuildConfigurationError::IncorrectWildcard(String::from(err.msg)),
+                )
+            })?;
+
+            install_headers.extend(
+                paths
+                    .flatten()
+                    .filter(|path| path.is_file())
+                    .filter_map(|path| path.relative_to(base_path))
+                    .map(|path| path.to_string_lossy().into_owned()),
+            );
+        }
+
+        Ok(install_headers.into_iter().unique().collect())
+    }
+
+    /// Returns every include directory of the project, followed by the public ones. Only the
+    /// public include directories of dependencies are visible to the project.
     fn resolve_includes(
         base_path: &Path,
         build_configuration: &BuildConfiguration,
         dependencies: &[Dependency],
-    ) -> Result<Vec<String>, ProjectOpenError> {
-        build_configuration
-            .project
-            .includes
-            .clone()
+    ) -> Result<(Vec<String>, Vec<String>), ProjectOpenError> {
+        // Includes may be absolute, such as those expanded from `${PROJECT_ROOT}`, as own includes
+        // are kept inside of the project by `resolve` instead
+        let validate = |include: String| {
+            let path = Path::new(&include);
+
+            if path.exists() && path.is_dir() && !path.is_symlink() {
+                Ok(include)
+            } else {
+                Err(ProjectOpenError::InvalidBuildConfiguration(
+                    BuildConfigurationError::IncorrectInclude(include),
+                ))
+            }
+        };
+        // The public includes of dependencies were already checked against their own projects
+        let resolve = |includes: &[String]| {
+            includes
+                .iter()
+                .map(|include| base_path.join(include).to_string_lossy().into_owned())
+                .map(|include| {
+                    if build_configuration.project.allow_external_paths
+                        || Self::is_inside_project(base_path, Path::new(&include))
+                    {
+                        validate(include)
+                    } else {
+                        Err(ProjectOpenError::InvalidBuildConfiguration(
+                            BuildConfigurationError::IncorrectInclude(include),
+                        ))
+                    }
+                })
+                .collect::<Result<Vec<_>, _>>()
+        };
+
+        let configuration = &build_configuration.project;
+        let own_public_includes = resolve(&configuration.includes)?
             .into_iter()
-            .map(|include| base_path.join(include).to_string_lossy().into_owned())
-            .chain(
-                dependencies
-                    .iter()
-                    .filter_map(|dependency| match dependency {
-                        Dependency::Project(project) => Some(project),
-                        _ => None,
-                    })
-                    .flat_map(|dependency_project| dependency_project.includes.clone().into_iter()),
+            .chain(resolve(&configuration.public_includes)?)
+            .collect::<Vec<_>>();
+        let private_includes = resolve(&configuration.private_includes)?;
+        let dependency_includes = dependencies
+            .iter()
+            .filter_map(|dependency| match dependency {
+                Dependency::Project(project) => Some(project),
+                _ => None,
+            })
+            .flat_map(|dependency_project| dependency_project.public_includes.clone())
+            .map(validate)
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let includes = Self::deduplicate_includes(
+            own_public_includes
+                .iter()
+                .chain(&private_includes)
+                .chain(&dependency_includes)
+                .cloned(),
+        );
+        let public_includes =
+            Self::deduplicate_includes(own_public_includes.into_iter().chain(dependency_includes));
+
+        Ok((includes, public_includes))
+    }
+
+    /// Symlinks are made relative through their directory, so that they keep their own name
+    /// rather than the one of the file they point to.
+    fn relative_source(path: &Path, base_path: &Path) -> Option<PathBuf> {
+        if path.is_symlink() {
+            Some(
+                path.parent()?
+                    .relative_to(base_path)?
+                    .join(path.file_name()?),
             )
-            .map(|include| {
-                let path = Path::new(&include);
+        } else {
+            path.relative_to(base_path)
+        }
+    }
 
-                if path.exists() && path.is_dir() && path.is_relative() && !path.is_symlink() {
-                    Ok(include)
-                } else {
-                    Err(ProjectOpenError::InvalidBuildConfiguration(
-                        BuildConfigurationError::IncorrectInclude(include),
-                    ))
-                }
+    fn is_inside_project(base_path: &Path, path: &Path) -> bool {
+        match (base_path.canonicalize(), path.canonicalize()) {
+            (Ok(base_path), Ok(path)) => path.starts_with(base_path),
+            _ => false,
+        }
+    }
+
+    /// Dependencies sharing a dependency each export its include directories, so the same
+    /// directory may be reached through several paths. The first occurrence is kept, as the
+    /// order decides which header shadows which.
+    fn deduplicate_includes(includes: impl IntoIterator<Item = String>) -> Vec<String> {
+        includes
+            .into_iter()
+            .unique_by(|include| {
+                Path::new(include)
+                    .canonicalize()
+                    .unwrap_or_else(|_| PathBuf::from(include))
             })
-            .collect::<Result<Vec<_>, _>>()
+            .collect()
     }
 }
 
@@ -255,8 +1653,18 @@ impl Project {
 pub(crate) enum ProjectOpenError {
     #[error("specificed path doesn't contain bakery.toml")]
     InvalidProjectPath(io::Error),
+    #[error("manifest {} doesn't exist", .0.display())]
+    ManifestNotFound(PathBuf),
+    #[error("lockfile {} is missing or out of date", .0.display())]
+    OutdatedLockfile(PathBuf),
     #[error("the project's build configuration is invalid: {0:?}")]
     InvalidBuildConfiguration(BuildConfigurationError),
+    #[error("found a dependency cycle: {}", .0.join(" -> "))]
+    DependencyCycle(Vec<String>),
+    #[error("failed to query pkg-config for {0}: {1}")]
+    PkgConfigError(String, String),
+    #[error("failed to fetch {0}: {1}")]
+    GitError(String, String),
 }
 
 #[derive(Error, Debug)]
@@ -273,6 +1681,34 @@ pub(crate) enum BuildConfigurationError {
     IncorrectInclude(String),
     #[error("dependency {0} is not a library")]
     DependencyIsNotALibrary(String),
+    #[error("profile {0} is not defined")]
+    UnknownProfile(String),
+    #[error("sanitizers {0} and {1} can't be used together")]
+    ConflictingSanitizers(String, String),
+    #[error("version {0} is not a valid semantic version")]
+    InvalidVersion(String),
+    #[error("linker {0} is not supported")]
+    InvalidLinker(String),
+    #[error("target predicate {0} is not supported")]
+    InvalidTargetPredicate(String),
+    #[error("frameworks can only be linked on macOS")]
+    FrameworksRequireMacos,
+    #[error("only executables can declare [[bin]] targets")]
+    BinariesRequireExecutable,
+    #[error("binary name {0} is invalid or used twice")]
+    InvalidBinaryName(String),
+    #[error("{0} is not a member of the workspace")]
+    UnknownMember(String),
+    #[error("source patterns match no files: {}", .0.join(", "))]
+    EmptySourcePatterns(Vec<String>),
+    #[error("additional arguments duplicate flags managed by bakery: {}", .0.join(", "))]
+    ManagedArguments(Vec<String>),
+    #[error("prebuilt dependency directory {0} doesn't exist")]
+    MissingPrebuiltDirectory(String),
+    #[error("variable {0} is not defined")]
+    UndefinedVariable(String),
+    #[error("test name {0} is used twice")]
+    DuplicateTestName(String),
 }
 
 #[derive(Error, Debug)]
@@ -283,7 +1719,15 @@ pub(crate) enum ProjectBuildError {
     FailedToOpenFile(io::Error),
     #[error("failed to save hashes: {0:?}")]
     FailedToSaveHashes(io::Error),
-    #[error("failed to compile a project: {0:?}")]
+    #[error("unsupported language standard: {0}")]
+    UnsupportedStandard(String),
+    #[error("unsupported optimization level: {0}")]
+    UnsupportedOptimization(String),
+    #[error("{0}")]
+    ToolNotFound(String),
+    #[error("failed to precompile a header: {0}")]
+    PrecompilationError(String),
+    #[error("failed to compile {} source files", .0.len())]
     CompilationError(Vec<SourceFileBuildError>),
     #[error("failed to link a project: {0}")]
     LinkageError(String),
@@ -293,8 +1737,332 @@ pub(crate) enum ProjectBuildError {
 
 #[derive(Error, Debug)]
 pub(crate) enum SourceFileBuildError {
-    #[error("failed to compile a source file: {0}")]
-    FailedToCompile(String),
-    #[error("failed to hash a source file: {0:?}")]
-    FailedToHash(io::Error),
+    /// Carries the source and the compiler's output.
+    #[error("failed to compile {0}:\n{1}")]
+    FailedToCompile(String, String),
+    #[error("failed to hash {0}: {1}")]
+    FailedToHash(String, io::Error),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write_file(path: &Path, content: &str) {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, content).unwrap();
+    }
+
+    /// Sources are stored with the platform's separators.
+    fn native_paths(paths: &[&str]) -> Vec<String> {
+        paths
+            .iter()
+            .map(|path| {
+                Path::new(path)
+                    .components()
+                    .collect::<PathBuf>()
+                    .to_string_lossy()
+                    .into_owned()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_recursive_glob_discovers_nested_sources() {
+        let root = tempfile::tempdir().unwrap();
+
+        write_file(
+            &root.path().join(BUILD_CONFIGURATION_FILE),
+            "[project]\nname = \"nested\"\nlanguage = \"C\"\nsources = [\"src/**/*.c\", \"src/*.c\"]\n",
+        );
+        write_file(&root.path().join("src/main.c"), "");
+        write_file(&root.path().join("src/b/b.c"), "");
+        write_file(&root.path().join("src/a/deeper/deepest/a.c"), "");
+        write_file(&root.path().join("src/a/ignored.h"), "");
+
+        let project = Project::open(root.path(), None, None).unwrap();
+
+        assert_eq!(
+            project.sources,
+            native_paths(&["src/a/deeper/deepest/a.c", "src/b/b.c", "src/main.c"])
+        );
+    }
+
+    #[test]
+    fn test_exclude_removes_matching_sources() {
+        let root = tempfile::tempdir().unwrap();
+
+        write_file(
+            &root.path().join(BUILD_CONFIGURATION_FILE),
+            "[project]\nname = \"excluded\"\nlanguage = \"C\"\nsources = [\"src/*.c\"]\nexclude = [\"src/broken.c\"]\n",
+        );
+        write_file(&root.path().join("src/main.c"), "");
+        write_file(&root.path().join("src/util.c"), "");
+        write_file(&root.path().join("src/broken.c"), "#error broken");
+
+        let project = Project::open(root.path(), None, None).unwrap();
+
+        assert_eq!(project.sources, native_paths(&["src/main.c", "src/util.c"]));
+    }
+
+    #[test]
+    fn test_strict_sources_rejects_patterns_matching_nothing() {
+        let root = tempfile::tempdir().unwrap();
+
+        write_file(
+            &root.path().join(BUILD_CONFIGURATION_FILE),
+            "[project]\nname = \"typo\"\nlanguage = \"C\"\nsources = [\"src/*.c\", \"scr/*.c\"]\nstrictSources = true\n",
+        );
+        write_file(&root.path().join("src/main.c"), "");
+
+        let result = Project::open(root.path(), None, None);
+
+        assert!(matches!(
+            result,
+            Err(ProjectOpenError::InvalidBuildConfiguration(
+                BuildConfigurationError::EmptySourcePatterns(patterns)
+            )) if patterns == ["scr/*.c"]
+        ));
+    }
+
+    #[test]
+    fn test_sources_outside_of_the_project_require_opt_in() {
+        let root = tempfile::tempdir().unwrap();
+        let project_path = root.path().join("app");
+        let build_configuration =
+            "[project]\nname = \"app\"\nlanguage = \"C\"\nsources = [\"src/main.c\", \"../shared/shared.c\"]\n";
+
+        write_file(
+            &project_path.join(BUILD_CONFIGURATION_FILE),
+            build_configuration,
+        );
+        write_file(&project_path.join("src/main.c"), "");
+        write_file(&root.path().join("shared/shared.c"), "");
+
+        let result = Project::open(&project_path, None, None);
+
+        assert!(matches!(
+            result,
+            Err(ProjectOpenError::InvalidBuildConfiguration(
+                BuildConfigurationError::IncorrectSource(source)
+            )) if source == "../shared/shared.c"
+        ));
+
+        write_file(
+            &project_path.join(BUILD_CONFIGURATION_FILE),
+            &format!("{}allowExternalPaths = true\n", build_configuration),
+        );
+
+        let project = Project::open(&project_path, None, None).unwrap();
+
+        assert_eq!(project.sources.len(), 2);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_symlinked_sources_require_opt_in() {
+        let root = tempfile::tempdir().unwrap();
+        let project_path = root.path().join("app");
+        let build_configuration =
+            "[project]\nname = \"app\"\nlanguage = \"C\"\nsources = [\"src/*.c\"]\n";
+
+        write_file(
+            &project_path.join(BUILD_CONFIGURATION_FILE),
+            build_configuration,
+        );
+        write_file(&project_path.join("src/main.c"), "");
+        write_file(&root.path().join("shared/shared.c"), "");
+        std::os::unix::fs::symlink(
+            root.path().join("shared/shared.c"),
+            project_path.join("src/shared.c"),
+        )
+        .unwrap();
+
+        let result = Project::open(&project_path, None, None);
+
+        assert!(matches!(
+            result,
+            Err(ProjectOpenError::InvalidBuildConfiguration(
+                BuildConfigurationError::IncorrectSource(source)
+            )) if Path::new(&source) == Path::new("src/shared.c")
+        ));
+
+        write_file(
+            &project_path.join(BUILD_CONFIGURATION_FILE),
+            &format!("{}allowSymlinks = true\n", build_configuration),
+        );
+
+        let project = Project::open(&project_path, None, None).unwrap();
+
+        assert_eq!(project.sources.len(), 2);
+    }
+
+    #[test]
+    fn test_corrupt_hashes_are_discarded() {
+        let root = tempfile::tempdir().unwrap();
+
+        write_file(
+            &root.path().join(BUILD_CONFIGURATION_FILE),
+            "[project]\nname = \"corrupt\"\nlanguage = \"C\"\nsources = [\"src/main.c\"]\n",
+        );
+        write_file(&root.path().join("src/main.c"), "");
+
+        let hashes_file = Project::open(root.path(), None, None)
+            .unwrap()
+            .hashes_file();
+
+        write_file(&hashes_file, "{\"src/main.c\": \"a1b2");
+
+        let project = Project::open(root.path(), None, None).unwrap();
+
+        assert!(project.hashes.is_empty());
+
+        let hash = blake3::hash(b"main");
+
+        write_file(
+            &hashes_file,
+            &format!(
+                "{{\"src/main.c\": \"{}\", \"src/util.c\": \"not a hash\"}}",
+                hash.to_hex()
+            ),
+        );
+
+        let project = Project::open(root.path(), None, None).unwrap();
+
+        assert_eq!(
+            project.hashes,
+            HashMap::from([(String::from("src/main.c"), hash)])
+        );
+    }
+
+    #[test]
+    fn test_interpolate_variables_expands_builtins_and_escapes() {
+        let mut table = toml::from_str::<toml::Table>(
+            "[project]\nincludes = [\"${PROJECT_ROOT}/include\", \"$${PROJECT_ROOT}\"]\n",
+        )
+        .unwrap();
+        let variables = HashMap::from([("PROJECT_ROOT", String::from("/root"))]);
+
+        Project::interpolate_variables(&mut table, &variables).unwrap();
+
+        assert_eq!(
+            table["project"]["includes"],
+            toml::Value::from(vec!["/root/include", "${PROJECT_ROOT}"])
+        );
+
+        let mut table = toml::from_str::<toml::Table>(
+            "[project]\ndescription = \"Prints ${BAKERY_TEST_UNDEFINED_VARIABLE}\"\n",
+        )
+        .unwrap();
+
+        Project::interpolate_variables(&mut table, &variables).unwrap();
+
+        assert_eq!(
+            table["project"]["description"].as_str(),
+            Some("Prints ${BAKERY_TEST_UNDEFINED_VARIABLE}")
+        );
+
+        let mut table = toml::from_str::<toml::Table>(
+            "[project]\nsources = [\"${BAKERY_TEST_UNDEFINED_VARIABLE}/*.c\"]\n",
+        )
+        .unwrap();
+
+        assert!(matches!(
+            Project::interpolate_variables(&mut table, &variables),
+            Err(ProjectOpenError::InvalidBuildConfiguration(
+                BuildConfigurationError::UndefinedVariable(name)
+            )) if name == "BAKERY_TEST_UNDEFINED_VARIABLE"
+        ));
+    }
+
+    #[test]
+    fn test_project_root_variable_resolves_sources_and_includes() {
+        let root = tempfile::tempdir().unwrap();
+
+        write_file(
+            &root.path().join(BUILD_CONFIGURATION_FILE),
+            "[project]\nname = \"rooted\"\nlanguage = \"C\"\nsources = [\"${PROJECT_ROOT}/src/*.c\"]\nincludes = [\"${PROJECT_ROOT}/include\"]\n",
+        );
+        write_file(&root.path().join("src/main.c"), "");
+        write_file(&root.path().join("include/rooted.h"), "");
+
+        let project = Project::open(root.path(), None, None).unwrap();
+
+        assert_eq!(
+            project.sources,
+            [Path::new("src").join("main.c").to_string_lossy()]
+        );
+        assert_eq!(
+            project.includes,
+            [root
+                .path()
+                .canonicalize()
+                .unwrap()
+                .join("include")
+                .to_string_lossy()]
+        );
+    }
+
+    #[test]
+    fn test_tests_are_named_after_their_paths() {
+        let root = tempfile::tempdir().unwrap();
+
+        write_file(
+            &root.path().join(BUILD_CONFIGURATION_FILE),
+            "[project]\nname = \"tested\"\nlanguage = \"C\"\n[test]\nsources = [\"tests/**/*.c\"]\n",
+        );
+        write_file(&root.path().join("tests/a/check.c"), "");
+        write_file(&root.path().join("tests/b/check.c"), "");
+
+        let project = Project::open(root.path(), None, None).unwrap();
+
+        assert_eq!(
+            project
+                .tests
+                .iter()
+                .map(|test| Project::test_name(test))
+                .collect::<Vec<_>>(),
+            ["tests/a/check", "tests/b/check"]
+        );
+
+        write_file(&root.path().join("tests/a/check.cpp"), "");
+        write_file(
+            &root.path().join(BUILD_CONFIGURATION_FILE),
+            "[project]\nname = \"tested\"\nlanguage = \"C\"\n[test]\nsources = [\"tests/**/*.c\", \"tests/**/*.cpp\"]\n",
+        );
+
+        assert!(matches!(
+            Project::open(root.path(), None, None),
+            Err(ProjectOpenError::InvalidBuildConfiguration(
+                BuildConfigurationError::DuplicateTestName(name)
+            )) if name == "tests/a/check"
+        ));
+    }
+
+    #[test]
+    fn test_deduplicate_includes_keeps_first_occurrence() {
+        let root = tempfile::tempdir().unwrap();
+        let include = |path: &str| root.path().join(path).to_string_lossy().into_owned();
+
+        fs::create_dir_all(root.path().join("common/include")).unwrap();
+        fs::create_dir_all(root.path().join("a/include")).unwrap();
+        fs::create_dir_all(root.path().join("b/include")).unwrap();
+
+        let includes = Project::deduplicate_includes([
+            include("a/include"),
+            include("common/include"),
+            include("b/include"),
+            include("a/../common/include"),
+            include("b/include"),
+        ]);
+
+        assert_eq!(
+            includes,
+            [
+                include("a/include"),
+                include("common/include"),
+                include("b/include")
+            ]
+        );
+    }
 }
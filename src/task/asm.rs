@@ -0,0 +1,30 @@
+use super::{Build, SourceOutput, Task, TaskContext};
+use eyre::eyre;
+
+pub struct Asm {}
+
+impl Asm {
+    pub fn new() -> Self {
+        Self {}
+    }
+}
+
+impl Task for Asm {
+    fn id(&self) -> &'static str {
+        "asm"
+    }
+
+    fn dependencies(&self) -> &[&'static str] {
+        &[]
+    }
+
+    fn on_execute(&mut self, context: &TaskContext) -> eyre::Result<()> {
+        let output = SourceOutput::Assembly {
+            intel_syntax: context.intel_syntax,
+        };
+
+        Build::new()
+            .compile_requested_source(context, output)
+            .map_err(|err| eyre!(err))
+    }
+}
@@ -1,26 +1,49 @@
 use super::{Project, Task, TaskContext, ToolchainConfiguration};
 use crate::{
-    config::{CStandard, CppStandard, Distribution, Language},
+    config::{
+        self, CStandard, CppStandard, DebugInfoLevel, Distribution, Language, LinkKind, Lto,
+        OptimizationLevel, Sanitizer,
+    },
+    log, log_error, log_info, log_project_info,
     tools::{
-        Archiver, CCompilationSettings, CCompiler, CppCompilationSettings, CppCompiler,
-        GccFlavorArchiver, GccFlavorCCompiler, GccFlavorCppCompiler, LinkingSettings,
+        is_assembly_source, is_objective_source, Archiver, CCompilationSettings, CCompiler,
+        CppCompilationSettings, CppCompiler, Flavor, GccFlavorArchiver, GccFlavorCCompiler,
+        GccFlavorCppCompiler, LinkingSettings, Strip,
     },
-    Dependency, ProjectBuildError, SourceFileBuildError, BAKERY_BUILD_DIRECTORY,
-    BAKERY_CACHE_DIRECTORY, BAKERY_HASHES_FILE, BUILD_CONFIGURATION_FILE,
+    Binary, Dependency, FileHash, ObjectCache, PathExtension, ProjectBuildError,
+    SourceFileBuildError, BAKERY_BUILD_LOG_FILE, BAKERY_SARIF_FILE, BUILD_CONFIGURATION_FILE,
 };
 use blake3::Hash;
+use eyre::{bail, eyre};
+use indicatif::{MultiProgress, ProgressBar, ProgressDrawTarget, ProgressStyle};
+use itertools::Itertools;
+use lazy_static::lazy_static;
 use memmap2::MmapOptions;
-use rayon::iter::{IntoParallelRefIterator, ParallelIterator};
+use rayon::{
+    iter::{IntoParallelIterator, IntoParallelRefIterator, ParallelIterator},
+    ThreadPoolBuilder,
+};
+use regex::Regex;
+use semver::Version;
 use std::{
-    collections::HashMap,
+    cmp::Reverse,
+    collections::{BTreeMap, HashMap, HashSet},
+    env,
+    fmt::{self, Display},
     fs::{self, File},
-    io,
+    io::{self, IsTerminal, Read},
+    iter, mem,
     path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
 };
 
 const EXECUTABLE_EXTENSION: &str = if cfg!(target_os = "windows") {
     "exe"
-} else if cfg!(target_os = "linux") {
+} else if cfg!(any(target_os = "linux", target_os = "macos")) {
     ""
 } else {
     unreachable!()
@@ -30,73 +53,452 @@ const DYNAMIC_LIBRARY_EXTENSION: &str = if cfg!(target_os = "windows") {
     "dll"
 } else if cfg!(target_os = "linux") {
     "so"
+} else if cfg!(target_os = "macos") {
+    "dylib"
 } else {
     unreachable!()
 };
 
 const STATIC_LIBRARY_EXTENSION: &str = if cfg!(target_os = "windows") {
     "lib"
-} else if cfg!(target_os = "linux") {
+} else if cfg!(any(target_os = "linux", target_os = "macos")) {
     "a"
 } else {
     unreachable!()
 };
 
+/// Unix linkers resolve `-l<name>` to `lib<name>.so`/`lib<name>.a`.
+const LIBRARY_PREFIX: &str = if cfg!(target_os = "windows") {
+    ""
+} else {
+    "lib"
+};
+
 const OBJECT_FILE_EXTENSION: &str = "o";
 
-pub struct Build {}
+const DEPENDENCY_FILE_EXTENSION: &str = "d";
+
+/// How many hex digits of the source path's hash object file names carry.
+const OBJECT_FILE_HASH_LENGTH: usize = 8;
+
+/// Files smaller than this are read into memory to be hashed instead of mapped.
+const MMAP_THRESHOLD: u64 = 64 * 1024;
+
+/// Files modified this recently when their hashes are saved could still change without their
+/// modification time moving on filesystems with coarse timestamps, so they are hashed again.
+const RACY_MODIFICATION_WINDOW: Duration = Duration::from_secs(2);
+
+const PROJECT_VERSION_DEFINE: &str = "PROJECT_VERSION";
+
+const UNITY_STUB_PREFIX: &str = "unity-";
+
+const TESTS_DIRECTORY: &str = "tests";
+
+/// Where the location of each tool can be set, suggested when `--frozen` finds one missing.
+const C_COMPILER_SETTING: &str = "gcc_location in config.toml, CC or [toolchain]";
+const CPP_COMPILER_SETTING: &str = "gpp_location in config.toml, CXX or [toolchain]";
+const ARCHIVER_SETTING: &str = "ar_location in config.toml, AR or [toolchain]";
+const STRIP_SETTING: &str = "strip_location in config.toml";
+
+const SARIF_SCHEMA: &str = "https://json.schemastore.org/sarif-2.1.0.json";
+
+/// How many times per second progress bars are redrawn at most.
+const PROGRESS_REFRESH_RATE: u8 = 10;
+
+lazy_static! {
+    static ref PROGRESS_STYLE: ProgressStyle =
+        ProgressStyle::with_template("{prefix:>12} [{bar:30}] compiled {pos}/{len} {wide_msg}")
+            .unwrap()
+            .progress_chars("=> ");
+    static ref DIAGNOSTIC_ERROR_REGEX: Regex = Regex::new(r"(^|: )(fatal )?error: ").unwrap();
+    static ref DIAGNOSTIC_WARNING_REGEX: Regex = Regex::new(r"(^|: )warning: ").unwrap();
+}
+
+/// Keys the fingerprints are saved under in the hashes file, next to the sources' own hashes.
+const C_FINGERPRINT_KEY: &str = "fingerprint:c";
+const CPP_FINGERPRINT_KEY: &str = "fingerprint:cpp";
+
+/// Per-language settings folded into every source's cache key.
+struct CompilationFingerprints {
+    c: String,
+    cpp: String,
+}
+
+impl CompilationFingerprints {
+    fn get(&self, language: Language) -> &str {
+        match language {
+            Language::C => &self.c,
+            Language::Cpp => &self.cpp,
+        }
+    }
+
+    fn hashes(&self) -> [(&'static str, Hash); 2] {
+        [
+            (C_FINGERPRINT_KEY, blake3::hash(self.c.as_bytes())),
+            (CPP_FINGERPRINT_KEY, blake3::hash(self.cpp.as_bytes())),
+        ]
+    }
+
+    /// Whether the fingerprint saved by the previous build differs. Caches written before
+    /// fingerprints were saved are given the benefit of the doubt, their sources are still hashed.
+    fn has_changed(&self, project: &Project, language: Language) -> bool {
+        let key = match language {
+            Language::C => C_FINGERPRINT_KEY,
+            Language::Cpp => CPP_FINGERPRINT_KEY,
+        };
+
+        project
+            .hashes
+            .get(key)
+            .is_some_and(|hash| *hash != blake3::hash(self.get(language).as_bytes()))
+    }
+}
+
+/// What gets handed to the compiler: a single source, or in unity mode a generated stub including
+/// a batch of sources.
+///
+/// Batched sources share one translation unit, so file-local names such as `static` functions or
+/// anonymous namespaces may collide across them and a header without include guards is pasted
+/// more than once. Such sources need `unity = false` or an override, which keeps them unbatched.
+#[derive(Clone, PartialEq)]
+struct CompilationUnit {
+    source: String,
+    sources: Vec<String>,
+}
+
+impl CompilationUnit {
+    fn single(source: &str) -> CompilationUnit {
+        CompilationUnit {
+            source: String::from(source),
+            sources: vec![String::from(source)],
+        }
+    }
+
+    fn is_unity_batch(&self) -> bool {
+        self.sources != [self.source.as_str()]
+    }
+
+    /// Objects share the build directory, so their names carry a hash of the source's path to
+    /// keep sources with the same name apart, such as `a/util.c` and `b/util.c`, or `main.c` and
+    /// `main.cpp`.
+    fn object_file(&self, project: &Project) -> PathBuf {
+        let source = Path::new(&self.source);
+
+        project.build_directory().join(format!(
+            "{}-{}.{}",
+            source.file_stem().unwrap().to_string_lossy(),
+            &blake3::hash(self.source.as_bytes()).to_hex()[..OBJECT_FILE_HASH_LENGTH],
+            OBJECT_FILE_EXTENSION
+        ))
+    }
+}
+
+/// Why a compilation unit is compiled again instead of reusing its cached object.
+enum RecompileReason {
+    NotCompiledBefore,
+    ObjectMissing,
+    SourceChanged,
+    FlagsChanged,
+    HeaderChanged(String),
+    Forced,
+}
+
+impl Display for RecompileReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RecompileReason::NotCompiledBefore => write!(f, "not compiled before"),
+            RecompileReason::ObjectMissing => write!(f, "object file missing"),
+            RecompileReason::SourceChanged => write!(f, "source changed"),
+            RecompileReason::FlagsChanged => write!(f, "flags changed"),
+            RecompileReason::HeaderChanged(header) => write!(f, "{} changed", header),
+            RecompileReason::Forced => write!(f, "forced"),
+        }
+    }
+}
+
+/// What [`Build::compile_source_file`] asks the compiler for.
+#[derive(Clone, Copy)]
+pub(crate) enum SourceOutput {
+    Object,
+    Assembly { intel_syntax: bool },
+    Preprocessed,
+}
+
+impl SourceOutput {
+    fn extension(&self, language: Language) -> &'static str {
+        match (self, language) {
+            (SourceOutput::Object, _) => OBJECT_FILE_EXTENSION,
+            (SourceOutput::Assembly { .. }, _) => "s",
+            (SourceOutput::Preprocessed, Language::C) => "i",
+            (SourceOutput::Preprocessed, Language::Cpp) => "ii",
+        }
+    }
+}
+
+/// Owned values the compilation settings of a project borrow from.
+struct CompilationEnvironment {
+    includes: Vec<String>,
+    defines: BTreeMap<String, String>,
+    sanitizers: Vec<Sanitizer>,
+    target_cpu: Option<String>,
+    tune_cpu: Option<String>,
+    precompiled_header_wrapper: Option<PathBuf>,
+    c_additional_pre_arguments: Vec<String>,
+    c_additional_post_arguments: Vec<String>,
+    cpp_additional_pre_arguments: Vec<String>,
+    cpp_additional_post_arguments: Vec<String>,
+}
+
+struct CompilationTiming {
+    project: String,
+    source: String,
+    duration: Duration,
+}
+
+#[derive(Clone, Copy, Default, PartialEq, Eq)]
+pub enum MessageFormat {
+    #[default]
+    Human,
+    Json,
+    /// Collects the diagnostics into a SARIF log for code scanning.
+    Sarif,
+}
+
+pub struct Build {
+    diagnostics_color: bool,
+    message_format: MessageFormat,
+    verbose: bool,
+    keep_going: bool,
+    force: bool,
+    progress: Option<MultiProgress>,
+    strip: Option<Strip>,
+    /// Why `strip` is missing, printed when an artifact should have been stripped.
+    strip_not_found: String,
+    linker: Option<String>,
+    /// Diagnostics awaiting the SARIF log, in SARIF's result format.
+    sarif_results: Mutex<Vec<serde_json::Value>>,
+    /// Warnings printed for sources that compiled, which the summary of a failed build counts
+    /// along with those of the sources that didn't.
+    warning_count: AtomicUsize,
+    /// Artifacts written to the `--emit-deps` manifest so far.
+    manifest_artifacts: Vec<serde_json::Value>,
+    object_cache: Option<ObjectCache>,
+}
 
 impl Build {
     pub fn new() -> Self {
-        Self {}
+        Self {
+            diagnostics_color: false,
+            message_format: MessageFormat::default(),
+            verbose: false,
+            keep_going: false,
+            force: false,
+            progress: None,
+            strip: None,
+            strip_not_found: String::new(),
+            linker: None,
+            sarif_results: Mutex::new(Vec::new()),
+            warning_count: AtomicUsize::new(0),
+            manifest_artifacts: Vec::new(),
+            object_cache: None,
+        }
+    }
+
+    pub(crate) fn locate_c_compiler(
+        &self,
+        toolchain_configuration: &ToolchainConfiguration,
+    ) -> Option<String> {
+        env::var("CC")
+            .ok()
+            .filter(|location| !location.is_empty())
+            .or_else(|| toolchain_configuration.gcc_location.clone())
+            .or_else(|| toolchain_configuration.locate("gcc"))
     }
 
-    fn create_c_compiler(
+    pub(crate) fn create_c_compiler(
         &self,
         toolchain_configuration: &ToolchainConfiguration,
     ) -> Option<Box<dyn CCompiler>> {
-        toolchain_configuration
-            .gcc_location
-            .as_ref()
-            .map(|gcc_location| {
-                let c_compiler: Box<dyn CCompiler> =
-                    Box::new(GccFlavorCCompiler::new(gcc_location.clone()));
+        self.locate_c_compiler(toolchain_configuration)
+            .map(|location| {
+                // clang accepts the same command line as GCC
+                let c_compiler: Box<dyn CCompiler> = Box::new(GccFlavorCCompiler::new(
+                    location,
+                    toolchain_configuration.compiler_launcher.clone(),
+                ));
 
                 c_compiler
             })
     }
 
-    fn create_cpp_compiler(
+    pub(crate) fn locate_cpp_compiler(
+        &self,
+        toolchain_configuration: &ToolchainConfiguration,
+    ) -> Option<String> {
+        env::var("CXX")
+            .ok()
+            .filter(|location| !location.is_empty())
+            .or_else(|| toolchain_configuration.gpp_location.clone())
+            .or_else(|| toolchain_configuration.locate("g++"))
+    }
+
+    pub(crate) fn create_cpp_compiler(
         &self,
         toolchain_configuration: &ToolchainConfiguration,
     ) -> Option<Box<dyn CppCompiler>> {
-        toolchain_configuration
-            .gpp_location
-            .as_ref()
-            .map(|gpp_location| {
-                let cpp_compiler: Box<dyn CppCompiler> =
-                    Box::new(GccFlavorCppCompiler::new(gpp_location.clone()));
+        self.locate_cpp_compiler(toolchain_configuration)
+            .map(|location| {
+                // clang++ accepts the same command line as g++
+                let cpp_compiler: Box<dyn CppCompiler> = Box::new(GccFlavorCppCompiler::new(
+                    location,
+                    toolchain_configuration.compiler_launcher.clone(),
+                ));
 
                 cpp_compiler
             })
     }
 
+    fn locate_configured_archiver(
+        &self,
+        toolchain_configuration: &ToolchainConfiguration,
+    ) -> Option<String> {
+        env::var("AR")
+            .ok()
+            .filter(|location| !location.is_empty())
+            .or_else(|| toolchain_configuration.ar_location.clone())
+    }
+
+    pub(crate) fn locate_archiver(
+        &self,
+        toolchain_configuration: &ToolchainConfiguration,
+    ) -> Option<String> {
+        self.locate_configured_archiver(toolchain_configuration)
+            .or_else(|| toolchain_configuration.locate("ar"))
+    }
+
     fn create_archiver(
         &self,
         toolchain_configuration: &ToolchainConfiguration,
     ) -> Option<Box<dyn Archiver>> {
-        toolchain_configuration
-            .ar_location
-            .as_ref()
-            .map(|ar_location| {
+        let configured_location = self.locate_configured_archiver(toolchain_configuration);
+
+        // An explicitly configured archiver is trusted with LTO objects as well, otherwise the
+        // plugin-aware wrapper matching the compiler is preferred
+        let lto_location = match configured_location {
+            Some(_) => None,
+            None => self
+                .locate_c_compiler(toolchain_configuration)
+                .and_then(|location| match Flavor::detect(&location) {
+                    Flavor::Gcc => toolchain_configuration.locate("gcc-ar"),
+                    Flavor::Clang => toolchain_configuration.locate("llvm-ar"),
+                }),
+        };
+
+        self.locate_archiver(toolchain_configuration)
+            .map(|location| {
+                // llvm-ar accepts the same command line as ar
                 let archiver: Box<dyn Archiver> =
-                    Box::new(GccFlavorArchiver::new(ar_location.clone()));
+                    Box::new(GccFlavorArchiver::new(location, lto_location));
 
                 archiver
             })
     }
 
-    fn get_c_standard(&self, project: &Project) -> CStandard {
+    pub(crate) fn get_binary_file(&self, project: &Project, binary: &Binary) -> PathBuf {
+        project
+            .build_directory()
+            .join(&binary.name)
+            .with_extension(EXECUTABLE_EXTENSION)
+    }
+
+    /// The project's own artifact, or its binaries when it declares any.
+    pub(crate) fn get_output_files(&self, project: &Project) -> Vec<PathBuf> {
+        if project.binaries.is_empty() {
+            vec![self.get_output_file(project)]
+        } else {
+            project
+                .binaries
+                .iter()
+                .map(|binary| self.get_binary_file(project, binary))
+                .collect()
+        }
+    }
+
+    pub(crate) fn get_output_file(&self, project: &Project) -> PathBuf {
+        let file_name = match project.distribution {
+            Distribution::Executable => project.name.clone(),
+            Distribution::DynamicLibrary | Distribution::StaticLibrary => {
+                format!("{}{}", LIBRARY_PREFIX, project.name)
+            }
+        };
+
+        project
+            .build_directory()
+            .join(file_name)
+            .with_extension(match project.distribution {
+                Distribution::Executable => EXECUTABLE_EXTENSION,
+                Distribution::DynamicLibrary => DYNAMIC_LIBRARY_EXTENSION,
+                Distribution::StaticLibrary => STATIC_LIBRARY_EXTENSION,
+            })
+    }
+
+    /// The name dependents record at link time and look up at runtime, carrying the major
+    /// version when the project has one, e.g. `libfoo.so.1` or `@rpath/libfoo.1.dylib`.
+    fn get_soname(&self, project: &Project) -> Option<String> {
+        if project.distribution != Distribution::DynamicLibrary || cfg!(target_os = "windows") {
+            return None;
+        }
+
+        let major_version = project
+            .version
+            .as_deref()
+            .and_then(|version| Version::parse(version).ok())
+            .map(|version| version.major);
+
+        Some(match (cfg!(target_os = "macos"), major_version) {
+            (true, Some(major_version)) => {
+                format!(
+                    "@rpath/{}{}.{}.dylib",
+                    LIBRARY_PREFIX, project.name, major_version
+                )
+            }
+            (true, None) => format!("@rpath/{}{}.dylib", LIBRARY_PREFIX, project.name),
+            (false, Some(major_version)) => {
+                format!("{}{}.so.{}", LIBRARY_PREFIX, project.name, major_version)
+            }
+            (false, None) => format!("{}{}.so", LIBRARY_PREFIX, project.name),
+        })
+    }
+
+    /// The symlink named after the soname, when it differs from the output file.
+    pub(crate) fn get_soname_file(&self, project: &Project) -> Option<PathBuf> {
+        let soname = self.get_soname(project)?;
+        let soname_file = project
+            .build_directory()
+            .join(soname.strip_prefix("@rpath/").unwrap_or(&soname));
+
+        (soname_file != self.get_output_file(project)).then_some(soname_file)
+    }
+
+    fn create_soname_file(&self, project: &Project) -> Result<(), io::Error> {
+        let Some(soname_file) = self.get_soname_file(project) else {
+            return Ok(());
+        };
+
+        if fs::symlink_metadata(&soname_file).is_ok() {
+            fs::remove_file(&soname_file)?;
+        }
+
+        #[cfg(unix)]
+        std::os::unix::fs::symlink(
+            self.get_output_file(project).file_name().unwrap(),
+            &soname_file,
+        )?;
+
+        Ok(())
+    }
+
+    pub(crate) fn get_c_standard(&self, project: &Project) -> CStandard {
         project
             .c
             .as_ref()
@@ -104,7 +506,7 @@ impl Build {
             .unwrap_or_else(CStandard::latest)
     }
 
-    fn get_cpp_standard(&self, project: &Project) -> CppStandard {
+    pub(crate) fn get_cpp_standard(&self, project: &Project) -> CppStandard {
         project
             .cpp
             .as_ref()
@@ -112,327 +514,1523 @@ impl Build {
             .unwrap_or_else(CppStandard::latest)
     }
 
-    fn collect_sources_to_compile(&self, project: &Project) -> Vec<String> {
-        if project.has_project_configuration_changed {
-            project.sources.to_vec()
-        } else {
-            project
-                .sources
-                .par_iter()
-                .cloned()
-                .filter(|source| {
-                    project
-                        .hashes
-                        .get(source)
-                        .map(|hash| {
-                            let object_file_exists = fs::metadata(
-                                project
-                                    .base_path
-                                    .join(BAKERY_BUILD_DIRECTORY)
-                                    .join(PathBuf::from(source).file_name().unwrap())
-                                    .with_extension(OBJECT_FILE_EXTENSION),
-                            )
-                            .map(|_| true)
-                            .unwrap_or(false);
+    pub(crate) fn get_c_gnu_extensions(&self, project: &Project) -> bool {
+        project
+            .c
+            .as_ref()
+            .and_then(|c| c.gnu_extensions)
+            .unwrap_or_default()
+    }
+
+    pub(crate) fn get_cpp_gnu_extensions(&self, project: &Project) -> bool {
+        project
+            .cpp
+            .as_ref()
+            .and_then(|cpp| cpp.gnu_extensions)
+            .unwrap_or_default()
+    }
 
-                            let source_file_changed = File::open(project.base_path.join(source))
-                                .map(|file| {
-                                    let file_content =
-                                        unsafe { MmapOptions::new().map(&file).unwrap() };
+    pub(crate) fn get_source_language(&self, project: &Project, source: &str) -> Language {
+        match PathBuf::from(source)
+            .extension()
+            .and_then(|extension| extension.to_str())
+        {
+            Some("c" | "m" | "s" | "S") => Language::C,
+            Some("cc" | "cpp" | "cxx" | "c++" | "mm") => Language::Cpp,
+            _ => project.language,
+        }
+    }
 
-                                    *hash != blake3::hash(&file_content)
-                                })
-                                .unwrap_or(false);
+    /// C++ objects need the C++ runtime, so the C++ compiler drives the link whenever the project
+    /// or any of its static libraries contains C++ sources.
+    fn get_linking_language(&self, project: &Project) -> Language {
+        let contains_cpp_sources = |project: &Project| {
+            project.language == Language::Cpp
+                || project
+                    .sources
+                    .iter()
+                    .any(|source| self.get_source_language(project, source) == Language::Cpp)
+        };
 
-                            !object_file_exists | source_file_changed
-                        })
-                        .unwrap_or(true)
-                })
-                .collect::<Vec<_>>()
+        if contains_cpp_sources(project)
+            || self
+                .collect_static_library_dependencies(project)
+                .into_iter()
+                .any(contains_cpp_sources)
+        {
+            Language::Cpp
+        } else {
+            Language::C
         }
     }
 
-    fn collect_project_dependencies<'a>(&self, project: &'a Project) -> Vec<&'a Project> {
+    fn get_optimization(&self, project: &Project) -> OptimizationLevel {
         project
-            .dependencies
-            .iter()
-            .filter_map(|dependency| match dependency {
-                Dependency::Project(project) => Some(project.as_ref()),
-                _ => None,
-            })
-            .collect::<Vec<_>>()
+            .profile
+            .as_ref()
+            .and_then(|profile| profile.configuration.optimization.clone())
+            .unwrap_or_else(|| project.optimization.clone())
     }
 
-    fn collect_libraries(&self, project: &Project) -> Vec<String> {
+    fn get_debug_info(&self, project: &Project) -> Option<DebugInfoLevel> {
         project
-            .dependencies
-            .iter()
-            .filter_map(|dependency| match dependency {
-                Dependency::System { name } => Some(name.clone()),
-                Dependency::Project(project) => match project.distribution {
-                    Distribution::DynamicLibrary => Some(project.name.clone()),
-                    _ => None,
-                },
-            })
-            .collect::<Vec<_>>()
+            .profile
+            .as_ref()
+            .and_then(|profile| profile.configuration.debug_info.clone())
+            .or_else(|| project.debug_info.clone())
     }
 
-    fn collect_library_search_paths(&self, project_dependencies: &[&Project]) -> Vec<String> {
-        project_dependencies
-            .iter()
-            .map(|project| {
-                Path::new(&project.base_path)
-                    .join(BAKERY_BUILD_DIRECTORY)
-                    .to_string_lossy()
-                    .into_owned()
-            })
-            .collect::<Vec<_>>()
+    fn get_enable_all_warnings(&self, project: &Project) -> bool {
+        project
+            .profile
+            .as_ref()
+            .and_then(|profile| profile.configuration.enable_all_warnings)
+            .unwrap_or(project.enable_all_warnings)
     }
 
-    fn collect_object_files(
-        &self,
-        project: &Project,
-        project_dependencies: &[&Project],
-    ) -> Vec<PathBuf> {
-        let mut object_files = project
-            .sources
-            .iter()
-            .map(|source| {
-                project
-                    .base_path
-                    .join(BAKERY_BUILD_DIRECTORY)
-                    .join(PathBuf::from(source).file_name().unwrap())
-                    .with_extension(OBJECT_FILE_EXTENSION)
-            })
-            .collect::<Vec<_>>();
+    fn get_target_cpu(&self, project: &Project) -> Option<String> {
+        project
+            .profile
+            .as_ref()
+            .and_then(|profile| profile.configuration.target_cpu.clone())
+            .or_else(|| project.target_cpu.clone())
+    }
 
-        for project_dependency in project_dependencies {
-            if project_dependency.distribution == Distribution::StaticLibrary {
-                object_files.push(
-                    Path::new(&project_dependency.base_path)
-                        .join(BAKERY_BUILD_DIRECTORY)
-                        .join(format!(
-                            "{}.{}",
-                            project_dependency.name, STATIC_LIBRARY_EXTENSION
-                        )),
-                );
-            }
-        }
+    fn get_tune_cpu(&self, project: &Project) -> Option<String> {
+        project
+            .profile
+            .as_ref()
+            .and_then(|profile| profile.configuration.tune_cpu.clone())
+            .or_else(|| project.tune_cpu.clone())
+    }
 
-        object_files
+    fn get_strip(&self, project: &Project) -> bool {
+        project
+            .profile
+            .as_ref()
+            .and_then(|profile| profile.configuration.strip)
+            .unwrap_or(project.strip)
     }
 
-    fn collect_artifacts(&self, project: &Project) -> Vec<PathBuf> {
-        let mut artifacts = Vec::new();
+    fn get_coverage(&self, project: &Project) -> bool {
+        project
+            .profile
+            .as_ref()
+            .and_then(|profile| profile.configuration.coverage)
+            .unwrap_or(project.coverage)
+    }
 
-        if project.distribution == Distribution::DynamicLibrary {
-            artifacts.push(
-                project
-                    .base_path
-                    .join(BAKERY_BUILD_DIRECTORY)
-                    .join(format!("{}.{}", project.name, DYNAMIC_LIBRARY_EXTENSION)),
+    fn get_lto(&self, project: &Project) -> Lto {
+        project
+            .profile
+            .as_ref()
+            .and_then(|profile| profile.configuration.lto)
+            .unwrap_or(project.lto)
+    }
+
+    fn get_treat_all_warnings_as_errors(&self, project: &Project) -> bool {
+        project
+            .profile
+            .as_ref()
+            .and_then(|profile| profile.configuration.treat_all_warnings_as_errors)
+            .unwrap_or(project.treat_all_warnings_as_errors)
+    }
+
+    /// `PROJECT_VERSION` is injected first so a user-defined define of the same name wins.
+    pub(crate) fn get_defines(&self, project: &Project) -> BTreeMap<String, String> {
+        let mut defines = BTreeMap::new();
+
+        if let Some(version) = &project.version {
+            defines.insert(
+                String::from(PROJECT_VERSION_DEFINE),
+                format!("\"{}\"", version),
             );
         }
 
-        for dependency in &project.dependencies {
-            if let Dependency::Project(subproject) = dependency {
-                artifacts.append(&mut self.collect_artifacts(subproject));
-            }
+        defines.extend(project.defines.clone());
+
+        if let Some(profile) = &project.profile {
+            defines.extend(profile.configuration.defines.clone());
         }
 
-        artifacts
+        defines
     }
 
-    fn serialize_hashes(&self, hashes: HashMap<String, Hash>) -> String {
-        serde_json::to_string_pretty(
-            &hashes
-                .into_iter()
-                .map(|(key, value)| (key, Hash::to_string(&value)))
-                .collect::<HashMap<_, _>>(),
+    fn get_sanitizers(&self, project: &Project) -> Vec<Sanitizer> {
+        project
+            .sanitizers
+            .iter()
+            .chain(
+                project
+                    .profile
+                    .as_ref()
+                    .map(|profile| profile.configuration.sanitizers.as_slice())
+                    .unwrap_or_default(),
+            )
+            .copied()
+            .collect::<Vec<_>>()
+    }
+
+    fn get_gcc_arguments(&self, project: &Project) -> (Vec<String>, Vec<String>) {
+        let profile_gcc = project
+            .profile
+            .as_ref()
+            .and_then(|profile| profile.configuration.gcc.as_ref());
+
+        project.gcc.iter().chain(profile_gcc).fold(
+            (Vec::new(), Vec::new()),
+            |(mut pre, mut post), gcc| {
+                pre.extend(gcc.additional_pre_arguments.iter().cloned());
+                post.extend(gcc.additional_post_arguments.iter().cloned());
+
+                (pre, post)
+            },
         )
-        .unwrap()
     }
 
-    fn create_directories(&self, project: &Project) -> Result<(), io::Error> {
-        fs::create_dir_all(project.base_path.join(BAKERY_BUILD_DIRECTORY))?;
-        fs::create_dir_all(project.base_path.join(BAKERY_CACHE_DIRECTORY))?;
+    fn get_gpp_arguments(&self, project: &Project) -> (Vec<String>, Vec<String>) {
+        let profile_gpp = project
+            .profile
+            .as_ref()
+            .and_then(|profile| profile.configuration.gpp.as_ref());
 
-        Ok(())
+        project.gpp.iter().chain(profile_gpp).fold(
+            (Vec::new(), Vec::new()),
+            |(mut pre, mut post), gpp| {
+                pre.extend(gpp.additional_pre_arguments.iter().cloned());
+                post.extend(gpp.additional_post_arguments.iter().cloned());
+
+                (pre, post)
+            },
+        )
     }
 
-    fn build_dependencies(
-        &self,
-        project: &Project,
-        c_compiler: &dyn CCompiler,
-        cpp_compiler: &dyn CppCompiler,
-        archiver: &dyn Archiver,
-    ) -> Result<(), ProjectBuildError> {
-        for dependency in &project.dependencies {
-            if let Dependency::Project(subproject) = dependency {
-                self.build_dependencies(subproject, c_compiler, cpp_compiler, archiver)?;
+    /// Everything besides the source's content that ends up in its object file, so that editing
+    /// unrelated parts of the build configuration doesn't invalidate the whole cache.
+    fn get_compilation_fingerprint(&self, project: &Project, language: Language) -> String {
+        let settings = serde_json::json!({
+            "distribution": project.distribution,
+            "optimization": self.get_optimization(project),
+            "debugInfo": self.get_debug_info(project),
+            "targetCpu": self.get_target_cpu(project),
+            "tuneCpu": self.get_tune_cpu(project),
+            "lto": self.get_lto(project),
+            "includes": self.collect_includes(project),
+            "defines": self.get_defines(project),
+            "enableAllWarnings": self.get_enable_all_warnings(project),
+            "treatAllWarningsAsErrors": self.get_treat_all_warnings_as_errors(project),
+            "warnings": project.warnings,
+            "sanitizers": self.get_sanitizers(project),
+            "coverage": self.get_coverage(project),
+            "threads": project.threads,
+            "pkgConfig": self.collect_pkg_config_compile_flags(project),
+        });
+
+        let precompiled_header = project
+            .precompiled_header
+            .as_ref()
+            .filter(|_| language == project.language)
+            .map(|header| {
+                File::open(project.base_path.join(header))
+                    .and_then(|file| hash_file(&file))
+                    .map(|hash| hash.to_hex().to_string())
+                    .unwrap_or_default()
+            });
 
-                let sources = self.collect_sources_to_compile(subproject);
+        let language_settings = match language {
+            Language::C => serde_json::json!({
+                "standard": self.get_c_standard(project),
+                "gnuExtensions": self.get_c_gnu_extensions(project),
+                "arguments": self.get_gcc_arguments(project),
+                "precompiledHeader": precompiled_header,
+            }),
+            Language::Cpp => serde_json::json!({
+                "standard": self.get_cpp_standard(project),
+                "gnuExtensions": self.get_cpp_gnu_extensions(project),
+                "arguments": self.get_gpp_arguments(project),
+                "precompiledHeader": precompiled_header,
+            }),
+        };
 
-                self.build(subproject, sources, c_compiler, cpp_compiler, archiver)?;
-            }
-        }
+        serde_json::json!([settings, language_settings]).to_string()
+    }
 
-        Ok(())
+    /// A wrapper including the configured header is precompiled in its place, so compilers that
+    /// reject the precompiled header for some source still find a header to include.
+    fn get_precompiled_header_wrapper(&self, project: &Project) -> Option<PathBuf> {
+        project.precompiled_header.as_ref().map(|header| {
+            project
+                .build_directory()
+                .join(Path::new(header).file_name().unwrap())
+        })
     }
 
-    fn build(
+    fn hash_source(
         &self,
         project: &Project,
-        sources: Vec<String>,
-        c_compiler: &dyn CCompiler,
-        cpp_compiler: &dyn CppCompiler,
-        archiver: &dyn Archiver,
-    ) -> Result<(), ProjectBuildError> {
-        println!("Building {}", project.name);
+        source: &str,
+        fingerprints: &CompilationFingerprints,
+    ) -> Result<Hash, io::Error> {
+        let fingerprint = fingerprints.get(self.get_source_language(project, source));
+        let overrides = project
+            .overrides
+            .iter()
+            .filter(|source_override| source_override.matches(source))
+            .map(|source_override| &source_override.configuration)
+            .collect::<Vec<_>>();
+
+        let mut hasher = blake3::Hasher::new();
+
+        hasher.update(hash_file_cached(project, &project.base_path.join(source))?.as_bytes());
+        hasher.update(fingerprint.as_bytes());
+        hasher.update(
+            serde_json::to_string(&overrides)
+                .unwrap_or_default()
+                .as_bytes(),
+        );
+
+        Ok(hasher.finalize())
+    }
+
+    fn get_compilation_fingerprints(&self, project: &Project) -> CompilationFingerprints {
+        CompilationFingerprints {
+            c: self.get_compilation_fingerprint(project, Language::C),
+            cpp: self.get_compilation_fingerprint(project, Language::Cpp),
+        }
+    }
+
+    fn hash_compilation_unit(
+        &self,
+        project: &Project,
+        unit: &CompilationUnit,
+        fingerprints: &CompilationFingerprints,
+    ) -> Result<Hash, io::Error> {
+        combine_source_hashes(unit, |source| {
+            self.hash_source(project, source, fingerprints)
+        })
+    }
+
+    fn collect_compilation_units(&self, project: &Project) -> Vec<CompilationUnit> {
+        if !project.unity {
+            return project
+                .sources
+                .iter()
+                .map(|source| CompilationUnit::single(source))
+                .collect();
+        }
+
+        // Overridden sources need their own compiler settings, Objective-C(++) and assembly sources
+        // their own language and binary sources their own executable, so none of them is ever
+        // batched
+        let (batchable_sources, unbatchable_sources): (Vec<_>, Vec<_>) =
+            project.sources.iter().partition(|source| {
+                !is_objective_source(Path::new(source))
+                    && !is_assembly_source(Path::new(source))
+                    && !project.is_binary_source(source)
+                    && !project
+                        .overrides
+                        .iter()
+                        .any(|source_override| source_override.matches(source))
+            });
+
+        let mut units = Vec::new();
+
+        for (language, extension) in [(Language::C, "c"), (Language::Cpp, "cpp")] {
+            let sources = batchable_sources
+                .iter()
+                .filter(|source| self.get_source_language(project, source) == language)
+                .map(|source| (*source).clone())
+                .collect::<Vec<_>>();
+
+            for batch in sources.chunks(project.unity_batch_size) {
+                let stub = project.build_directory().join(format!(
+                    "{}{}.{}",
+                    UNITY_STUB_PREFIX,
+                    units.len(),
+                    extension
+                ));
+
+                units.push(CompilationUnit {
+                    source: stub.to_string_lossy().into_owned(),
+                    sources: batch.to_vec(),
+                });
+            }
+        }
+
+        units.extend(
+            unbatchable_sources
+                .into_iter()
+                .map(|source| CompilationUnit::single(source)),
+        );
+
+        units
+    }
+
+    /// Whether the project has nothing to compile and nothing to link, see
+    /// [`Build::are_artifacts_up_to_date`].
+    fn is_up_to_date(&self, project: &Project) -> bool {
+        self.collect_sources_to_compile(project).is_empty()
+            && self.are_artifacts_up_to_date(project)
+    }
+
+    /// Whether the project's artifacts exist and are newer than those of its dependencies, which
+    /// have to be up to date themselves, so that an edited dependency is rebuilt and relinked.
+    fn are_artifacts_up_to_date(&self, project: &Project) -> bool {
+        let built_at = self
+            .get_output_files(project)
+            .iter()
+            .map(|output_file| {
+                fs::metadata(output_file)
+                    .and_then(|metadata| metadata.modified())
+                    .ok()
+            })
+            .collect::<Option<Vec<_>>>()
+            .and_then(|modified| modified.into_iter().min());
+
+        let Some(built_at) = built_at else {
+            return false;
+        };
+
+        !project.has_project_configuration_changed
+            && self
+                .collect_project_dependencies(project)
+                .into_iter()
+                .all(|dependency| {
+                    self.is_up_to_date(dependency)
+                        && fs::metadata(self.get_output_file(dependency))
+                            .and_then(|metadata| metadata.modified())
+                            .is_ok_and(|modified| modified <= built_at)
+                })
+    }
+
+    fn collect_sources_to_compile(
+        &self,
+        project: &Project,
+    ) -> Vec<(CompilationUnit, RecompileReason)> {
+        // Existing objects are overwritten rather than deleted up front, so an interrupted
+        // build still leaves the previous ones behind
+        if self.force {
+            return self
+                .collect_compilation_units(project)
+                .into_iter()
+                .map(|unit| (unit, RecompileReason::Forced))
+                .collect();
+        }
+
+        let fingerprints = self.get_compilation_fingerprints(project);
+        let object_files_modified = self.collect_modification_times(&project.build_directory());
+
+        // Only an edited build configuration is expected to change the fingerprints, which then
+        // invalidate every unit of their language without hashing its sources
+        let changed_languages = if project.has_project_configuration_changed {
+            [Language::C, Language::Cpp]
+                .into_iter()
+                .filter(|language| fingerprints.has_changed(project, *language))
+                .collect::<Vec<_>>()
+        } else {
+            Vec::new()
+        };
+
+        // Each worker maps one source at a time, so memory stays bounded by the number of jobs
+        // rather than the size of the project
+        self.collect_compilation_units(project)
+            .into_par_iter()
+            .filter_map(|unit| {
+                let object_file_modified = object_files_modified
+                    .get(&unit.object_file(project))
+                    .copied();
+                let fingerprint_changed =
+                    changed_languages.contains(&self.get_source_language(project, &unit.source));
+
+                self.get_recompile_reason(
+                    project,
+                    &unit,
+                    object_file_modified,
+                    fingerprint_changed,
+                    &fingerprints,
+                )
+                .map(|reason| (unit, reason))
+            })
+            .collect::<Vec<_>>()
+    }
+
+    /// Objects are shared by content, so the key covers the preprocessed unit, which includes the
+    /// headers and defines it's compiled with, along with its flags and the compiler. Coverage
+    /// writes notes next to the object, which the cache doesn't keep, so it's never used then.
+    #[allow(clippy::too_many_arguments)]
+    fn get_object_cache_key(
+        &self,
+        project: &Project,
+        unit: &CompilationUnit,
+        fingerprints: &CompilationFingerprints,
+        compiler_identities: &(String, String),
+        c_compiler: &dyn CCompiler,
+        c_compilation_settings: &CCompilationSettings,
+        cpp_compiler: &dyn CppCompiler,
+        cpp_compilation_settings: &CppCompilationSettings,
+    ) -> Option<String> {
+        if self.get_coverage(project) {
+            return None;
+        }
+
+        let language = self.get_source_language(project, &unit.source);
+        let preprocessed_file = unit
+            .object_file(project)
+            .with_extension(SourceOutput::Preprocessed.extension(language));
+
+        self.compile_source_file(
+            project,
+            &unit.source,
+            SourceOutput::Preprocessed,
+            &preprocessed_file,
+            c_compiler,
+            c_compilation_settings,
+            cpp_compiler,
+            cpp_compilation_settings,
+        )
+        .ok()?;
+
+        let preprocessed_hash = File::open(&preprocessed_file).and_then(|file| hash_file(&file));
+        let _ = fs::remove_file(&preprocessed_file);
+        let mut hasher = blake3::Hasher::new();
+
+        hasher.update(
+            self.hash_compilation_unit(project, unit, fingerprints)
+                .ok()?
+                .as_bytes(),
+        );
+        hasher.update(preprocessed_hash.ok()?.as_bytes());
+        hasher.update(
+            match language {
+                Language::C => &compiler_identities.0,
+                Language::Cpp => &compiler_identities.1,
+            }
+            .as_bytes(),
+        );
+
+        Some(hasher.finalize().to_hex().to_string())
+    }
+
+    /// Reads the modification times of a directory's files in one pass, instead of looking up
+    /// every object file on its own.
+    fn collect_modification_times(&self, directory: &Path) -> HashMap<PathBuf, SystemTime> {
+        fs::read_dir(directory)
+            .into_iter()
+            .flatten()
+            .flatten()
+            .filter_map(|entry| Some((entry.path(), entry.metadata().ok()?.modified().ok()?)))
+            .collect::<HashMap<_, _>>()
+    }
+
+    /// Returns `None` when the cached object is still up to date. Modification times only tell
+    /// sources apart from flags once the hash already differs. Headers are compared by content
+    /// against the previous build, or by modification time when it didn't record them.
+    fn get_recompile_reason(
+        &self,
+        project: &Project,
+        unit: &CompilationUnit,
+        object_file_modified: Option<SystemTime>,
+        fingerprint_changed: bool,
+        fingerprints: &CompilationFingerprints,
+    ) -> Option<RecompileReason> {
+        let Some(hash) = project.hashes.get(&unit.source) else {
+            return Some(RecompileReason::NotCompiledBefore);
+        };
+
+        let Some(object_file_modified) = object_file_modified else {
+            return Some(RecompileReason::ObjectMissing);
+        };
+
+        let is_newer_than_object_file = |path: &Path| {
+            fs::metadata(path)
+                .and_then(|metadata| metadata.modified())
+                .map(|modified| modified > object_file_modified)
+                .unwrap_or(true)
+        };
+
+        let source_changed = fingerprint_changed
+            || self
+                .hash_compilation_unit(project, unit, fingerprints)
+                .map(|current_hash| *hash != current_hash)
+                .unwrap_or(true);
+
+        if source_changed {
+            return Some(
+                if unit
+                    .sources
+                    .iter()
+                    .any(|source| is_newer_than_object_file(&project.base_path.join(source)))
+                {
+                    RecompileReason::SourceChanged
+                } else {
+                    RecompileReason::FlagsChanged
+                },
+            );
+        }
+
+        let dependency_file_content = fs::read_to_string(
+            unit.object_file(project)
+                .with_extension(DEPENDENCY_FILE_EXTENSION),
+        )
+        .ok()?;
+        // The compiled file comes first, and batched sources are already covered by the hash
+        let dependencies = parse_dependency_file(&dependency_file_content)
+            .into_iter()
+            .skip(1)
+            .collect::<Vec<_>>();
+
+        if dependencies.is_empty() {
+            return None;
+        }
+
+        let sources = unit
+            .sources
+            .iter()
+            .filter_map(|source| project.base_path.join(source).canonicalize().ok())
+            .collect::<Vec<_>>();
+
+        dependencies
+            .into_iter()
+            .filter(|dependency| {
+                Path::new(dependency)
+                    .relative_to(&project.base_path)
+                    .is_none_or(|dependency| !project.is_excluded(&dependency.to_string_lossy()))
+            })
+            .filter(|dependency| {
+                fs::canonicalize(dependency)
+                    .map(|dependency| !sources.contains(&dependency))
+                    .unwrap_or(true)
+            })
+            .find(|dependency| match project.file_hashes.get(dependency) {
+                Some(previous) => hash_file_cached(project, Path::new(dependency))
+                    .map(|hash| hash.to_hex().as_str() != previous.hash)
+                    .unwrap_or(true),
+                None => {
+                    // Recorded for the next build, which can then compare it by content
+                    let _ = hash_file_cached(project, Path::new(dependency));
+
+                    is_newer_than_object_file(Path::new(dependency))
+                }
+            })
+            .map(RecompileReason::HeaderChanged)
+    }
+
+    /// Stubs are rewritten before every compilation, as their object only depends on the hashes
+    /// of the batched sources.
+    fn write_unity_stub(&self, project: &Project, unit: &CompilationUnit) -> Result<(), io::Error> {
+        let content = unit
+            .sources
+            .iter()
+            .map(|source| {
+                project
+                    .base_path
+                    .join(source)
+                    .canonicalize()
+                    .map(|path| format!("#include \"{}\"\n", path.display()))
+            })
+            .collect::<Result<String, _>>()?;
+
+        fs::write(&unit.source, content)
+    }
+
+    fn collect_project_dependencies<'a>(&self, project: &'a Project) -> Vec<&'a Project> {
+        project
+            .dependencies
+            .iter()
+            .filter_map(|dependency| match dependency {
+                Dependency::Project(project) => Some(project.as_ref()),
+                _ => None,
+            })
+            .collect::<Vec<_>>()
+    }
+
+    /// Every project reachable through project dependencies, each once and dependents before
+    /// their dependencies.
+    fn collect_transitive_project_dependencies<'a>(
+        &self,
+        project: &'a Project,
+    ) -> Vec<&'a Project> {
+        fn visit<'a>(
+            project: &'a Project,
+            visited: &mut HashSet<*const Project>,
+            post_order: &mut Vec<&'a Project>,
+        ) {
+            for dependency in &project.dependencies {
+                if let Dependency::Project(subproject) = dependency {
+                    if visited.insert(Arc::as_ptr(subproject)) {
+                        visit(subproject, visited, post_order);
+
+                        post_order.push(subproject);
+                    }
+                }
+            }
+        }
+
+        let mut post_order = Vec::new();
+
+        visit(project, &mut HashSet::new(), &mut post_order);
+
+        post_order.reverse();
+
+        post_order
+    }
+
+    /// A dynamic library only records its own dependencies, so the final link needs every
+    /// dynamic library in the graph, not just the direct ones.
+    fn collect_dynamic_library_dependencies<'a>(&self, project: &'a Project) -> Vec<&'a Project> {
+        self.collect_transitive_project_dependencies(project)
+            .into_iter()
+            .filter(|subproject| subproject.distribution == Distribution::DynamicLibrary)
+            .collect::<Vec<_>>()
+    }
+
+    /// Libraries are ordered so that each comes before the ones it depends on, as the linker
+    /// only resolves symbols against libraries that follow.
+    fn collect_libraries(&self, project: &Project) -> Vec<String> {
+        self.collect_static_library_dependencies(project)
+            .into_iter()
+            .chain(self.collect_dynamic_library_dependencies(project))
+            .map(|subproject| subproject.name.clone())
+            .chain(
+                project
+                    .dependencies
+                    .iter()
+                    .flat_map(|dependency| match dependency {
+                        Dependency::System { name, .. } => vec![name.clone()],
+                        Dependency::Prebuilt { libraries, .. } => libraries.clone(),
+                        _ => vec![],
+                    }),
+            )
+            .chain(project.libraries.iter().cloned())
+            .collect::<Vec<_>>()
+    }
+
+    /// The system libraries among [`Build::collect_libraries`] that have to be linked statically.
+    fn collect_static_system_libraries(&self, project: &Project) -> Vec<String> {
+        project
+            .dependencies
+            .iter()
+            .filter_map(|dependency| match dependency {
+                Dependency::System {
+                    name,
+                    kind: LinkKind::Static,
+                } => Some(name.clone()),
+                _ => None,
+            })
+            .collect::<Vec<_>>()
+    }
+
+    pub(crate) fn collect_includes(&self, project: &Project) -> Vec<String> {
+        project
+            .includes
+            .iter()
+            .cloned()
+            .chain(
+                project
+                    .dependencies
+                    .iter()
+                    .flat_map(|dependency| match dependency {
+                        Dependency::PkgConfig { includes, .. } => includes.clone(),
+                        Dependency::Prebuilt {
+                            include_directory, ..
+                        } => vec![include_directory.clone()],
+                        _ => vec![],
+                    }),
+            )
+            .collect::<Vec<_>>()
+    }
+
+    pub(crate) fn collect_pkg_config_compile_flags(&self, project: &Project) -> Vec<String> {
+        project
+            .dependencies
+            .iter()
+            .flat_map(|dependency| match dependency {
+                Dependency::PkgConfig { compile_flags, .. } => compile_flags.clone(),
+                _ => vec![],
+            })
+            .collect::<Vec<_>>()
+    }
+
+    fn collect_link_flags(&self, project: &Project) -> Vec<String> {
+        project
+            .dependencies
+            .iter()
+            .flat_map(|dependency| match dependency {
+                Dependency::PkgConfig { link_flags, .. } => link_flags.clone(),
+                _ => vec![],
+            })
+            .chain(project.link_flags.iter().cloned())
+            .collect::<Vec<_>>()
+    }
+
+    fn collect_library_search_paths(&self, project: &Project) -> Vec<String> {
+        self.collect_static_library_dependencies(project)
+            .into_iter()
+            .chain(self.collect_dynamic_library_dependencies(project))
+            .map(|project| project.build_directory().to_string_lossy().into_owned())
+            .chain(
+                project
+                    .dependencies
+                    .iter()
+                    .filter_map(|dependency| match dependency {
+                        Dependency::Prebuilt {
+                            library_directory, ..
+                        } => Some(library_directory.clone()),
+                        _ => None,
+                    }),
+            )
+            .chain(project.library_search_paths.iter().cloned())
+            .collect::<Vec<_>>()
+    }
+
+    /// Collects the static libraries the project links against, including the transitive ones,
+    /// with each library appearing once and before the libraries it depends on.
+    fn collect_static_library_dependencies<'a>(&self, project: &'a Project) -> Vec<&'a Project> {
+        fn visit<'a>(
+            project: &'a Project,
+            visited: &mut HashSet<*const Project>,
+            post_order: &mut Vec<&'a Project>,
+        ) {
+            for dependency in &project.dependencies {
+                if let Dependency::Project(subproject) = dependency {
+                    if subproject.distribution == Distribution::StaticLibrary
+                        && visited.insert(Arc::as_ptr(subproject))
+                    {
+                        visit(subproject, visited, post_order);
+
+                        post_order.push(subproject);
+                    }
+                }
+            }
+        }
+
+        let mut post_order = Vec::new();
+
+        visit(project, &mut HashSet::new(), &mut post_order);
+
+        post_order.reverse();
+
+        post_order
+    }
+
+    /// Leaves out the objects of binary sources, which only end up in their own binary.
+    fn collect_object_files(&self, project: &Project) -> Vec<PathBuf> {
+        self.collect_compilation_units(project)
+            .iter()
+            .filter(|unit| !project.is_binary_source(&unit.source))
+            .map(|unit| unit.object_file(project))
+            .collect::<Vec<_>>()
+    }
+
+    fn collect_artifacts(&self, project: &Project) -> Vec<PathBuf> {
+        let mut artifacts = Vec::new();
+
+        if project.distribution == Distribution::DynamicLibrary {
+            artifacts.push(self.get_output_file(project));
+            artifacts.extend(self.get_soname_file(project));
+        }
+
+        for dependency in &project.dependencies {
+            if let Dependency::Project(subproject) = dependency {
+                artifacts.append(&mut self.collect_artifacts(subproject));
+            }
+        }
+
+        artifacts
+    }
+
+    fn serialize_hashes(&self, hashes: HashMap<String, Hash>) -> String {
+        serde_json::to_string_pretty(
+            &hashes
+                .into_iter()
+                .map(|(key, value)| (key, Hash::to_string(&value)))
+                .collect::<HashMap<_, _>>(),
+        )
+        .unwrap()
+    }
+
+    /// Records the hashes of the files a freshly compiled unit depends on, so the next build can
+    /// tell whether they changed without comparing modification times.
+    fn hash_dependencies(&self, project: &Project, unit: &CompilationUnit) {
+        let Ok(dependency_file_content) = fs::read_to_string(
+            unit.object_file(project)
+                .with_extension(DEPENDENCY_FILE_EXTENSION),
+        ) else {
+            return;
+        };
+
+        for dependency in parse_dependency_file(&dependency_file_content)
+            .into_iter()
+            .skip(1)
+        {
+            let _ = hash_file_cached(project, Path::new(&dependency));
+        }
+    }
+
+    /// Saves the hashes taken by this build, which drops the files it no longer read.
+    fn save_file_hashes(&self, project: &Project) -> Result<(), io::Error> {
+        let racy_since = (SystemTime::now() - RACY_MODIFICATION_WINDOW)
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_nanos() as u64)
+            .unwrap_or_default();
+        let file_hashes = project
+            .current_file_hashes
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(path, file_hash)| {
+                let mut file_hash = file_hash.clone();
+
+                file_hash.modified = file_hash.modified.filter(|modified| *modified < racy_since);
+
+                (path.clone(), file_hash)
+            })
+            .collect::<BTreeMap<_, _>>();
+
+        fs::write(
+            project.file_hashes_file(),
+            serde_json::to_string_pretty(&file_hashes).unwrap(),
+        )
+    }
+
+    fn create_directories(&self, project: &Project) -> Result<(), io::Error> {
+        fs::create_dir_all(project.build_directory())?;
+        fs::create_dir_all(project.cache_directory())?;
+
+        Ok(())
+    }
+
+    /// Stops after the first level with a failed dependency, unless asked to keep going, in
+    /// which case everything that doesn't depend on a failed project is still built. The errors
+    /// are returned along with the name of the project they come from.
+    fn build_dependencies(
+        &self,
+        project: &Project,
+        toolchain_configuration: &ToolchainConfiguration,
+        c_compiler: &dyn CCompiler,
+        cpp_compiler: &dyn CppCompiler,
+        archiver: &dyn Archiver,
+    ) -> Result<Vec<CompilationTiming>, Vec<(String, ProjectBuildError)>> {
+        let mut timings = Vec::new();
+        let mut failures = Vec::new();
+        let mut failed_projects = HashSet::new();
+
+        for level in self.collect_dependency_levels(project) {
+            let (buildable, skipped): (Vec<_>, Vec<_>) = level
+                .into_iter()
+                .partition(|subproject| !self.depends_on_any(subproject, &failed_projects));
+
+            for subproject in skipped {
+                log_error!(
+                    "Skipping {} as one of its dependencies failed to build",
+                    subproject.name
+                );
+                failed_projects.insert(project_key(subproject));
+            }
+
+            let results = buildable
+                .par_iter()
+                .map(|subproject| {
+                    let units = self.collect_sources_to_compile(subproject);
+
+                    // Rewriting an unchanged artifact would only make its dependents relink
+                    if units.is_empty() && self.are_artifacts_up_to_date(subproject) {
+                        return Ok(Vec::new());
+                    }
+
+                    if subproject.toolchain == project.toolchain {
+                        return self.build(subproject, units, c_compiler, cpp_compiler, archiver);
+                    }
+
+                    let toolchain_configuration =
+                        toolchain_configuration.with_project_toolchain(&subproject.toolchain);
+                    let c_compiler = self
+                        .create_c_compiler(&toolchain_configuration)
+                        .ok_or_else(|| {
+                            ProjectBuildError::ToolNotFound(
+                                toolchain_configuration
+                                    .tool_not_found("C compiler", C_COMPILER_SETTING),
+                            )
+                        })?;
+                    let cpp_compiler = self
+                        .create_cpp_compiler(&toolchain_configuration)
+                        .ok_or_else(|| {
+                            ProjectBuildError::ToolNotFound(
+                                toolchain_configuration
+                                    .tool_not_found("C++ compiler", CPP_COMPILER_SETTING),
+                            )
+                        })?;
+                    let archiver =
+                        self.create_archiver(&toolchain_configuration)
+                            .ok_or_else(|| {
+                                ProjectBuildError::ToolNotFound(
+                                    toolchain_configuration
+                                        .tool_not_found("archiver", ARCHIVER_SETTING),
+                                )
+                            })?;
+
+                    self.build(
+                        subproject,
+                        units,
+                        c_compiler.as_ref(),
+                        cpp_compiler.as_ref(),
+                        archiver.as_ref(),
+                    )
+                })
+                .collect::<Vec<_>>();
+
+            for (subproject, result) in buildable.into_iter().zip(results) {
+                match result {
+                    Ok(project_timings) => timings.extend(project_timings),
+                    Err(err) => {
+                        failed_projects.insert(project_key(subproject));
+                        failures.push((subproject.name.clone(), err));
+                    }
+                }
+            }
+
+            if !failures.is_empty() && !self.keep_going {
+                break;
+            }
+        }
+
+        if failures.is_empty() {
+            Ok(timings)
+        } else {
+            Err(failures)
+        }
+    }
+
+    fn depends_on_any(&self, project: &Project, projects: &HashSet<PathBuf>) -> bool {
+        self.collect_project_dependencies(project)
+            .into_iter()
+            .any(|subproject| {
+                projects.contains(&project_key(subproject))
+                    || self.depends_on_any(subproject, projects)
+            })
+    }
+
+    /// Groups the project's transitive dependencies into levels, where every project only
+    /// depends on projects from the preceding levels, so each level can be built in parallel.
+    fn collect_dependency_levels<'a>(&self, project: &'a Project) -> Vec<Vec<&'a Project>> {
+        let mut assigned_levels = HashMap::new();
+
+        self.assign_dependency_levels(project, &mut assigned_levels);
+
+        let mut levels: Vec<Vec<&Project>> = Vec::new();
+
+        for (level, subproject) in assigned_levels.into_values() {
+            if levels.len() <= level {
+                levels.resize_with(level + 1, Vec::new);
+            }
+
+            levels[level].push(subproject);
+        }
+
+        for level in &mut levels {
+            level.sort_by(|a, b| a.base_path.cmp(&b.base_path));
+        }
+
+        levels
+    }
+
+    fn assign_dependency_levels<'a>(
+        &self,
+        project: &'a Project,
+        assigned_levels: &mut HashMap<PathBuf, (usize, &'a Project)>,
+    ) -> usize {
+        let mut level = 0;
+
+        for subproject in self.collect_project_dependencies(project) {
+            let subproject_level = self.assign_dependency_levels(subproject, assigned_levels);
+
+            assigned_levels
+                .entry(project_key(subproject))
+                .and_modify(|(assigned_level, _)| {
+                    *assigned_level = (*assigned_level).max(subproject_level)
+                })
+                .or_insert((subproject_level, subproject));
+
+            level = level.max(subproject_level + 1);
+        }
+
+        level
+    }
+
+    fn create_compilation_environment(&self, project: &Project) -> CompilationEnvironment {
+        let pkg_config_compile_flags = self.collect_pkg_config_compile_flags(project);
+        let (mut c_additional_pre_arguments, c_additional_post_arguments) =
+            self.get_gcc_arguments(project);
+        let (mut cpp_additional_pre_arguments, cpp_additional_post_arguments) =
+            self.get_gpp_arguments(project);
+
+        c_additional_pre_arguments.extend(pkg_config_compile_flags.iter().cloned());
+        cpp_additional_pre_arguments.extend(pkg_config_compile_flags.iter().cloned());
+
+        CompilationEnvironment {
+            includes: self.collect_includes(project),
+            defines: self.get_defines(project),
+            sanitizers: self.get_sanitizers(project),
+            target_cpu: self.get_target_cpu(project),
+            tune_cpu: self.get_tune_cpu(project),
+            precompiled_header_wrapper: self.get_precompiled_header_wrapper(project),
+            c_additional_pre_arguments,
+            c_additional_post_arguments,
+            cpp_additional_pre_arguments,
+            cpp_additional_post_arguments,
+        }
+    }
+
+    fn create_c_compilation_settings<'a>(
+        &self,
+        project: &'a Project,
+        environment: &'a CompilationEnvironment,
+    ) -> CCompilationSettings<'a> {
+        CCompilationSettings {
+            distribution: project.distribution.clone(),
+            standard: self.get_c_standard(project),
+            gnu_extensions: self.get_c_gnu_extensions(project),
+            optimization: self.get_optimization(project),
+            debug_info: self.get_debug_info(project),
+            target_cpu: environment.target_cpu.as_deref(),
+            tune_cpu: environment.tune_cpu.as_deref(),
+            lto: self.get_lto(project),
+            includes: &environment.includes,
+            defines: &environment.defines,
+            enable_all_warnings: self.get_enable_all_warnings(project),
+            treat_all_warnings_as_errors: self.get_treat_all_warnings_as_errors(project),
+            diagnostics_color: self.diagnostics_color,
+            json_diagnostics: self.message_format != MessageFormat::Human,
+            warnings: &project.warnings,
+            sanitizers: &environment.sanitizers,
+            coverage: self.get_coverage(project),
+            threads: project.threads,
+            precompiled_header: environment
+                .precompiled_header_wrapper
+                .as_deref()
+                .filter(|_| project.language == Language::C),
+            additional_pre_arguments: &environment.c_additional_pre_arguments,
+            additional_post_arguments: &environment.c_additional_post_arguments,
+        }
+    }
+
+    fn create_cpp_compilation_settings<'a>(
+        &self,
+        project: &'a Project,
+        environment: &'a CompilationEnvironment,
+    ) -> CppCompilationSettings<'a> {
+        CppCompilationSettings {
+            distribution: project.distribution.clone(),
+            standard: self.get_cpp_standard(project),
+            gnu_extensions: self.get_cpp_gnu_extensions(project),
+            optimization: self.get_optimization(project),
+            debug_info: self.get_debug_info(project),
+            target_cpu: environment.target_cpu.as_deref(),
+            tune_cpu: environment.tune_cpu.as_deref(),
+            lto: self.get_lto(project),
+            includes: &environment.includes,
+            defines: &environment.defines,
+            enable_all_warnings: self.get_enable_all_warnings(project),
+            treat_all_warnings_as_errors: self.get_treat_all_warnings_as_errors(project),
+            diagnostics_color: self.diagnostics_color,
+            json_diagnostics: self.message_format != MessageFormat::Human,
+            warnings: &project.warnings,
+            sanitizers: &environment.sanitizers,
+            coverage: self.get_coverage(project),
+            threads: project.threads,
+            precompiled_header: environment
+                .precompiled_header_wrapper
+                .as_deref()
+                .filter(|_| project.language == Language::Cpp),
+            additional_pre_arguments: &environment.cpp_additional_pre_arguments,
+            additional_post_arguments: &environment.cpp_additional_post_arguments,
+        }
+    }
+
+    fn build(
+        &self,
+        project: &Project,
+        units: Vec<(CompilationUnit, RecompileReason)>,
+        c_compiler: &dyn CCompiler,
+        cpp_compiler: &dyn CppCompiler,
+        archiver: &dyn Archiver,
+    ) -> Result<Vec<CompilationTiming>, ProjectBuildError> {
+        log_project_info!(&project.name, "Building {}", project.name);
 
         if let Err(err) = self.create_directories(project) {
             return Err(ProjectBuildError::FailedToCreateBakeryDirectories(err));
         }
 
-        static EMPTY: Vec<String> = vec![];
+        let compilation_units = self.collect_compilation_units(project);
+
+        // Units skipped as up to date keep their previous hashes
+        let mut current_hashes = compilation_units
+            .iter()
+            .filter(|unit| {
+                !units
+                    .iter()
+                    .any(|(compiled_unit, _)| compiled_unit == *unit)
+            })
+            .filter_map(|unit| {
+                project
+                    .hashes
+                    .get(&unit.source)
+                    .map(|hash| (unit.source.clone(), *hash))
+            })
+            .collect::<HashMap<_, _>>();
+
+        current_hashes.insert(
+            String::from(BUILD_CONFIGURATION_FILE),
+            project.configuration_hash,
+        );
+
+        let environment = self.create_compilation_environment(project);
+        let c_compilation_settings = self.create_c_compilation_settings(project, &environment);
+        let cpp_compilation_settings = self.create_cpp_compilation_settings(project, &environment);
+
+        let source_languages = units
+            .iter()
+            .map(|(unit, _)| self.get_source_language(project, &unit.source))
+            .collect::<HashSet<_>>();
+
+        if source_languages.contains(&Language::C) {
+            c_compiler
+                .check_standard_support(&c_compilation_settings.standard)
+                .map_err(ProjectBuildError::UnsupportedStandard)?;
+            c_compiler
+                .check_optimization_support(&c_compilation_settings.optimization)
+                .map_err(ProjectBuildError::UnsupportedOptimization)?;
+        }
+
+        if source_languages.contains(&Language::Cpp) {
+            cpp_compiler
+                .check_standard_support(&cpp_compilation_settings.standard)
+                .map_err(ProjectBuildError::UnsupportedStandard)?;
+            cpp_compiler
+                .check_optimization_support(&cpp_compilation_settings.optimization)
+                .map_err(ProjectBuildError::UnsupportedOptimization)?;
+        }
 
-        let mut current_hashes = HashMap::new();
+        let fingerprints = self.get_compilation_fingerprints(project);
+        let started_at = Instant::now();
 
-        current_hashes.insert(
-            String::from(BUILD_CONFIGURATION_FILE),
-            hash_file(
-                &File::open(project.base_path.join(BUILD_CONFIGURATION_FILE))
-                    .map_err(ProjectBuildError::FailedToOpenFile)?,
-            )
-            .map_err(ProjectBuildError::FailedToOpenFile)?,
+        current_hashes.extend(
+            fingerprints
+                .hashes()
+                .map(|(key, hash)| (String::from(key), hash)),
         );
 
-        let c_standard = self.get_c_standard(project);
-        let (c_additional_pre_arguments, c_additional_post_arguments) = project
-            .gcc
-            .as_ref()
-            .map(|gcc| {
-                (
-                    &gcc.additional_pre_arguments,
-                    &gcc.additional_post_arguments,
-                )
-            })
-            .unwrap_or_else(|| (&EMPTY, &EMPTY));
-        let c_compilation_settings = CCompilationSettings {
-            distribution: project.distribution.clone(),
-            standard: c_standard,
-            optimization: project.optimization.clone(),
-            includes: &project.includes,
-            enable_all_warnings: project.enable_all_warnings,
-            treat_all_warnings_as_errors: project.treat_all_warnings_as_errors,
-            additional_pre_arguments: c_additional_pre_arguments,
-            additional_post_arguments: c_additional_post_arguments,
-        };
+        let precompiled_header_hash = self.precompile_header(
+            project,
+            &fingerprints,
+            c_compiler,
+            &c_compilation_settings,
+            cpp_compiler,
+            &cpp_compilation_settings,
+        )?;
+
+        if let (Some(header), Some(hash)) = (&project.precompiled_header, precompiled_header_hash) {
+            current_hashes.insert(header.clone(), hash);
+        }
 
-        let cpp_standard = self.get_cpp_standard(project);
-        let (cpp_additional_pre_arguments, cpp_additional_post_arguments) = project
-            .gpp
+        for (unit, _) in units.iter().filter(|(unit, _)| unit.is_unity_batch()) {
+            self.write_unity_stub(project, unit)
+                .map_err(ProjectBuildError::FailedToOpenFile)?;
+        }
+
+        // Only asked for once per project, as it runs the compilers
+        let compiler_identities = self
+            .object_cache
             .as_ref()
-            .map(|gpp| {
-                (
-                    &gpp.additional_pre_arguments,
-                    &gpp.additional_post_arguments,
-                )
-            })
-            .unwrap_or_else(|| (&EMPTY, &EMPTY));
-        let cpp_compilation_settings = CppCompilationSettings {
-            distribution: project.distribution.clone(),
-            standard: cpp_standard,
-            optimization: project.optimization.clone(),
-            includes: &project.includes,
-            enable_all_warnings: project.enable_all_warnings,
-            treat_all_warnings_as_errors: project.treat_all_warnings_as_errors,
-            additional_pre_arguments: cpp_additional_pre_arguments,
-            additional_post_arguments: cpp_additional_post_arguments,
-        };
+            .map(|_| (c_compiler.identity(), cpp_compiler.identity()));
 
-        let (hashes, errors) = sources
+        let progress_bar = self.progress.as_ref().map(|progress| {
+            progress.add(
+                ProgressBar::new(units.len() as u64)
+                    .with_style(PROGRESS_STYLE.clone())
+                    .with_prefix(project.name.clone()),
+            )
+        });
+
+        let (hashes, timings, errors, warning_count) = units
             .par_iter()
             .fold(
-                || (HashMap::new(), Vec::new()),
-                |(mut hashes, mut errors), source| {
-                    println!("Compiling {}", source);
-
-                    match self.compile_source_file(
-                        project,
-                        source,
-                        c_compiler,
-                        &c_compilation_settings,
-                        cpp_compiler,
-                        &cpp_compilation_settings,
-                    ) {
-                        Ok(_) => match File::open(project.base_path.join(source)) {
-                            Ok(file) => match hash_file(&file) {
-                                Ok(hash) => {
-                                    hashes.insert((*source).clone(), hash);
-
-                                    println!("Compiled {}", source);
+                || (HashMap::new(), Vec::new(), Vec::new(), 0),
+                |(mut hashes, mut timings, mut errors, mut warning_count), (unit, reason)| {
+                    let source = &unit.source;
+                    let object_file = unit.object_file(project);
+                    let dependency_file = object_file.with_extension(DEPENDENCY_FILE_EXTENSION);
+                    let cache_key = compiler_identities
+                        .as_ref()
+                        .and_then(|compiler_identities| {
+                            self.get_object_cache_key(
+                                project,
+                                unit,
+                                &fingerprints,
+                                compiler_identities,
+                                c_compiler,
+                                &c_compilation_settings,
+                                cpp_compiler,
+                                &cpp_compilation_settings,
+                            )
+                        });
+                    let restored = match (&self.object_cache, &cache_key) {
+                        (Some(object_cache), Some(cache_key)) => {
+                            object_cache.restore(cache_key, &object_file, &dependency_file)
+                        }
+                        _ => false,
+                    };
+
+                    match (&progress_bar, restored) {
+                        (Some(progress_bar), _) => {
+                            progress_bar.set_message(source.clone());
+                            log::write_to_log_file(&log::with_project_prefix(
+                                &project.name,
+                                &if restored {
+                                    format!("Restored {} from the object cache", source)
+                                } else {
+                                    format!("Compiling {}", source)
+                                },
+                            ));
+                        }
+                        (None, true) => log_project_info!(
+                            &project.name,
+                            "Restored {} from the object cache",
+                            source
+                        ),
+                        (None, false) if self.verbose => {
+                            log_project_info!(&project.name, "Compiling {} ({})", source, reason)
+                        }
+                        (None, false) => log_project_info!(&project.name, "Compiling {}", source),
+                    }
+
+                    let compilation_started_at = Instant::now();
+                    let result = if restored {
+                        Ok(String::new())
+                    } else {
+                        self.compile_source_file(
+                            project,
+                            source,
+                            SourceOutput::Object,
+                            &object_file,
+                            c_compiler,
+                            &c_compilation_settings,
+                            cpp_compiler,
+                            &cpp_compilation_settings,
+                        )
+                    };
+
+                    // Warnings of sources that compiled would go unseen otherwise
+                    if let Ok(diagnostics) = &result {
+                        if self.message_format == MessageFormat::Human && !diagnostics.is_empty() {
+                            log_error!("{}\n", diagnostics.trim_end());
+                        }
+
+                        warning_count += self.count_diagnostics(diagnostics).1;
+                    }
+
+                    match result {
+                        Ok(_) => match self.hash_compilation_unit(project, unit, &fingerprints) {
+                            Ok(hash) if restored => {
+                                hashes.insert(source.clone(), hash);
+                                self.hash_dependencies(project, unit);
+                            }
+                            Ok(hash) => {
+                                hashes.insert(source.clone(), hash);
+                                self.hash_dependencies(project, unit);
+
+                                if let (Some(object_cache), Some(cache_key)) =
+                                    (&self.object_cache, &cache_key)
+                                {
+                                    if let Err(err) = object_cache.store(
+                                        cache_key,
+                                        &object_file,
+                                        &dependency_file,
+                                    ) {
+                                        log_error!(
+                                            "Warning: failed to cache the object of {}: {}",
+                                            source,
+                                            err
+                                        );
+                                    }
+                                }
+
+                                timings.push(CompilationTiming {
+                                    project: project.name.clone(),
+                                    source: source.clone(),
+                                    duration: compilation_started_at.elapsed(),
+                                });
+
+                                match &progress_bar {
+                                    Some(_) => log::write_to_log_file(&log::with_project_prefix(
+                                        &project.name,
+                                        &format!("Compiled {}", source),
+                                    )),
+                                    None => log_project_info!(&project.name, "Compiled {}", source),
                                 }
-                                Err(err) => errors.push(SourceFileBuildError::FailedToHash(err)),
-                            },
-                            Err(err) => errors.push(SourceFileBuildError::FailedToHash(err)),
+                            }
+                            Err(err) => {
+                                errors.push(SourceFileBuildError::FailedToHash(source.clone(), err))
+                            }
                         },
                         Err(err) => errors.push(err),
                     }
 
-                    (hashes, errors)
+                    if let Some(progress_bar) = &progress_bar {
+                        progress_bar.inc(1);
+                    }
+
+                    (hashes, timings, errors, warning_count)
                 },
             )
             .reduce(
-                || (HashMap::new(), Vec::new()),
-                |(mut hashes1, mut errors1), (hashes2, errors2)| {
+                || (HashMap::new(), Vec::new(), Vec::new(), 0),
+                |(mut hashes1, mut timings1, mut errors1, warning_count1),
+                 (hashes2, timings2, errors2, warning_count2)| {
                     hashes1.extend(hashes2);
+                    timings1.extend(timings2);
                     errors1.extend(errors2);
 
-                    (hashes1, errors1)
+                    (hashes1, timings1, errors1, warning_count1 + warning_count2)
                 },
             );
 
-        if !errors.is_empty() {
-            return Err(ProjectBuildError::CompilationError(errors));
+        self.warning_count
+            .fetch_add(warning_count, Ordering::Relaxed);
+
+        if let Some(progress_bar) = progress_bar {
+            progress_bar.finish_and_clear();
         }
 
         current_hashes.extend(hashes);
 
+        // Saved even when some sources failed, so the next build only retries those
         let hashes_content = self.serialize_hashes(current_hashes);
 
-        fs::write(project.base_path.join(BAKERY_HASHES_FILE), &hashes_content)
+        fs::write(project.hashes_file(), &hashes_content)
+            .map_err(ProjectBuildError::FailedToSaveHashes)?;
+        self.save_file_hashes(project)
             .map_err(ProjectBuildError::FailedToSaveHashes)?;
 
-        let project_dependencies = self.collect_project_dependencies(project);
-        let object_files = self.collect_object_files(project, &project_dependencies);
+        if !errors.is_empty() {
+            // The previous artifacts no longer match the cached hashes, so they must be relinked
+            for output_file in self.get_output_files(project) {
+                let _ = fs::remove_file(output_file);
+            }
 
-        let absolute_output_file_path = project
-            .base_path
-            .join(BAKERY_BUILD_DIRECTORY)
-            .join(&project.name)
-            .with_extension(match project.distribution {
-                Distribution::Executable => EXECUTABLE_EXTENSION,
-                Distribution::DynamicLibrary => DYNAMIC_LIBRARY_EXTENSION,
-                Distribution::StaticLibrary => STATIC_LIBRARY_EXTENSION,
-            });
+            return Err(ProjectBuildError::CompilationError(errors));
+        }
+
+        let object_files = self.collect_object_files(project);
+
+        let absolute_output_file_path = self.get_output_file(project);
 
         match project.distribution {
             Distribution::Executable | Distribution::DynamicLibrary => {
                 let libraries = self.collect_libraries(project);
-                let library_search_paths = self.collect_library_search_paths(&project_dependencies);
+                let static_libraries = self.collect_static_system_libraries(project);
+                let library_search_paths = self.collect_library_search_paths(project);
+                let link_flags = self.collect_link_flags(project);
+                let linking_language = self.get_linking_language(project);
+                let soname = self.get_soname(project);
                 let linking_setttings = LinkingSettings {
                     distribution: project.distribution.clone(),
                     includes: &project.includes,
                     libraries: &libraries,
+                    static_libraries: &static_libraries,
                     library_search_paths: &library_search_paths,
+                    link_flags: &link_flags,
+                    frameworks: &project.frameworks,
+                    sanitizers: &environment.sanitizers,
+                    coverage: self.get_coverage(project),
+                    lto: self.get_lto(project),
+                    threads: project.threads,
+                    math: project.math,
+                    static_runtime: project.static_runtime,
+                    fully_static: project.fully_static,
+                    // Dependencies of a dynamic library are looked up through its own rpath, as
+                    // the executable's doesn't apply to them
+                    rpath_origin: project.rpath
+                        && !self
+                            .collect_dynamic_library_dependencies(project)
+                            .is_empty(),
+                    soname: soname.as_deref(),
+                    linker: project.linker.as_deref().or(self.linker.as_deref()),
                 };
 
                 match project.distribution {
+                    Distribution::Executable if !project.binaries.is_empty() => {
+                        for binary in &project.binaries {
+                            log_project_info!(
+                                &project.name,
+                                "Generating executable {}",
+                                binary.name
+                            );
+
+                            let binary_file = self.get_binary_file(project, binary);
+                            let object_files = object_files
+                                .iter()
+                                .cloned()
+                                .chain(binary.sources.iter().map(|source| {
+                                    CompilationUnit::single(source).object_file(project)
+                                }))
+                                .collect::<Vec<_>>();
+
+                            match linking_language {
+                                Language::C => c_compiler.link_object_files(
+                                    &object_files,
+                                    &binary_file,
+                                    &linking_setttings,
+                                ),
+                                Language::Cpp => cpp_compiler.link_object_files(
+                                    &object_files,
+                                    &binary_file,
+                                    &linking_setttings,
+                                ),
+                            }
+                            .map_err(ProjectBuildError::LinkageError)?;
+
+                            log_project_info!(
+                                &project.name,
+                                "Generated executable {}",
+                                binary.name
+                            );
+
+                            self.strip_artifact(project, &binary_file);
+                        }
+                    }
                     Distribution::Executable => {
-                        println!("Generating executable");
+                        log_project_info!(&project.name, "Generating executable");
+
+                        match linking_language {
+                            Language::C => {
+                                c_compiler
+                                    .link_object_files(
+                                        &object_files,
+                                        &absolute_output_file_path,
+                                        &linking_setttings,
+                                    )
+                                    .map_err(ProjectBuildError::LinkageError)?;
+                            }
+                            Language::Cpp => {
+                                cpp_compiler
+                                    .link_object_files(
+                                        &object_files,
+                                        &absolute_output_file_path,
+                                        &linking_setttings,
+                                    )
+                                    .map_err(ProjectBuildError::LinkageError)?;
+                            }
+                        }
 
-                        match project.language {
+                        log_project_info!(&project.name, "Generated executable");
+
+                        self.strip_artifact(project, &absolute_output_file_path);
+                    }
+                    Distribution::DynamicLibrary => {
+                        log_project_info!(&project.name, "Generating dynamic library");
+
+                        match linking_language {
                             Language::C => {
                                 c_compiler
                                     .link_object_files(
@@ -453,203 +2051,1488 @@ impl Build {
                             }
                         }
 
-                        println!("Generated executable");
-                    }
-                    Distribution::DynamicLibrary => {
-                        println!("Generating dynamic library");
+                        self.create_soname_file(project)
+                            .map_err(|err| ProjectBuildError::LinkageError(err.to_string()))?;
+
+                        log_project_info!(&project.name, "Generated dynamic library");
+
+                        self.strip_artifact(project, &absolute_output_file_path);
+                    }
+                    _ => unreachable!(),
+                }
+            }
+            Distribution::StaticLibrary => {
+                log_project_info!(&project.name, "Generating static library");
+
+                archiver
+                    .archive_object_files(
+                        &object_files,
+                        &absolute_output_file_path,
+                        self.get_lto(project),
+                    )
+                    .map_err(ProjectBuildError::ArchivalError)?;
+
+                log_project_info!(&project.name, "Generated static library");
+            }
+        }
+
+        log_project_info!(
+            &project.name,
+            "Built {} in {:.2}s ({} files compiled, {} cached{})",
+            project.name,
+            started_at.elapsed().as_secs_f64(),
+            timings.len(),
+            compilation_units.len() - timings.len(),
+            match warning_count {
+                0 => String::new(),
+                1 => String::from(", 1 warning"),
+                _ => format!(", {} warnings", warning_count),
+            }
+        );
+
+        Ok(timings)
+    }
+
+    /// Compiles the configured header once before any source, reusing the previous result while
+    /// neither the header nor the project's compile settings changed.
+    fn precompile_header(
+        &self,
+        project: &Project,
+        fingerprints: &CompilationFingerprints,
+        c_compiler: &dyn CCompiler,
+        c_compilation_settings: &CCompilationSettings,
+        cpp_compiler: &dyn CppCompiler,
+        cpp_compilation_settings: &CppCompilationSettings,
+    ) -> Result<Option<Hash>, ProjectBuildError> {
+        let (Some(header), Some(wrapper)) = (
+            &project.precompiled_header,
+            self.get_precompiled_header_wrapper(project),
+        ) else {
+            return Ok(None);
+        };
+
+        let (fingerprint, extension) = match project.language {
+            Language::C => (&fingerprints.c, c_compiler.precompiled_header_extension()),
+            Language::Cpp => (
+                &fingerprints.cpp,
+                cpp_compiler.precompiled_header_extension(),
+            ),
+        };
+        let mut output_file = wrapper.clone().into_os_string();
+
+        output_file.push(".");
+        output_file.push(extension);
+
+        let output_file = PathBuf::from(output_file);
+        let hash = blake3::hash(fingerprint.as_bytes());
+
+        if project.hashes.get(header) == Some(&hash) && output_file.exists() {
+            return Ok(Some(hash));
+        }
+
+        log_project_info!(&project.name, "Precompiling {}", header);
+
+        let absolute_header_path = project
+            .base_path
+            .join(header)
+            .canonicalize()
+            .map_err(ProjectBuildError::FailedToOpenFile)?;
+
+        fs::write(
+            &wrapper,
+            format!("#include \"{}\"\n", absolute_header_path.display()),
+        )
+        .map_err(ProjectBuildError::FailedToOpenFile)?;
+
+        let result = match project.language {
+            Language::C => {
+                c_compiler.precompile_header(&wrapper, &output_file, c_compilation_settings)
+            }
+            Language::Cpp => {
+                cpp_compiler.precompile_header(&wrapper, &output_file, cpp_compilation_settings)
+            }
+        };
+
+        match &result {
+            Ok(diagnostics) | Err(diagnostics) => self.emit_json_diagnostics(diagnostics),
+        }
+
+        result
+            .map(|_| Some(hash))
+            .map_err(ProjectBuildError::PrecompilationError)
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn compile_source_file(
+        &self,
+        project: &Project,
+        source: &str,
+        output: SourceOutput,
+        output_file: &Path,
+        c_compiler: &dyn CCompiler,
+        c_compilation_settings: &CCompilationSettings,
+        cpp_compiler: &dyn CppCompiler,
+        cpp_compilation_settings: &CppCompilationSettings,
+    ) -> Result<String, SourceFileBuildError> {
+        let absolute_source_file_path = project.base_path.join(source);
+
+        // Matching overrides are applied in order, so the last match wins
+        let overrides = project
+            .overrides
+            .iter()
+            .filter(|source_override| source_override.matches(source))
+            .map(|source_override| &source_override.configuration)
+            .collect::<Vec<_>>();
+        let optimization = overrides
+            .iter()
+            .rev()
+            .find_map(|configuration| configuration.optimization.clone());
+
+        let result =
+            match self.get_source_language(project, source) {
+                Language::C => {
+                    let additional_pre_arguments = c_compilation_settings
+                        .additional_pre_arguments
+                        .iter()
+                        .chain(overrides.iter().flat_map(|configuration| {
+                            configuration.additional_pre_arguments.iter()
+                        }))
+                        .cloned()
+                        .collect::<Vec<_>>();
+                    let additional_post_arguments = c_compilation_settings
+                        .additional_post_arguments
+                        .iter()
+                        .chain(overrides.iter().flat_map(|configuration| {
+                            configuration.additional_post_arguments.iter()
+                        }))
+                        .cloned()
+                        .collect::<Vec<_>>();
+                    let c_compilation_settings = CCompilationSettings {
+                        standard: overrides
+                            .iter()
+                            .rev()
+                            .find_map(|configuration| configuration.c.as_ref()?.standard.clone())
+                            .unwrap_or_else(|| c_compilation_settings.standard.clone()),
+                        gnu_extensions: overrides
+                            .iter()
+                            .rev()
+                            .find_map(|configuration| configuration.c.as_ref()?.gnu_extensions)
+                            .unwrap_or(c_compilation_settings.gnu_extensions),
+                        optimization: optimization
+                            .unwrap_or_else(|| c_compilation_settings.optimization.clone()),
+                        additional_pre_arguments: &additional_pre_arguments,
+                        additional_post_arguments: &additional_post_arguments,
+                        ..c_compilation_settings.clone()
+                    };
+
+                    match output {
+                        SourceOutput::Object => c_compiler.compile_source_file(
+                            &absolute_source_file_path,
+                            output_file,
+                            &c_compilation_settings,
+                        ),
+                        SourceOutput::Assembly { intel_syntax } => c_compiler.generate_assembly(
+                            &absolute_source_file_path,
+                            output_file,
+                            intel_syntax,
+                            &c_compilation_settings,
+                        ),
+                        SourceOutput::Preprocessed => c_compiler.preprocess(
+                            &absolute_source_file_path,
+                            output_file,
+                            &c_compilation_settings,
+                        ),
+                    }
+                }
+                Language::Cpp => {
+                    let additional_pre_arguments = cpp_compilation_settings
+                        .additional_pre_arguments
+                        .iter()
+                        .chain(overrides.iter().flat_map(|configuration| {
+                            configuration.additional_pre_arguments.iter()
+                        }))
+                        .cloned()
+                        .collect::<Vec<_>>();
+                    let additional_post_arguments = cpp_compilation_settings
+                        .additional_post_arguments
+                        .iter()
+                        .chain(overrides.iter().flat_map(|configuration| {
+                            configuration.additional_post_arguments.iter()
+                        }))
+                        .cloned()
+                        .collect::<Vec<_>>();
+                    let cpp_compilation_settings = CppCompilationSettings {
+                        standard: overrides
+                            .iter()
+                            .rev()
+                            .find_map(|configuration| configuration.cpp.as_ref()?.standard.clone())
+                            .unwrap_or_else(|| cpp_compilation_settings.standard.clone()),
+                        gnu_extensions: overrides
+                            .iter()
+                            .rev()
+                            .find_map(|configuration| configuration.cpp.as_ref()?.gnu_extensions)
+                            .unwrap_or(cpp_compilation_settings.gnu_extensions),
+                        optimization: optimization
+                            .unwrap_or_else(|| cpp_compilation_settings.optimization.clone()),
+                        additional_pre_arguments: &additional_pre_arguments,
+                        additional_post_arguments: &additional_post_arguments,
+                        ..cpp_compilation_settings.clone()
+                    };
+
+                    match output {
+                        SourceOutput::Object => cpp_compiler.compile_source_file(
+                            &absolute_source_file_path,
+                            output_file,
+                            &cpp_compilation_settings,
+                        ),
+                        SourceOutput::Assembly { intel_syntax } => cpp_compiler.generate_assembly(
+                            &absolute_source_file_path,
+                            output_file,
+                            intel_syntax,
+                            &cpp_compilation_settings,
+                        ),
+                        SourceOutput::Preprocessed => cpp_compiler.preprocess(
+                            &absolute_source_file_path,
+                            output_file,
+                            &cpp_compilation_settings,
+                        ),
+                    }
+                }
+            };
+
+        match &result {
+            Ok(diagnostics) | Err(diagnostics) => self.emit_json_diagnostics(diagnostics),
+        }
+
+        result.map_err(|output| SourceFileBuildError::FailedToCompile(String::from(source), output))
+    }
+
+    /// Re-emits the compiler's machine-readable diagnostics as one normalized object per line,
+    /// or keeps them for the SARIF log.
+    fn emit_json_diagnostics(&self, diagnostics: &str) {
+        let flattened = parse_json_diagnostics(diagnostics);
+
+        match self.message_format {
+            MessageFormat::Human => {}
+            MessageFormat::Json => {
+                for diagnostic in flattened {
+                    let caret = &diagnostic["locations"][0]["caret"];
+
+                    println!(
+                        "{}",
+                        serde_json::json!({
+                            "file": caret["file"],
+                            "line": caret["line"],
+                            "column": caret["column"],
+                            "severity": diagnostic["kind"],
+                            "message": diagnostic["message"],
+                        })
+                    );
+                }
+            }
+            MessageFormat::Sarif => self.sarif_results.lock().unwrap().extend(
+                flattened
+                    .iter()
+                    .filter(|diagnostic| diagnostic["locations"][0]["caret"]["file"].is_string())
+                    .map(create_sarif_result),
+            ),
+        }
+    }
+
+    /// Writes the diagnostics collected so far as a SARIF 2.1.0 log.
+    fn write_sarif(&self, path: &Path) -> Result<(), io::Error> {
+        let results = mem::take(&mut *self.sarif_results.lock().unwrap());
+        let rules = results
+            .iter()
+            .filter_map(|result| result["ruleId"].as_str())
+            .unique()
+            .sorted()
+            .map(|rule| serde_json::json!({ "id": rule }))
+            .collect::<Vec<_>>();
+        let log = serde_json::json!({
+            "$schema": SARIF_SCHEMA,
+            "version": "2.1.0",
+            "runs": [{
+                "tool": {
+                    "driver": {
+                        "name": "gcc",
+                        "informationUri": "https://gcc.gnu.org/",
+                        "rules": rules,
+                    },
+                },
+                "results": results,
+            }],
+        });
+
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        fs::write(path, serde_json::to_string_pretty(&log).unwrap())
+    }
+
+    /// Lists every artifact of the project and its dependencies along with the objects, sources
+    /// and headers it was built from, for other build systems to pick up. The members of a
+    /// workspace are built one after another, so each adds its artifacts to those of the
+    /// previous ones.
+    fn write_dependency_manifest(
+        &mut self,
+        project: &Project,
+        path: &Path,
+    ) -> Result<(), io::Error> {
+        let artifacts = [project]
+            .into_iter()
+            .chain(self.collect_transitive_project_dependencies(project))
+            .flat_map(|project| self.describe_artifacts(project))
+            .collect::<Vec<_>>();
+
+        for artifact in artifacts {
+            if !self
+                .manifest_artifacts
+                .iter()
+                .any(|existing| existing["path"] == artifact["path"])
+            {
+                self.manifest_artifacts.push(artifact);
+            }
+        }
+
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        fs::write(
+            path,
+            serde_json::to_string_pretty(
+                &serde_json::json!({ "artifacts": self.manifest_artifacts }),
+            )
+            .unwrap(),
+        )
+    }
+
+    fn describe_artifacts(&self, project: &Project) -> Vec<serde_json::Value> {
+        let shared_units = self
+            .collect_compilation_units(project)
+            .into_iter()
+            .filter(|unit| !project.is_binary_source(&unit.source))
+            .collect::<Vec<_>>();
+        let libraries = self
+            .collect_project_dependencies(project)
+            .into_iter()
+            .map(|dependency| self.get_output_file(dependency))
+            .collect::<Vec<_>>();
+        let describe = |output_file: PathBuf, units: Vec<&CompilationUnit>| {
+            let sources = units
+                .iter()
+                .flat_map(|unit| &unit.sources)
+                .map(|source| project.base_path.join(source))
+                .collect::<Vec<_>>();
+            let canonical_sources = sources
+                .iter()
+                .filter_map(|source| source.canonicalize().ok())
+                .collect::<HashSet<_>>();
+            // Only units compiled at least once have their headers recorded
+            let headers = units
+                .iter()
+                .filter_map(|unit| {
+                    fs::read_to_string(
+                        unit.object_file(project)
+                            .with_extension(DEPENDENCY_FILE_EXTENSION),
+                    )
+                    .ok()
+                })
+                .flat_map(|content| parse_dependency_file(&content).into_iter().skip(1))
+                .map(PathBuf::from)
+                .filter(|dependency| {
+                    dependency
+                        .canonicalize()
+                        .map_or(true, |dependency| !canonical_sources.contains(&dependency))
+                })
+                .unique()
+                .sorted()
+                .collect::<Vec<_>>();
+
+            serde_json::json!({
+                "project": project.name,
+                "path": output_file,
+                "objects": units
+                    .iter()
+                    .map(|unit| unit.object_file(project))
+                    .collect::<Vec<_>>(),
+                "sources": sources,
+                "headers": headers,
+                "libraries": libraries,
+            })
+        };
+
+        if project.binaries.is_empty() {
+            vec![describe(
+                self.get_output_file(project),
+                shared_units.iter().collect(),
+            )]
+        } else {
+            project
+                .binaries
+                .iter()
+                .map(|binary| {
+                    let binary_units = binary
+                        .sources
+                        .iter()
+                        .map(|source| CompilationUnit::single(source))
+                        .collect::<Vec<_>>();
+
+                    describe(
+                        self.get_binary_file(project, binary),
+                        shared_units.iter().chain(&binary_units).collect(),
+                    )
+                })
+                .collect()
+        }
+    }
+
+    /// A failed strip leaves a working, just larger, artifact behind, so it only warns.
+    fn strip_artifact(&self, project: &Project, artifact: &Path) {
+        if !self.get_strip(project) {
+            return;
+        }
+
+        let Some(strip) = &self.strip else {
+            log_error!(
+                "Warning: {}, {} was not stripped",
+                self.strip_not_found,
+                project.name
+            );
+
+            return;
+        };
+
+        match strip.strip_file(artifact) {
+            Ok(_) => log_project_info!(&project.name, "Stripped {}", project.name),
+            Err(err) => log_error!("Warning: failed to strip {}: {}", project.name, err),
+        }
+    }
+
+    fn log_timings(&self, mut timings: Vec<CompilationTiming>) {
+        timings.sort_by_key(|timing| Reverse(timing.duration));
+
+        log_info!("Compilation timings:");
+
+        for timing in timings {
+            log_info!(
+                "{:>8.2}s  {}: {}",
+                timing.duration.as_secs_f64(),
+                timing.project,
+                timing.source
+            );
+        }
+    }
+
+    /// Counts the errors and warnings in a compiler's output, whichever format it was asked for.
+    fn count_diagnostics(&self, output: &str) -> (usize, usize) {
+        if self.message_format != MessageFormat::Human {
+            let diagnostics = parse_json_diagnostics(output);
+            let count = |kind: &str| {
+                diagnostics
+                    .iter()
+                    .filter(|diagnostic| diagnostic["kind"] == kind)
+                    .count()
+            };
+
+            return (count("error") + count("fatal error"), count("warning"));
+        }
+
+        let output = log::strip_color_codes(output);
+        let lines = output.lines();
+
+        (
+            lines
+                .clone()
+                .filter(|line| DIAGNOSTIC_ERROR_REGEX.is_match(line))
+                .count(),
+            lines
+                .filter(|line| DIAGNOSTIC_WARNING_REGEX.is_match(line))
+                .count(),
+        )
+    }
+
+    /// Compilation errors carry the whole compiler output, so each one is logged on its own
+    /// under a header naming the source instead of through the error's debug representation.
+    fn log_build_error(&self, err: &ProjectBuildError) {
+        match err {
+            ProjectBuildError::CompilationError(errors) => {
+                // Sources fail in whatever order the thread pool finishes them
+                let errors = errors
+                    .iter()
+                    .sorted_by_key(|error| match error {
+                        SourceFileBuildError::FailedToCompile(source, _)
+                        | SourceFileBuildError::FailedToHash(source, _) => source,
+                    })
+                    .collect::<Vec<_>>();
+
+                for error in &errors {
+                    match error {
+                        // Machine-readable output was already emitted along with the other
+                        // diagnostics
+                        SourceFileBuildError::FailedToCompile(source, _)
+                            if self.message_format != MessageFormat::Human =>
+                        {
+                            log_error!("Failed to compile {}\n", source);
+                        }
+                        SourceFileBuildError::FailedToCompile(source, output) => {
+                            log_error!("Failed to compile {}:", source);
+                            log_error!("{}\n", output.trim_end());
+                        }
+                        SourceFileBuildError::FailedToHash(source, err) => {
+                            log_error!("Failed to hash {}: {}\n", source, err);
+                        }
+                    }
+                }
+
+                let (error_count, warning_count) = errors
+                    .iter()
+                    .filter_map(|error| match error {
+                        SourceFileBuildError::FailedToCompile(_, output) => {
+                            Some(self.count_diagnostics(output))
+                        }
+                        _ => None,
+                    })
+                    .fold(
+                        (0, self.warning_count.load(Ordering::Relaxed)),
+                        |(error_count, warning_count), (errors, warnings)| {
+                            (error_count + errors, warning_count + warnings)
+                        },
+                    );
+
+                log_error!("{} errors, {} warnings", error_count, warning_count);
+                log_error!(
+                    "{} {} failed to compile",
+                    errors.len(),
+                    if errors.len() == 1 { "file" } else { "files" }
+                );
+            }
+            _ => log_error!("{}", err),
+        }
+    }
+
+    /// Compiles every test source into its own executable, returning each test's name and path.
+    /// Tests link against the project's library, or only against its dependencies when the
+    /// project is an executable, as its `main` would clash with the test's.
+    pub(crate) fn build_tests(&mut self, context: &TaskContext) -> Option<Vec<(String, PathBuf)>> {
+        let project = &context.project;
+        let toolchain_configuration = &context
+            .toolchain_configuration
+            .with_project_toolchain(&project.toolchain);
+
+        self.diagnostics_color = context.diagnostics_color;
+        self.message_format = context.message_format;
+        self.linker = context.toolchain_configuration.linker.clone();
+
+        let Some(c_compiler) = self.create_c_compiler(toolchain_configuration) else {
+            log_error!(
+                "{}",
+                toolchain_configuration.tool_not_found("C compiler", C_COMPILER_SETTING)
+            );
+
+            return None;
+        };
+        let Some(cpp_compiler) = self.create_cpp_compiler(toolchain_configuration) else {
+            log_error!(
+                "{}",
+                toolchain_configuration.tool_not_found("C++ compiler", CPP_COMPILER_SETTING)
+            );
+
+            return None;
+        };
+
+        let thread_pool = match ThreadPoolBuilder::new()
+            .num_threads(context.jobs.unwrap_or_default())
+            .build()
+        {
+            Ok(thread_pool) => thread_pool,
+            Err(err) => {
+                log_error!("Failed to create thread pool: {}", err);
+
+                return None;
+            }
+        };
+
+        let tests_directory = project.build_directory().join(TESTS_DIRECTORY);
+
+        if let Err(err) = fs::create_dir_all(&tests_directory) {
+            log_error!("Failed to create directories: {}", err);
+
+            return None;
+        }
+
+        let environment = self.create_compilation_environment(project);
+        let c_compilation_settings = self.create_c_compilation_settings(project, &environment);
+        let cpp_compilation_settings = self.create_cpp_compilation_settings(project, &environment);
 
-                        match project.language {
-                            Language::C => {
-                                c_compiler
-                                    .link_object_files(
-                                        &object_files,
-                                        &absolute_output_file_path,
-                                        &linking_setttings,
-                                    )
-                                    .map_err(ProjectBuildError::LinkageError)?;
-                            }
-                            Language::Cpp => {
-                                cpp_compiler
-                                    .link_object_files(
-                                        &object_files,
-                                        &absolute_output_file_path,
-                                        &linking_setttings,
-                                    )
-                                    .map_err(ProjectBuildError::LinkageError)?;
-                            }
-                        }
+        let libraries = self.collect_libraries(project);
+        let static_libraries = self.collect_static_system_libraries(project);
+        let library_search_paths = self.collect_library_search_paths(project);
+        let link_flags = self.collect_link_flags(project);
+        let artifacts = if project.distribution != Distribution::Executable {
+            vec![self.get_output_file(project)]
+        } else {
+            vec![]
+        };
 
-                        println!("Generated dynamic library");
+        let linking_settings = LinkingSettings {
+            distribution: Distribution::Executable,
+            includes: &project.includes,
+            libraries: &libraries,
+            static_libraries: &static_libraries,
+            library_search_paths: &library_search_paths,
+            link_flags: &link_flags,
+            frameworks: &project.frameworks,
+            sanitizers: &environment.sanitizers,
+            coverage: self.get_coverage(project),
+            lto: self.get_lto(project),
+            threads: project.threads,
+            math: project.math,
+            static_runtime: project.static_runtime,
+            fully_static: project.fully_static,
+            rpath_origin: false,
+            soname: None,
+            linker: project.linker.as_deref().or(self.linker.as_deref()),
+        };
+
+        let results = thread_pool.install(|| {
+            project
+                .tests
+                .par_iter()
+                .map(|test| {
+                    let name = Project::test_name(test);
+                    // Named like compilation units' objects, as tests may share a file name
+                    let output_name = format!(
+                        "{}-{}",
+                        Path::new(test).file_stem().unwrap().to_string_lossy(),
+                        &blake3::hash(test.as_bytes()).to_hex()[..OBJECT_FILE_HASH_LENGTH]
+                    );
+                    let object_file = tests_directory
+                        .join(&output_name)
+                        .with_extension(OBJECT_FILE_EXTENSION);
+                    let executable = tests_directory
+                        .join(&output_name)
+                        .with_extension(EXECUTABLE_EXTENSION);
+                    let object_files = iter::once(object_file.clone())
+                        .chain(artifacts.iter().cloned())
+                        .collect::<Vec<_>>();
+
+                    log_info!("Compiling {}", test);
+
+                    let absolute_test_path = project.base_path.join(test);
+                    let source_language = self.get_source_language(project, test);
+
+                    match source_language {
+                        Language::C => c_compiler
+                            .compile_source_file(
+                                &absolute_test_path,
+                                &object_file,
+                                &c_compilation_settings,
+                            )
+                            .map(|_| ()),
+                        Language::Cpp => cpp_compiler
+                            .compile_source_file(
+                                &absolute_test_path,
+                                &object_file,
+                                &cpp_compilation_settings,
+                            )
+                            .map(|_| ()),
+                    }?;
+
+                    if source_language == Language::Cpp
+                        || self.get_linking_language(project) == Language::Cpp
+                    {
+                        cpp_compiler.link_object_files(
+                            &object_files,
+                            &executable,
+                            &linking_settings,
+                        )?;
+                    } else {
+                        c_compiler.link_object_files(
+                            &object_files,
+                            &executable,
+                            &linking_settings,
+                        )?;
                     }
-                    _ => unreachable!(),
-                }
-            }
-            Distribution::StaticLibrary => {
-                println!("Generating static library");
 
-                archiver
-                    .archive_object_files(&object_files, &absolute_output_file_path)
-                    .map_err(ProjectBuildError::ArchivalError)?;
+                    Ok((name, executable))
+                })
+                .collect::<Vec<Result<_, String>>>()
+        });
+
+        let mut tests = Vec::new();
+        let mut failed = false;
+
+        for result in results {
+            match result {
+                Ok(test) => tests.push(test),
+                Err(err) => {
+                    log_error!("{}", err);
 
-                println!("Generated static library");
+                    failed = true;
+                }
             }
         }
 
-        println!("Built {}", project.name);
+        if failed {
+            log_error!("Failed to build tests");
 
-        Ok(())
+            return None;
+        }
+
+        Some(tests)
     }
 
-    fn compile_source_file(
-        &self,
-        project: &Project,
-        source: &str,
-        c_compiler: &dyn CCompiler,
-        c_compilation_settings: &CCompilationSettings,
-        cpp_compiler: &dyn CppCompiler,
-        cpp_compilation_settings: &CppCompilationSettings,
-    ) -> Result<(), SourceFileBuildError> {
-        let absolute_source_file_path = project.base_path.join(source);
-        let absolute_output_file_path = project
-            .base_path
-            .join(BAKERY_BUILD_DIRECTORY)
-            .join(PathBuf::from(source).file_name().unwrap())
-            .with_extension(OBJECT_FILE_EXTENSION);
+    /// Compiles the source given on the command line to assembly or just preprocesses it, with
+    /// the flags a build would use and without building anything else. The result is printed
+    /// unless an output file was given.
+    pub(crate) fn compile_requested_source(
+        &mut self,
+        context: &TaskContext,
+        output: SourceOutput,
+    ) -> Result<(), String> {
+        let project = &context.project;
+        let toolchain_configuration = &context
+            .toolchain_configuration
+            .with_project_toolchain(&project.toolchain);
 
-        match project.language {
-            Language::C => {
-                c_compiler
-                    .compile_source_file(
-                        &absolute_source_file_path,
-                        &absolute_output_file_path,
-                        c_compilation_settings,
-                    )
-                    .map_err(SourceFileBuildError::FailedToCompile)?;
-            }
-            Language::Cpp => {
-                cpp_compiler
-                    .compile_source_file(
-                        &absolute_source_file_path,
-                        &absolute_output_file_path,
-                        cpp_compilation_settings,
-                    )
-                    .map_err(SourceFileBuildError::FailedToCompile)?;
-            }
+        self.diagnostics_color = context.diagnostics_color;
+
+        let requested_source = context.source.as_deref().unwrap_or_default();
+        let source = project
+            .find_source(requested_source)
+            .ok_or_else(|| format!("{} is not one of the project's sources", requested_source))?;
+
+        // The compiler always needs a file to write to, printed results go to the build directory
+        let output_file = context.output.clone().unwrap_or_else(|| {
+            project
+                .build_directory()
+                .join(Path::new(source).file_name().unwrap())
+                .with_extension(output.extension(self.get_source_language(project, source)))
+        });
+
+        if let Some(parent) = output_file
+            .parent()
+            .filter(|parent| !parent.as_os_str().is_empty())
+        {
+            fs::create_dir_all(parent)
+                .map_err(|err| format!("Failed to create directories: {}", err))?;
+        }
+
+        let c_compiler = self
+            .create_c_compiler(toolchain_configuration)
+            .ok_or_else(|| {
+                toolchain_configuration.tool_not_found("C compiler", C_COMPILER_SETTING)
+            })?;
+        let cpp_compiler = self
+            .create_cpp_compiler(toolchain_configuration)
+            .ok_or_else(|| {
+                toolchain_configuration.tool_not_found("C++ compiler", CPP_COMPILER_SETTING)
+            })?;
+
+        let mut environment = self.create_compilation_environment(project);
+
+        // The wrapper only exists once a build precompiled the header
+        environment.precompiled_header_wrapper = environment
+            .precompiled_header_wrapper
+            .filter(|wrapper| wrapper.exists());
+
+        // With LTO the compiler would emit its intermediate representation instead of assembly
+        let c_compilation_settings = CCompilationSettings {
+            lto: Lto::Off,
+            ..self.create_c_compilation_settings(project, &environment)
+        };
+        let cpp_compilation_settings = CppCompilationSettings {
+            lto: Lto::Off,
+            ..self.create_cpp_compilation_settings(project, &environment)
+        };
+
+        self.compile_source_file(
+            project,
+            source,
+            output,
+            &output_file,
+            c_compiler.as_ref(),
+            &c_compilation_settings,
+            cpp_compiler.as_ref(),
+            &cpp_compilation_settings,
+        )
+        .map_err(|err| err.to_string())?;
+
+        if context.output.is_none() {
+            let content = fs::read_to_string(&output_file)
+                .map_err(|err| format!("Failed to read {}: {}", output_file.display(), err))?;
+
+            print!("{}", content);
         }
 
         Ok(())
     }
 
     fn copy_artifacts_to_build_directory(&self, project: &Project) -> Result<(), io::Error> {
-        for (index, artifact) in self.collect_artifacts(project).into_iter().enumerate() {
-            if index == 0 && project.distribution == Distribution::DynamicLibrary {
+        let build_directory = project.build_directory();
+
+        for artifact in self.collect_artifacts(project).into_iter().unique() {
+            if artifact.parent() == Some(build_directory.as_path()) {
                 continue;
             }
 
             fs::copy(
                 &artifact,
-                project
-                    .base_path
-                    .join(BAKERY_BUILD_DIRECTORY)
-                    .join(artifact.file_name().unwrap()),
+                build_directory.join(artifact.file_name().unwrap()),
             )?;
         }
 
         Ok(())
     }
-}
 
-impl Task for Build {
-    fn id(&self) -> &'static str {
-        "build"
-    }
+    fn build_project(&mut self, context: &TaskContext) -> eyre::Result<()> {
+        let project = &context.project;
+        let toolchain_configuration = &context
+            .toolchain_configuration
+            .with_project_toolchain(&project.toolchain);
+
+        self.diagnostics_color = context.diagnostics_color;
+        self.message_format = context.message_format;
+        self.verbose = context.verbose;
+        self.keep_going = context.keep_going;
+        self.force = context.force;
+        self.object_cache = context
+            .toolchain_configuration
+            .object_cache_location
+            .as_ref()
+            .map(|location| ObjectCache::new(PathBuf::from(location)));
+        self.strip = context
+            .toolchain_configuration
+            .strip_location
+            .clone()
+            .or_else(|| context.toolchain_configuration.locate("strip"))
+            .map(Strip::new);
+        self.strip_not_found = context
+            .toolchain_configuration
+            .tool_not_found("strip", STRIP_SETTING);
+
+        if let Some(linker) = &context.toolchain_configuration.linker {
+            if !config::is_valid_linker(linker) {
+                bail!(
+                    "Linker {} is not supported, use one of {} or an absolute path",
+                    linker,
+                    config::SUPPORTED_LINKERS.join(", ")
+                );
+            }
+        }
 
-    fn dependencies(&self) -> &[&'static str] {
-        &[]
-    }
+        self.linker = context.toolchain_configuration.linker.clone();
 
-    fn on_execute(&mut self, context: &TaskContext) {
-        let project = &context.project;
-        let toolchain_configuration = &context.toolchain_configuration;
+        // Keeps stdout a clean stream of diagnostics for tools parsing it
+        log::set_info_on_stderr(self.message_format == MessageFormat::Json);
+
+        let log_file = project.output_directory.join(BAKERY_BUILD_LOG_FILE);
+
+        if let Err(err) = log_file
+            .parent()
+            .map_or(Ok(()), fs::create_dir_all)
+            .and_then(|_| log::open_log_file(&log_file))
+        {
+            eprintln!("Failed to open build log: {}", err);
+        }
 
         let c_compiler = match self.create_c_compiler(toolchain_configuration) {
             Some(c_compiler) => c_compiler,
-            None => {
-                eprintln!("C compiler not found");
-
-                return;
-            }
+            None => bail!(toolchain_configuration.tool_not_found("C compiler", C_COMPILER_SETTING)),
         };
         let cpp_compiler = match self.create_cpp_compiler(toolchain_configuration) {
             Some(cpp_compiler) => cpp_compiler,
             None => {
-                eprintln!("C++ compiler not found");
-
-                return;
+                bail!(toolchain_configuration.tool_not_found("C++ compiler", CPP_COMPILER_SETTING))
             }
         };
         let archiver = match self.create_archiver(toolchain_configuration) {
             Some(archiver) => archiver,
-            None => {
-                eprintln!("Archiver not found");
+            None => bail!(toolchain_configuration.tool_not_found("Archiver", ARCHIVER_SETTING)),
+        };
 
-                return;
-            }
+        let thread_pool = match ThreadPoolBuilder::new()
+            .num_threads(context.jobs.unwrap_or_default())
+            .build()
+        {
+            Ok(thread_pool) => thread_pool,
+            Err(err) => bail!("Failed to create thread pool: {}", err),
         };
 
-        let sources = self.collect_sources_to_compile(project);
+        let units = thread_pool.install(|| self.collect_sources_to_compile(project));
 
-        if sources.is_empty() {
-            println!("Nothing to build");
+        if units.is_empty() && thread_pool.install(|| self.are_artifacts_up_to_date(project)) {
+            // Files touched without being edited were hashed again, which only has to happen once
+            let _ = self.save_file_hashes(project);
 
-            return;
+            log_info!("Nothing to build");
+
+            return Ok(());
         }
 
         if let Err(err) = self.create_directories(project) {
-            eprintln!("Failed to create directories: {}", err);
-
-            return;
+            bail!("Failed to create directories: {}", err);
         }
 
+        // Compiler output is printed as is, so progress bars are only shown where it can be
+        // printed around them and the per-file lines aren't asked for
+        self.progress = (!self.verbose
+            && self.message_format == MessageFormat::Human
+            && io::stderr().is_terminal())
+        .then(|| {
+            MultiProgress::with_draw_target(ProgressDrawTarget::stderr_with_hz(
+                PROGRESS_REFRESH_RATE,
+            ))
+        });
+
+        log::set_progress(self.progress.clone());
+
+        let mut timings = Vec::new();
+
         if !project.dependencies.is_empty() {
-            println!("Building dependencies");
+            log_info!("Building dependencies");
+
+            match thread_pool.install(|| {
+                self.build_dependencies(
+                    project,
+                    &context.toolchain_configuration,
+                    c_compiler.as_ref(),
+                    cpp_compiler.as_ref(),
+                    archiver.as_ref(),
+                )
+            }) {
+                Ok(dependency_timings) => {
+                    timings.extend(dependency_timings);
+
+                    log_info!("Built dependencies");
+                }
+                Err(failures) => {
+                    for (name, err) in &failures {
+                        log_error!("Failed to build {}:", name);
+                        self.log_build_error(err);
+                    }
 
-            match self.build_dependencies(
+                    if failures.len() > 1 {
+                        log_error!("{} dependencies failed to build", failures.len());
+                    }
+
+                    log::set_progress(None);
+
+                    bail!(
+                        "Skipping {} as its dependencies failed to build",
+                        project.name
+                    );
+                }
+            }
+        }
+
+        match thread_pool.install(|| {
+            self.build(
                 project,
+                units,
                 c_compiler.as_ref(),
                 cpp_compiler.as_ref(),
                 archiver.as_ref(),
-            ) {
-                Ok(_) => {
-                    println!("Built dependencies");
+            )
+        }) {
+            Ok(project_timings) => {
+                timings.extend(project_timings);
+
+                if context.timings {
+                    self.log_timings(timings);
                 }
-                Err(err) => {
-                    eprintln!("Failed to build dependencies: {}", err);
+            }
+            Err(err) => {
+                self.log_build_error(&err);
+                log::set_progress(None);
+
+                bail!("Failed to build {}", project.name);
+            }
+        }
+
+        log::set_progress(None);
+
+        self.copy_artifacts_to_build_directory(project)
+            .map_err(|err| eyre!("Failed to copy artifacts to build directory: {}", err))
+    }
+}
+
+impl Task for Build {
+    fn id(&self) -> &'static str {
+        "build"
+    }
+
+    fn dependencies(&self) -> &[&'static str] {
+        &[]
+    }
+
+    fn on_execute(&mut self, context: &TaskContext) -> eyre::Result<()> {
+        let result = self.build_project(context);
+
+        // Written even when the build failed, as that's when the diagnostics matter the most
+        if self.message_format == MessageFormat::Sarif {
+            let sarif_file = context.project.output_directory.join(BAKERY_SARIF_FILE);
+
+            match self.write_sarif(&sarif_file) {
+                Ok(_) => log_info!("Wrote diagnostics to {}", sarif_file.display()),
+                Err(err) => log_error!("Failed to write {}: {}", sarif_file.display(), err),
+            }
+        }
+
+        if let (Ok(_), Some(dependency_manifest)) = (&result, &context.emit_deps) {
+            if let Err(err) = self.write_dependency_manifest(&context.project, dependency_manifest)
+            {
+                bail!("Failed to write {}: {}", dependency_manifest.display(), err);
+            }
+        }
+
+        result
+    }
+}
+
+/// Collects the diagnostics of gcc's `-fdiagnostics-format=json` output along with the notes
+/// attached to them, converting clang's SARIF output to the same shape.
+fn parse_json_diagnostics(output: &str) -> Vec<serde_json::Value> {
+    fn flatten(diagnostic: &serde_json::Value, flattened: &mut Vec<serde_json::Value>) {
+        flattened.push(diagnostic.clone());
+
+        if let Some(children) = diagnostic["children"].as_array() {
+            for child in children {
+                flatten(child, flattened);
+            }
+        }
+    }
+
+    let mut flattened = Vec::new();
 
-                    return;
+    for line in output.lines() {
+        match serde_json::from_str(line) {
+            Ok(serde_json::Value::Array(diagnostics)) => {
+                for diagnostic in &diagnostics {
+                    flatten(diagnostic, &mut flattened);
                 }
             }
+            Ok(log @ serde_json::Value::Object(_)) => flattened.extend(
+                log["runs"]
+                    .as_array()
+                    .into_iter()
+                    .flatten()
+                    .filter_map(|run| run["results"].as_array())
+                    .flatten()
+                    .map(convert_sarif_result),
+            ),
+            _ => {}
         }
+    }
 
-        match self.build(
-            project,
-            sources,
-            c_compiler.as_ref(),
-            cpp_compiler.as_ref(),
-            archiver.as_ref(),
-        ) {
-            Ok(_) => {
-                if let Err(err) = self.copy_artifacts_to_build_directory(project) {
-                    eprintln!("Failed to copy artifacts to build directory: {}", err);
+    flattened
+}
+
+/// Maps one of clang's SARIF results to the shape of gcc's JSON diagnostics.
+fn convert_sarif_result(result: &serde_json::Value) -> serde_json::Value {
+    let location = &result["locations"][0]["physicalLocation"];
+    let uri = location["artifactLocation"]["uri"]
+        .as_str()
+        .unwrap_or_default();
+
+    serde_json::json!({
+        "kind": result["level"].as_str().unwrap_or("error"),
+        "message": result["message"]["text"],
+        "locations": [{
+            "caret": {
+                "file": uri.strip_prefix("file://").unwrap_or(uri),
+                "line": location["region"]["startLine"],
+                "column": location["region"]["startColumn"],
+            },
+        }],
+    })
+}
+
+/// Maps one of gcc's JSON diagnostics to a SARIF result, using the warning option that
+/// triggered it as the rule, or its kind when there is none.
+fn create_sarif_result(diagnostic: &serde_json::Value) -> serde_json::Value {
+    let caret = &diagnostic["locations"][0]["caret"];
+    let kind = diagnostic["kind"].as_str().unwrap_or("error");
+    let file = caret["file"].as_str().unwrap_or_default();
+    // Code scanning expects paths relative to the repository, which is assumed to be the current
+    // directory
+    let uri = env::current_dir()
+        .ok()
+        .and_then(|current_directory| Path::new(file).relative_to(&current_directory))
+        .filter(|path| !path.starts_with(".."))
+        .map(|path| path.to_string_lossy().replace('\\', "/"))
+        .unwrap_or_else(|| file.trim_start_matches("./").to_owned());
+
+    serde_json::json!({
+        "ruleId": diagnostic["option"].as_str().unwrap_or(kind),
+        "level": match kind {
+            "warning" => "warning",
+            "note" => "note",
+            _ => "error",
+        },
+        "message": { "text": diagnostic["message"] },
+        "locations": [{
+            "physicalLocation": {
+                "artifactLocation": { "uri": uri },
+                "region": {
+                    "startLine": caret["line"],
+                    "startColumn": caret["column"],
+                },
+            },
+        }],
+    })
+}
+
+/// Lists the prerequisites of a Makefile-style dependency file written by `-MMD`, the compiled
+/// file first.
+fn parse_dependency_file(content: &str) -> Vec<String> {
+    let content = content.replace("\\\r\n", " ").replace("\\\n", " ");
+    let Some((_, prerequisites)) = content.split_once(": ") else {
+        return Vec::new();
+    };
+
+    let mut dependencies = Vec::new();
+    let mut dependency = String::new();
+    let mut characters = prerequisites.chars().peekable();
+
+    while let Some(character) = characters.next() {
+        match character {
+            '\\' if characters.peek() == Some(&' ') => {
+                dependency.push(' ');
+                characters.next();
+            }
+            character if character.is_whitespace() => {
+                if !dependency.is_empty() {
+                    dependencies.push(mem::take(&mut dependency));
                 }
             }
-            Err(err) => eprintln!("{}", err),
+            character => dependency.push(character),
         }
     }
+
+    if !dependency.is_empty() {
+        dependencies.push(dependency);
+    }
+
+    dependencies
+}
+
+/// A unity batch is keyed by the hashes of all the sources it includes, in order.
+fn combine_source_hashes<E>(
+    unit: &CompilationUnit,
+    mut hash_source: impl FnMut(&str) -> Result<Hash, E>,
+) -> Result<Hash, E> {
+    if !unit.is_unity_batch() {
+        return hash_source(&unit.source);
+    }
+
+    let mut hasher = blake3::Hasher::new();
+
+    for source in &unit.sources {
+        hasher.update(hash_source(source)?.as_bytes());
+    }
+
+    Ok(hasher.finalize())
+}
+
+/// Identifies a project regardless of the relative path it was reached through.
+fn project_key(project: &Project) -> PathBuf {
+    project
+        .base_path
+        .canonicalize()
+        .unwrap_or_else(|_| project.base_path.clone())
+}
+
+/// Hashes a file once per build, reusing the previous build's hash when the file's modification
+/// time and size are exactly the ones it was taken at. Only equality is trusted, so a clock
+/// going backwards can't make an edited file look unchanged.
+fn hash_file_cached(project: &Project, path: &Path) -> Result<Hash, io::Error> {
+    let key = path.to_string_lossy().into_owned();
+
+    if let Some(hash) = project
+        .current_file_hashes
+        .lock()
+        .unwrap()
+        .get(&key)
+        .and_then(|file_hash| Hash::from_hex(&file_hash.hash).ok())
+    {
+        return Ok(hash);
+    }
+
+    let file = File::open(path)?;
+    let metadata = file.metadata()?;
+    let modified = metadata
+        .modified()?
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_nanos() as u64)
+        .unwrap_or_default();
+    let size = metadata.len();
+    let hash = match project
+        .file_hashes
+        .get(&key)
+        .filter(|file_hash| file_hash.modified == Some(modified) && file_hash.size == size)
+        .and_then(|file_hash| Hash::from_hex(&file_hash.hash).ok())
+    {
+        Some(hash) => hash,
+        None => hash_file(&file)?,
+    };
+
+    project.current_file_hashes.lock().unwrap().insert(
+        key,
+        FileHash {
+            modified: Some(modified),
+            size,
+            hash: hash.to_hex().to_string(),
+        },
+    );
+
+    Ok(hash)
 }
 
-fn hash_file(file: &File) -> Result<Hash, io::Error> {
+fn hash_file(mut file: &File) -> Result<Hash, io::Error> {
+    let length = file.metadata()?.len();
+
+    // Mapping a zero-byte file fails on several platforms, and for typical sources setting up
+    // the mapping costs more than just reading them
+    if length < MMAP_THRESHOLD {
+        let mut file_content = Vec::with_capacity(length as usize);
+
+        file.read_to_end(&mut file_content)?;
+
+        return Ok(blake3::hash(&file_content));
+    }
+
     let file_content = unsafe { MmapOptions::new().map(file)? };
 
     Ok(blake3::hash(&file_content))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::task::GraphFormat;
+    use std::{path::Path, process::Command};
+
+    fn write_project(root: &Path, name: &str, distribution: &str, dependencies: &[&str]) {
+        let project_path = root.join(name);
+        let dependencies = dependencies
+            .iter()
+            .map(|dependency| format!("{{ path = \"../{dependency}\" }}"))
+            .join(", ");
+
+        fs::create_dir_all(&project_path).unwrap();
+        fs::write(
+            project_path.join(BUILD_CONFIGURATION_FILE),
+            format!(
+                "[project]\nname = \"{name}\"\nlanguage = \"C\"\ndistribution = \"{distribution}\"\ndependencies = [{dependencies}]\n"
+            ),
+        )
+        .unwrap();
+    }
+
+    fn write_source(root: &Path, name: &str, source: &str) {
+        let project_path = root.join(name);
+        let manifest_path = project_path.join(BUILD_CONFIGURATION_FILE);
+        let manifest = fs::read_to_string(&manifest_path).unwrap();
+
+        fs::write(
+            manifest_path,
+            format!("{manifest}sources = [\"src/*.c\"]\n[c]\nstandard = \"17\"\n"),
+        )
+        .unwrap();
+        fs::create_dir_all(project_path.join("src")).unwrap();
+        fs::write(project_path.join("src").join(format!("{name}.c")), source).unwrap();
+    }
+
+    fn create_context(project: Project) -> TaskContext {
+        TaskContext {
+            project: Arc::new(project),
+            toolchain_configuration: ToolchainConfiguration::default(),
+            run_arguments: vec![],
+            binary: None,
+            run_environment: vec![],
+            clear_environment: false,
+            jobs: None,
+            timings: false,
+            verbose: false,
+            keep_going: false,
+            force: false,
+            emit_deps: None,
+            diagnostics_color: false,
+            message_format: MessageFormat::default(),
+            check: false,
+            graph_format: GraphFormat::default(),
+            prefix: None,
+            package_format: None,
+            source: None,
+            intel_syntax: false,
+            output: None,
+        }
+    }
+
+    #[test]
+    fn test_parse_json_diagnostics_normalizes_gcc_and_clang_output() {
+        let gcc_output = concat!(
+            r#"[{"kind": "error", "message": "'z' undeclared", "children": [{"kind": "note", "message": "each undeclared identifier is reported only once", "locations": []}], "#,
+            r#""locations": [{"caret": {"file": "./src/main.c", "line": 1, "column": 29}}]}]"#
+        );
+        let clang_output = concat!(
+            r#"{"runs": [{"results": [{"level": "warning", "message": {"text": "unused variable 'x'"}, "ruleId": "3942", "#,
+            r#""locations": [{"physicalLocation": {"artifactLocation": {"uri": "file:///project/src/main.c"}, "region": {"startLine": 1, "startColumn": 17}}}]}]}], "version": "2.1.0"}"#
+        );
+
+        let gcc_diagnostics = parse_json_diagnostics(gcc_output);
+
+        assert_eq!(gcc_diagnostics.len(), 2);
+        assert_eq!(gcc_diagnostics[0]["kind"], "error");
+        assert_eq!(gcc_diagnostics[1]["kind"], "note");
+
+        assert_eq!(
+            parse_json_diagnostics(clang_output),
+            vec![serde_json::json!({
+                "kind": "warning",
+                "message": "unused variable 'x'",
+                "locations": [{
+                    "caret": { "file": "/project/src/main.c", "line": 1, "column": 17 },
+                }],
+            })]
+        );
+    }
+
+    #[test]
+    fn test_diamond_dependency_is_built_once() {
+        let root = tempfile::tempdir().unwrap();
+
+        write_project(root.path(), "pd", "staticLibrary", &[]);
+        write_project(root.path(), "pb", "staticLibrary", &["pd"]);
+        write_project(root.path(), "pc", "staticLibrary", &["pd"]);
+        write_project(root.path(), "pa", "executable", &["pb", "pc"]);
+
+        let project = Project::open(root.path().join("pa"), None, None).unwrap();
+        let build = Build::new();
+
+        let levels = build
+            .collect_dependency_levels(&project)
+            .into_iter()
+            .map(|level| {
+                level
+                    .into_iter()
+                    .map(|project| project.name.as_str())
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(levels, vec![vec!["pd"], vec!["pb", "pc"]]);
+
+        let static_libraries = build.collect_libraries(&project);
+
+        assert_eq!(static_libraries.len(), 3);
+        assert_eq!(
+            static_libraries.iter().filter(|name| *name == "pd").count(),
+            1
+        );
+        assert_eq!(static_libraries.last().unwrap(), "pd");
+    }
+
+    #[test]
+    fn test_executable_links_dependent_static_libraries() {
+        let root = tempfile::tempdir().unwrap();
+
+        write_project(root.path(), "pc", "staticLibrary", &[]);
+        write_project(root.path(), "pb", "staticLibrary", &["pc"]);
+        // Listing the dependency first must not put it before its dependent on the link line
+        write_project(root.path(), "pa", "executable", &["pc", "pb"]);
+
+        for (name, source) in [
+            ("pc", "int c(void) { return 2; }\n"),
+            ("pb", "int c(void);\nint b(void) { return c() + 1; }\n"),
+            (
+                "pa",
+                "int b(void);\nint main(void) { return b() == 3 ? 0 : 1; }\n",
+            ),
+        ] {
+            write_source(root.path(), name, source);
+        }
+
+        let project = Project::open(root.path().join("pa"), None, None).unwrap();
+        let mut build = Build::new();
+
+        assert_eq!(build.collect_libraries(&project), vec!["pb", "pc"]);
+
+        let executable = build.get_output_file(&project);
+
+        build.on_execute(&create_context(project)).unwrap();
+
+        assert!(Command::new(executable).status().unwrap().success());
+    }
+
+    #[test]
+    fn test_edited_dependency_is_rebuilt_and_relinked() {
+        let root = tempfile::tempdir().unwrap();
+
+        write_project(root.path(), "pb", "staticLibrary", &[]);
+        write_project(root.path(), "pa", "executable", &["pb"]);
+        write_source(root.path(), "pb", "int b(void) { return 3; }\n");
+        write_source(
+            root.path(),
+            "pa",
+            "int b(void);\nint main(void) { return b(); }\n",
+        );
+
+        let project = Project::open(root.path().join("pa"), None, None).unwrap();
+        let executable = Build::new().get_output_file(&project);
+
+        Build::new().on_execute(&create_context(project)).unwrap();
+
+        assert_eq!(Command::new(&executable).status().unwrap().code(), Some(3));
+
+        let project = Project::open(root.path().join("pa"), None, None).unwrap();
+
+        assert!(Build::new().is_up_to_date(&project));
+
+        fs::write(
+            root.path().join("pb/src/pb.c"),
+            "int b(void) { return 4; }\n",
+        )
+        .unwrap();
+
+        let project = Project::open(root.path().join("pa"), None, None).unwrap();
+
+        assert!(!Build::new().is_up_to_date(&project));
+
+        Build::new().on_execute(&create_context(project)).unwrap();
+
+        assert_eq!(Command::new(&executable).status().unwrap().code(), Some(4));
+    }
+
+    #[test]
+    fn test_executable_runs_with_dynamic_library_dependency_chain() {
+        let root = tempfile::tempdir().unwrap();
+
+        write_project(root.path(), "pc", "dynamicLibrary", &[]);
+        write_project(root.path(), "pb", "dynamicLibrary", &["pc"]);
+        write_project(root.path(), "pa", "executable", &["pb"]);
+        write_source(root.path(), "pc", "int c(void) { return 2; }\n");
+        write_source(
+            root.path(),
+            "pb",
+            "int c(void);\nint b(void) { return c() + 1; }\n",
+        );
+        write_source(
+            root.path(),
+            "pa",
+            "int b(void);\nint main(void) { return b() == 3 ? 0 : 1; }\n",
+        );
+
+        let project = Project::open(root.path().join("pa"), None, None).unwrap();
+        let mut build = Build::new();
+        let executable = build.get_output_file(&project);
+
+        build.on_execute(&create_context(project)).unwrap();
+
+        assert!(Command::new(executable).status().unwrap().success());
+    }
+}
@@ -0,0 +1,332 @@
+use super::Build;
+use crate::{
+    config::{self, BuildConfiguration, Language, ToolchainConfiguration},
+    BuildConfigurationError, Project, ProjectOpenError, BUILD_CONFIGURATION_FILE,
+};
+use glob::glob;
+use std::{fs, path::Path, process::Command};
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Severity {
+    Warning,
+    Error,
+}
+
+/// Checks the toolchain and the project in the current directory, reporting every problem it
+/// finds instead of stopping at the first one.
+pub struct Doctor {
+    warnings: usize,
+    errors: usize,
+}
+
+impl Doctor {
+    pub fn new() -> Self {
+        Self {
+            warnings: 0,
+            errors: 0,
+        }
+    }
+
+    /// Returns whether nothing critical is missing.
+    pub fn diagnose(
+        &mut self,
+        toolchain_configuration: &ToolchainConfiguration,
+        manifest: Option<&Path>,
+    ) -> bool {
+        println!("Toolchain");
+
+        self.check_toolchain(toolchain_configuration);
+
+        println!();
+        println!("Project");
+
+        self.check_project(toolchain_configuration, manifest);
+
+        println!();
+        println!(
+            "Found {} error{} and {} warning{}",
+            self.errors,
+            if self.errors == 1 { "" } else { "s" },
+            self.warnings,
+            if self.warnings == 1 { "" } else { "s" }
+        );
+
+        self.errors == 0
+    }
+
+    fn check_toolchain(&mut self, toolchain_configuration: &ToolchainConfiguration) {
+        let build = Build::new();
+
+        self.check_tool(
+            "C compiler",
+            build.locate_c_compiler(toolchain_configuration),
+            Severity::Error,
+            "install gcc or clang, or point gcc_location in config.toml or CC at it",
+        );
+        self.check_tool(
+            "C++ compiler",
+            build.locate_cpp_compiler(toolchain_configuration),
+            Severity::Error,
+            "install g++ or clang++, or point gpp_location in config.toml or CXX at it",
+        );
+        self.check_tool(
+            "archiver",
+            build.locate_archiver(toolchain_configuration),
+            Severity::Error,
+            "install binutils, or point ar_location in config.toml or AR at an archiver",
+        );
+        self.check_tool(
+            "strip",
+            toolchain_configuration
+                .strip_location
+                .clone()
+                .or_else(|| toolchain_configuration.locate("strip")),
+            Severity::Warning,
+            "install binutils or set strip_location in config.toml, otherwise strip is skipped",
+        );
+
+        // Tools only some commands use are checked when they were explicitly configured
+        if let Some(compiler_launcher) = &toolchain_configuration.compiler_launcher {
+            self.check_tool(
+                "compiler launcher",
+                Some(compiler_launcher.clone()),
+                Severity::Error,
+                "install it or remove compiler_launcher from config.toml",
+            );
+        }
+
+        if let Some(clang_format) = &toolchain_configuration.clang_format_location {
+            self.check_tool(
+                "clang-format",
+                Some(clang_format.clone()),
+                Severity::Warning,
+                "install it or fix clang_format_location in config.toml, bakery fmt needs it",
+            );
+        }
+
+        if let Some(clang_tidy) = &toolchain_configuration.clang_tidy_location {
+            self.check_tool(
+                "clang-tidy",
+                Some(clang_tidy.clone()),
+                Severity::Warning,
+                "install it or fix clang_tidy_location in config.toml, bakery tidy needs it",
+            );
+        }
+
+        if let Some(linker) = &toolchain_configuration.linker {
+            if config::is_valid_linker(linker) {
+                self.report_ok(&format!("linker {}", linker));
+            } else {
+                self.report(
+                    Severity::Error,
+                    &format!("linker {} is not supported", linker),
+                    &format!(
+                        "use one of {} or an absolute path in config.toml",
+                        config::SUPPORTED_LINKERS.join(", ")
+                    ),
+                );
+            }
+        }
+    }
+
+    fn check_tool(&mut self, name: &str, location: Option<String>, severity: Severity, help: &str) {
+        let Some(path) = location.and_then(|location| which::which(location).ok()) else {
+            self.report(severity, &format!("{} not found", name), help);
+
+            return;
+        };
+
+        let version = Command::new(&path)
+            .arg("--version")
+            .output()
+            .ok()
+            .and_then(|output| {
+                String::from_utf8_lossy(&output.stdout)
+                    .lines()
+                    .map(str::trim)
+                    .find(|line| !line.is_empty())
+                    .map(String::from)
+            });
+
+        match version {
+            Some(version) => self.report_ok(&format!("{}: {} ({})", name, path.display(), version)),
+            None => self.report_ok(&format!("{}: {}", name, path.display())),
+        }
+    }
+
+    fn check_project(
+        &mut self,
+        toolchain_configuration: &ToolchainConfiguration,
+        manifest: Option<&Path>,
+    ) {
+        let previous_problems = self.errors + self.warnings;
+
+        let Ok(content) =
+            fs::read_to_string(manifest.unwrap_or(Path::new(BUILD_CONFIGURATION_FILE)))
+        else {
+            match manifest {
+                Some(manifest) => self.report(
+                    Severity::Error,
+                    &format!("manifest {} doesn't exist", manifest.display()),
+                    "check the path passed to --manifest",
+                ),
+                None => self.report(
+                    Severity::Warning,
+                    "there is no bakery.toml in the current directory",
+                    "run bakery doctor from a project's directory to check its configuration",
+                ),
+            }
+
+            return;
+        };
+
+        let project = match manifest {
+            Some(manifest) => Project::open_manifest(manifest, None, None),
+            None => Project::open(".", None, None),
+        };
+        let manifest = manifest.unwrap_or(Path::new(BUILD_CONFIGURATION_FILE));
+        let base_path = manifest
+            .parent()
+            .filter(|parent| !parent.as_os_str().is_empty())
+            .unwrap_or(Path::new("."));
+
+        let build_configuration = match toml::from_str::<BuildConfiguration>(&content) {
+            Ok(build_configuration) => build_configuration,
+            Err(err) => {
+                self.report(
+                    Severity::Error,
+                    &format!("{} doesn't parse: {}", manifest.display(), err.message()),
+                    "fix the syntax error, the message names the offending key",
+                );
+
+                return;
+            }
+        };
+
+        self.report_ok(&format!("{} parses", manifest.display()));
+
+        for include in build_configuration
+            .project
+            .includes
+            .iter()
+            .chain(&build_configuration.project.public_includes)
+            .chain(&build_configuration.project.private_includes)
+        {
+            if !base_path.join(include).is_dir() {
+                self.report(
+                    Severity::Error,
+                    &format!("include directory {} doesn't exist", include),
+                    "create it or remove it from includes",
+                );
+            }
+        }
+
+        for source in build_configuration
+            .project
+            .sources
+            .iter()
+            .chain(&build_configuration.test.sources)
+        {
+            match glob(&base_path.join(source).to_string_lossy()) {
+                Ok(mut paths) => {
+                    if paths.next().is_none() {
+                        self.report(
+                            Severity::Warning,
+                            &format!("source pattern {} matches no files", source),
+                            "check the pattern's directory and extension",
+                        );
+                    }
+                }
+                Err(err) => self.report(
+                    Severity::Error,
+                    &format!("source pattern {} is invalid: {}", source, err.msg),
+                    "fix the wildcard, only *, ** and ? are supported",
+                ),
+            }
+        }
+
+        let project = match project {
+            Ok(project) => project,
+            Err(ProjectOpenError::InvalidBuildConfiguration(
+                BuildConfigurationError::IncorrectWildcard(_)
+                | BuildConfigurationError::IncorrectSource(_)
+                | BuildConfigurationError::IncorrectInclude(_),
+                // Already reported in detail by the checks above
+            )) if self.errors + self.warnings > previous_problems => return,
+            Err(err) => {
+                self.report(
+                    Severity::Error,
+                    &err.to_string(),
+                    "run bakery build for a detailed explanation",
+                );
+
+                return;
+            }
+        };
+
+        self.report_ok(&format!("project {} opens", project.name));
+
+        let build = Build::new();
+        let toolchain_configuration =
+            &toolchain_configuration.with_project_toolchain(&project.toolchain);
+        let languages = project
+            .sources
+            .iter()
+            .chain(&project.tests)
+            .map(|source| build.get_source_language(&project, source))
+            .collect::<Vec<_>>();
+
+        if languages.contains(&Language::C) {
+            if let Some(c_compiler) = build.create_c_compiler(toolchain_configuration) {
+                match c_compiler.check_standard_support(&build.get_c_standard(&project)) {
+                    Ok(_) => self.report_ok(&format!(
+                        "C compiler supports {}",
+                        build.get_c_standard(&project).name()
+                    )),
+                    Err(err) => self.report(
+                        Severity::Error,
+                        &err,
+                        "set an older standard in the [c] table or upgrade the compiler",
+                    ),
+                }
+            }
+        }
+
+        if languages.contains(&Language::Cpp) {
+            if let Some(cpp_compiler) = build.create_cpp_compiler(toolchain_configuration) {
+                match cpp_compiler.check_standard_support(&build.get_cpp_standard(&project)) {
+                    Ok(_) => self.report_ok(&format!(
+                        "C++ compiler supports {}",
+                        build.get_cpp_standard(&project).name()
+                    )),
+                    Err(err) => self.report(
+                        Severity::Error,
+                        &err,
+                        "set an older standard in the [cpp] table or upgrade the compiler",
+                    ),
+                }
+            }
+        }
+    }
+
+    fn report_ok(&self, message: &str) {
+        println!("  ok       {}", message);
+    }
+
+    fn report(&mut self, severity: Severity, message: &str, help: &str) {
+        match severity {
+            Severity::Warning => {
+                self.warnings += 1;
+
+                println!("  warning  {}", message);
+            }
+            Severity::Error => {
+                self.errors += 1;
+
+                println!("  error    {}", message);
+            }
+        }
+
+        println!("           help: {}", help);
+    }
+}
@@ -0,0 +1,112 @@
+use super::{Task, TaskContext};
+use crate::{
+    tools::{is_assembly_source, ClangFormat},
+    PathExtension,
+};
+use eyre::bail;
+use glob::glob;
+use std::path::{Path, PathBuf};
+
+const HEADER_EXTENSIONS: [&str; 4] = ["h", "hh", "hpp", "hxx"];
+const CLANG_FORMAT_CONFIGURATION_FILES: [&str; 2] = [".clang-format", "_clang-format"];
+const DEFAULT_STYLE: &str = "LLVM";
+
+pub struct Fmt {}
+
+impl Fmt {
+    pub fn new() -> Self {
+        Self {}
+    }
+
+    fn collect_headers(&self, base_path: &Path, includes: &[String]) -> Vec<PathBuf> {
+        includes
+            .iter()
+            // Includes inherited from dependencies belong to other projects
+            .filter(|include| {
+                Path::new(include)
+                    .relative_to(base_path)
+                    .is_some_and(|path| !path.starts_with(".."))
+            })
+            .flat_map(|include| {
+                HEADER_EXTENSIONS.iter().flat_map(move |extension| {
+                    glob(
+                        &Path::new(include)
+                            .join(format!("**/*.{extension}"))
+                            .to_string_lossy(),
+                    )
+                    .into_iter()
+                    .flatten()
+                    .flatten()
+                })
+            })
+            .collect::<Vec<_>>()
+    }
+}
+
+impl Task for Fmt {
+    fn id(&self) -> &'static str {
+        "fmt"
+    }
+
+    fn dependencies(&self) -> &[&'static str] {
+        &[]
+    }
+
+    fn on_execute(&mut self, context: &TaskContext) -> eyre::Result<()> {
+        let project = &context.project;
+
+        let clang_format = match context
+            .toolchain_configuration
+            .clang_format_location
+            .clone()
+            .or_else(|| context.toolchain_configuration.locate("clang-format"))
+        {
+            Some(clang_format_location) => ClangFormat::new(clang_format_location),
+            None => bail!(context
+                .toolchain_configuration
+                .tool_not_found("clang-format", "clang_format_location in config.toml")),
+        };
+
+        let mut files = project
+            .sources
+            .iter()
+            .filter(|source| !is_assembly_source(Path::new(source)))
+            .map(|source| project.base_path.join(source))
+            .collect::<Vec<_>>();
+
+        files.extend(self.collect_headers(&project.base_path, &project.includes));
+
+        if files.is_empty() {
+            println!("Nothing to format");
+
+            return Ok(());
+        }
+
+        let has_style_file = CLANG_FORMAT_CONFIGURATION_FILES
+            .iter()
+            .any(|file| project.base_path.join(file).exists());
+        let style = if has_style_file {
+            None
+        } else {
+            Some(DEFAULT_STYLE)
+        };
+
+        match clang_format.format_files(&files, style, context.check) {
+            Ok(_) => {
+                if context.check {
+                    println!("All files are formatted");
+                } else {
+                    println!("Formatted {} files", files.len());
+                }
+            }
+            Err(err) if context.check => {
+                eprintln!("{}", err);
+
+                bail!("Some files are not formatted");
+            }
+            Err(err) => bail!("{}", err),
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,171 @@
+use super::{Task, TaskContext};
+use crate::{project::Project, Dependency};
+use std::collections::HashMap;
+
+#[derive(Clone, Copy, Default)]
+pub enum GraphFormat {
+    #[default]
+    Dot,
+    Mermaid,
+}
+
+enum NodeKind {
+    Project,
+    System,
+    PkgConfig,
+    Prebuilt,
+}
+
+struct Node {
+    label: String,
+    kind: NodeKind,
+}
+
+#[derive(Default)]
+struct DependencyGraph {
+    nodes: Vec<Node>,
+    edges: Vec<(usize, usize)>,
+    project_nodes: HashMap<*const Project, usize>,
+    library_nodes: HashMap<String, usize>,
+}
+
+impl DependencyGraph {
+    /// Adds the project and everything it depends on, shared dependencies become a single node.
+    fn add_project(&mut self, project: &Project) -> usize {
+        if let Some(&node) = self.project_nodes.get(&(project as *const Project)) {
+            return node;
+        }
+
+        let node = self.add_node(project.name.clone(), NodeKind::Project);
+
+        self.project_nodes.insert(project as *const Project, node);
+
+        for dependency in &project.dependencies {
+            let dependency_nodes = match dependency {
+                Dependency::Project(subproject) => vec![self.add_project(subproject)],
+                Dependency::System { name, .. } => vec![self.add_library(name, NodeKind::System)],
+                Dependency::PkgConfig { name, .. } => {
+                    vec![self.add_library(name, NodeKind::PkgConfig)]
+                }
+                Dependency::Prebuilt { libraries, .. } => libraries
+                    .iter()
+                    .map(|library| self.add_library(library, NodeKind::Prebuilt))
+                    .collect(),
+            };
+
+            for dependency_node in dependency_nodes {
+                self.edges.push((node, dependency_node));
+            }
+        }
+
+        node
+    }
+
+    fn add_library(&mut self, name: &str, kind: NodeKind) -> usize {
+        let key = match kind {
+            NodeKind::PkgConfig => format!("pkg-config:{}", name),
+            NodeKind::Prebuilt => format!("prebuilt:{}", name),
+            _ => format!("system:{}", name),
+        };
+
+        if let Some(&node) = self.library_nodes.get(&key) {
+            return node;
+        }
+
+        let node = self.add_node(String::from(name), kind);
+
+        self.library_nodes.insert(key, node);
+
+        node
+    }
+
+    fn add_node(&mut self, label: String, kind: NodeKind) -> usize {
+        self.nodes.push(Node { label, kind });
+
+        self.nodes.len() - 1
+    }
+
+    fn to_dot(&self) -> String {
+        let mut output = String::from("digraph dependencies {\n");
+
+        for (index, node) in self.nodes.iter().enumerate() {
+            let style = match node.kind {
+                NodeKind::Project => "shape=box",
+                NodeKind::System => "shape=ellipse, style=dashed",
+                NodeKind::PkgConfig => "shape=ellipse, style=dotted",
+                NodeKind::Prebuilt => "shape=ellipse",
+            };
+
+            output.push_str(&format!(
+                "    n{} [label=\"{}\", {}];\n",
+                index,
+                node.label.replace('"', "\\\""),
+                style
+            ));
+        }
+
+        for (from, to) in &self.edges {
+            output.push_str(&format!("    n{} -> n{};\n", from, to));
+        }
+
+        output.push('}');
+
+        output
+    }
+
+    fn to_mermaid(&self) -> String {
+        let mut output = String::from("graph TD\n");
+
+        for (index, node) in self.nodes.iter().enumerate() {
+            let label = node.label.replace('"', "#quot;");
+
+            match node.kind {
+                NodeKind::Project => output.push_str(&format!("    n{}[\"{}\"]\n", index, label)),
+                NodeKind::System => output.push_str(&format!("    n{}([\"{}\"])\n", index, label)),
+                NodeKind::PkgConfig => {
+                    output.push_str(&format!("    n{}{{{{\"{}\"}}}}\n", index, label))
+                }
+                NodeKind::Prebuilt => {
+                    output.push_str(&format!("    n{}[(\"{}\")]\n", index, label))
+                }
+            }
+        }
+
+        for (from, to) in &self.edges {
+            output.push_str(&format!("    n{} --> n{}\n", from, to));
+        }
+
+        output
+    }
+}
+
+pub struct Graph {}
+
+impl Graph {
+    pub fn new() -> Self {
+        Self {}
+    }
+}
+
+impl Task for Graph {
+    fn id(&self) -> &'static str {
+        "graph"
+    }
+
+    fn dependencies(&self) -> &[&'static str] {
+        &[]
+    }
+
+    fn on_execute(&mut self, context: &TaskContext) -> eyre::Result<()> {
+        let mut graph = DependencyGraph::default();
+
+        graph.add_project(&context.project);
+
+        match context.graph_format {
+            GraphFormat::Dot => println!("{}", graph.to_dot()),
+            GraphFormat::Mermaid => print!("{}", graph.to_mermaid()),
+        }
+
+        Ok(())
+    }
+}
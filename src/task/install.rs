@@ -0,0 +1,143 @@
+use super::{Build, Task, TaskContext};
+use crate::{config::Distribution, project::Project};
+use eyre::bail;
+use std::{
+    env, fs, io,
+    path::{Component, Path, PathBuf},
+};
+
+const DEFAULT_PREFIX: &str = "/usr/local";
+
+pub struct Install {}
+
+impl Install {
+    pub fn new() -> Self {
+        Self {}
+    }
+
+    /// Staged installs put the whole prefix under `DESTDIR`, e.g. `$DESTDIR/usr/local`.
+    fn get_installation_directory(&self, prefix: &Path) -> PathBuf {
+        match env::var_os("DESTDIR").filter(|destination| !destination.is_empty()) {
+            Some(destination) => PathBuf::from(destination).join(
+                prefix
+                    .components()
+                    .filter(|component| !matches!(component, Component::RootDir))
+                    .collect::<PathBuf>(),
+            ),
+            None => prefix.to_path_buf(),
+        }
+    }
+
+    /// Headers keep their layout relative to the include directory they live in, so
+    /// `include/foo/bar.h` is installed as `<prefix>/include/foo/bar.h`.
+    fn get_header_destination(&self, project: &Project, header: &str) -> PathBuf {
+        let path = project.base_path.join(header);
+
+        project
+            .includes
+            .iter()
+            .find_map(|include| path.strip_prefix(include).ok())
+            .map(Path::to_path_buf)
+            .or_else(|| path.file_name().map(PathBuf::from))
+            .unwrap_or_else(|| PathBuf::from(header))
+    }
+
+    fn install_file(&self, source: &Path, destination: &Path) -> Result<(), io::Error> {
+        if let Some(parent) = destination.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        fs::copy(source, destination)?;
+
+        println!("Installed {}", destination.display());
+
+        Ok(())
+    }
+
+    /// Pairs every file to install with its destination relative to the prefix.
+    pub(crate) fn collect_installation_files(&self, project: &Project) -> Vec<(PathBuf, PathBuf)> {
+        let output_files = Build::new().get_output_files(project);
+        let artifact_directory = match project.distribution {
+            Distribution::Executable => PathBuf::from("bin"),
+            Distribution::DynamicLibrary | Distribution::StaticLibrary => PathBuf::from("lib"),
+        };
+
+        let mut installation_files = Vec::new();
+
+        for output_file in output_files {
+            if let Some(file_name) = output_file.file_name() {
+                let destination = artifact_directory.join(file_name);
+
+                installation_files.push((output_file, destination));
+            }
+        }
+
+        if let Some(soname_file) = Build::new().get_soname_file(project) {
+            if let Some(file_name) = soname_file.file_name() {
+                installation_files.push((soname_file.clone(), artifact_directory.join(file_name)));
+            }
+        }
+
+        if project.distribution != Distribution::Executable {
+            for header in &project.install_headers {
+                installation_files.push((
+                    project.base_path.join(header),
+                    PathBuf::from("include").join(self.get_header_destination(project, header)),
+                ));
+            }
+        }
+
+        installation_files
+    }
+
+    fn install(&self, project: &Project, installation_directory: &Path) -> Result<(), io::Error> {
+        for (source, destination) in self.collect_installation_files(project) {
+            self.install_file(&source, &installation_directory.join(destination))?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Task for Install {
+    fn id(&self) -> &'static str {
+        "install"
+    }
+
+    fn dependencies(&self) -> &[&'static str] {
+        &["build"]
+    }
+
+    fn on_execute(&mut self, context: &TaskContext) -> eyre::Result<()> {
+        let project = &context.project;
+
+        if !Build::new()
+            .get_output_files(project)
+            .iter()
+            .all(|output_file| output_file.exists())
+        {
+            bail!("Nothing to install, the project has not been built");
+        }
+
+        let prefix = context
+            .prefix
+            .clone()
+            .unwrap_or_else(|| PathBuf::from(DEFAULT_PREFIX));
+        let installation_directory = self.get_installation_directory(&prefix);
+
+        match self.install(project, &installation_directory) {
+            Ok(_) => println!(
+                "Installed {} to {}",
+                project.name,
+                installation_directory.display()
+            ),
+            Err(err) if err.kind() == io::ErrorKind::PermissionDenied => bail!(
+                "Permission denied while installing to {}, try a different --prefix or running with elevated privileges",
+                installation_directory.display()
+            ),
+            Err(err) => bail!("Failed to install {}: {}", project.name, err),
+        }
+
+        Ok(())
+    }
+}
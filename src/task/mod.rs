@@ -1,19 +1,69 @@
+mod asm;
 mod build;
+mod doctor;
+mod fmt;
+mod graph;
+mod install;
+mod package;
+mod preprocess;
 mod run;
+mod test;
+mod tidy;
+mod watch;
 
+pub use asm::*;
 pub use build::*;
+pub use doctor::*;
+pub use fmt::*;
+pub use graph::*;
+pub use install::*;
+pub use package::*;
+pub use preprocess::*;
 pub use run::*;
+pub use test::*;
+pub use tidy::*;
+pub use watch::*;
 
 use crate::{config::ToolchainConfiguration, Project};
+use std::path::PathBuf;
+use std::sync::Arc;
 
 pub struct TaskContext {
-    pub project: Project,
+    pub project: Arc<Project>,
     pub toolchain_configuration: ToolchainConfiguration,
+    pub run_arguments: Vec<String>,
+    /// The `[[bin]]` target `run` launches, required when the project declares several.
+    pub binary: Option<String>,
+    /// `--env` variables, which take precedence over the ones from `[run]`.
+    pub run_environment: Vec<(String, String)>,
+    /// Starts the executable with only the given environment variables.
+    pub clear_environment: bool,
+    pub jobs: Option<usize>,
+    pub timings: bool,
+    pub verbose: bool,
+    /// Builds every dependency that doesn't depend on a failed one instead of stopping at the
+    /// first failure.
+    pub keep_going: bool,
+    /// Compiles every source, ignoring the objects of previous builds.
+    pub force: bool,
+    /// Where `build` writes the manifest of its artifacts and their inputs.
+    pub emit_deps: Option<PathBuf>,
+    pub diagnostics_color: bool,
+    pub message_format: MessageFormat,
+    pub check: bool,
+    pub graph_format: GraphFormat,
+    pub prefix: Option<PathBuf>,
+    pub package_format: Option<PackageFormat>,
+    /// The single source a task such as `asm` operates on, as given on the command line.
+    pub source: Option<String>,
+    pub intel_syntax: bool,
+    pub output: Option<PathBuf>,
 }
 
 pub trait Task {
     fn id(&self) -> &'static str;
     fn dependencies(&self) -> &[&'static str];
 
-    fn on_execute(&mut self, context: &TaskContext);
+    /// A failed task stops the tasks depending on it and makes bakery exit with a non-zero code.
+    fn on_execute(&mut self, context: &TaskContext) -> eyre::Result<()>;
 }
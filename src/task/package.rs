@@ -0,0 +1,132 @@
+use super::{Build, Install, Task, TaskContext};
+use crate::project::Project;
+use eyre::bail;
+use flate2::{write::GzEncoder, Compression};
+use std::{
+    fs::{self, File},
+    io,
+    path::{Path, PathBuf},
+};
+use zip::{write::SimpleFileOptions, ZipWriter};
+
+#[derive(Clone, Copy)]
+pub enum PackageFormat {
+    TarGz,
+    Zip,
+}
+
+impl PackageFormat {
+    fn native() -> PackageFormat {
+        if cfg!(target_os = "windows") {
+            PackageFormat::Zip
+        } else {
+            PackageFormat::TarGz
+        }
+    }
+
+    fn extension(&self) -> &'static str {
+        match self {
+            PackageFormat::TarGz => "tar.gz",
+            PackageFormat::Zip => "zip",
+        }
+    }
+}
+
+pub struct Package {}
+
+impl Package {
+    pub fn new() -> Self {
+        Self {}
+    }
+
+    fn get_package_file(&self, project: &Project, format: PackageFormat) -> PathBuf {
+        let package_name = match &project.version {
+            Some(version) => format!("{}-{}", project.name, version),
+            None => project.name.clone(),
+        };
+
+        project
+            .output_directory
+            .join(format!("{}.{}", package_name, format.extension()))
+    }
+
+    fn write_tar_gz(
+        &self,
+        files: &[(PathBuf, PathBuf)],
+        package_file: &Path,
+    ) -> Result<(), io::Error> {
+        let encoder = GzEncoder::new(File::create(package_file)?, Compression::default());
+        let mut builder = tar::Builder::new(encoder);
+
+        for (source, destination) in files {
+            builder.append_path_with_name(source, destination)?;
+        }
+
+        builder.into_inner()?.finish()?;
+
+        Ok(())
+    }
+
+    fn write_zip(
+        &self,
+        files: &[(PathBuf, PathBuf)],
+        package_file: &Path,
+    ) -> Result<(), io::Error> {
+        let mut writer = ZipWriter::new(File::create(package_file)?);
+
+        for (source, destination) in files {
+            // Zip entries always use forward slashes, whatever the host separator is
+            let name = destination
+                .components()
+                .map(|component| component.as_os_str().to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/");
+
+            writer.start_file(name, SimpleFileOptions::default())?;
+
+            io::copy(&mut File::open(source)?, &mut writer)?;
+        }
+
+        writer.finish()?;
+
+        Ok(())
+    }
+}
+
+impl Task for Package {
+    fn id(&self) -> &'static str {
+        "package"
+    }
+
+    fn dependencies(&self) -> &[&'static str] {
+        &["build"]
+    }
+
+    fn on_execute(&mut self, context: &TaskContext) -> eyre::Result<()> {
+        let project = &context.project;
+
+        if !Build::new()
+            .get_output_files(project)
+            .iter()
+            .all(|output_file| output_file.exists())
+        {
+            bail!("Nothing to package, the project has not been built");
+        }
+
+        let format = context.package_format.unwrap_or_else(PackageFormat::native);
+        let package_file = self.get_package_file(project, format);
+        let files = Install::new().collect_installation_files(project);
+
+        let result = fs::create_dir_all(&project.output_directory).and_then(|_| match format {
+            PackageFormat::TarGz => self.write_tar_gz(&files, &package_file),
+            PackageFormat::Zip => self.write_zip(&files, &package_file),
+        });
+
+        match result {
+            Ok(_) => println!("Packaged {} into {}", project.name, package_file.display()),
+            Err(err) => bail!("Failed to package {}: {}", project.name, err),
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,26 @@
+use super::{Build, SourceOutput, Task, TaskContext};
+use eyre::eyre;
+
+pub struct Preprocess {}
+
+impl Preprocess {
+    pub fn new() -> Self {
+        Self {}
+    }
+}
+
+impl Task for Preprocess {
+    fn id(&self) -> &'static str {
+        "preprocess"
+    }
+
+    fn dependencies(&self) -> &[&'static str] {
+        &[]
+    }
+
+    fn on_execute(&mut self, context: &TaskContext) -> eyre::Result<()> {
+        Build::new()
+            .compile_requested_source(context, SourceOutput::Preprocessed)
+            .map_err(|err| eyre!(err))
+    }
+}
@@ -1,14 +1,17 @@
-use super::{Task, TaskContext};
-use crate::{config::Distribution, BAKERY_BUILD_DIRECTORY};
+use super::{Build, Task, TaskContext};
+use crate::config::Distribution;
+use eyre::bail;
+use itertools::Itertools;
 use std::process::Command;
+use thiserror::Error;
 
-const EXECUTABLE_EXTENSION: &str = if cfg!(target_os = "windows") {
-    "exe"
-} else if cfg!(target_os = "linux") {
-    ""
-} else {
-    unreachable!()
-};
+/// The executable ran but failed, bakery exits with its exit code once every task is done.
+#[derive(Error, Debug)]
+#[error("{name} exited with code {code}")]
+pub struct RunFailed {
+    pub name: String,
+    pub code: i32,
+}
 
 pub struct Run {}
 
@@ -27,25 +30,78 @@ impl Task for Run {
         &["build"]
     }
 
-    fn on_execute(&mut self, context: &TaskContext) {
+    fn on_execute(&mut self, context: &TaskContext) -> eyre::Result<()> {
         let project = &context.project;
 
         if project.distribution != Distribution::Executable {
             eprintln!("Skipping run task because the project is not an executable");
+
+            return Ok(());
         }
 
-        let absolute_executable_path = project
-            .base_path
-            .join(BAKERY_BUILD_DIRECTORY)
-            .join(&project.name)
-            .with_extension(EXECUTABLE_EXTENSION);
+        let build = Build::new();
+        let (name, absolute_executable_path) =
+            match (context.binary.as_deref(), project.binaries.as_slice()) {
+                (None, []) => (project.name.as_str(), build.get_output_file(project)),
+                (Some(name), []) if name == project.name => (name, build.get_output_file(project)),
+                (None, [binary]) => (binary.name.as_str(), build.get_binary_file(project, binary)),
+                (name, binaries) => {
+                    let names = binaries
+                        .iter()
+                        .map(|binary| binary.name.as_str())
+                        .join(", ");
+
+                    match name
+                        .map(|name| (name, binaries.iter().find(|binary| binary.name == name)))
+                    {
+                        Some((_, Some(binary))) => {
+                            (binary.name.as_str(), build.get_binary_file(project, binary))
+                        }
+                        Some((name, None)) if binaries.is_empty() => {
+                            bail!("{} has no binary named {}", project.name, name)
+                        }
+                        Some((name, None)) => bail!(
+                            "{} has no binary named {}, available binaries: {}",
+                            project.name,
+                            name,
+                            names
+                        ),
+                        None => bail!(
+                            "Pick one of the binaries of {} to run: {}",
+                            project.name,
+                            names
+                        ),
+                    }
+                }
+            };
 
         let mut command = Command::new(&absolute_executable_path);
 
-        println!("Running {}", project.name);
+        command.args(&context.run_arguments);
+
+        if context.clear_environment {
+            command.env_clear();
+        }
+
+        command.envs(&project.run_environment);
+        command.envs(context.run_environment.iter().cloned());
 
-        if let Err(error) = command.status() {
-            eprintln!("Failed to run the executable: {}", error);
+        println!("Running {}", name);
+
+        match command.status() {
+            Ok(status) => {
+                if !status.success() {
+                    // Killed by a signal when there is no code
+                    return Err(RunFailed {
+                        name: name.to_owned(),
+                        code: status.code().unwrap_or(1),
+                    }
+                    .into());
+                }
+            }
+            Err(error) => bail!("Failed to run the executable: {}", error),
         }
+
+        Ok(())
     }
 }
@@ -0,0 +1,135 @@
+use super::{Build, Task, TaskContext};
+use eyre::bail;
+use rayon::iter::{IntoParallelRefIterator, ParallelIterator};
+use std::{
+    env,
+    ffi::OsString,
+    path::Path,
+    process::{Command, Output},
+};
+
+const LIBRARY_PATH_VARIABLE: &str = if cfg!(target_os = "windows") {
+    "PATH"
+} else if cfg!(target_os = "linux") {
+    "LD_LIBRARY_PATH"
+} else if cfg!(target_os = "macos") {
+    "DYLD_LIBRARY_PATH"
+} else {
+    unreachable!()
+};
+
+pub struct Test {}
+
+impl Test {
+    pub fn new() -> Self {
+        Self {}
+    }
+
+    /// Dynamic libraries are copied into the build directory, so tests must be able to load them
+    /// from there.
+    fn create_library_path(&self, build_directory: &Path) -> OsString {
+        let mut paths = vec![build_directory.to_path_buf()];
+
+        if let Some(library_path) = env::var_os(LIBRARY_PATH_VARIABLE) {
+            paths.extend(env::split_paths(&library_path));
+        }
+
+        env::join_paths(paths).unwrap_or_else(|_| build_directory.as_os_str().to_owned())
+    }
+}
+
+impl Task for Test {
+    fn id(&self) -> &'static str {
+        "test"
+    }
+
+    fn dependencies(&self) -> &[&'static str] {
+        &["build"]
+    }
+
+    fn on_execute(&mut self, context: &TaskContext) -> eyre::Result<()> {
+        let project = &context.project;
+
+        if project.tests.is_empty() {
+            eprintln!("No tests found, add their sources to the [test] section");
+
+            return Ok(());
+        }
+
+        let Some(tests) = Build::new().build_tests(context) else {
+            bail!("Failed to build the tests");
+        };
+
+        let library_path = self.create_library_path(&project.build_directory());
+
+        println!("Running {} tests", tests.len());
+
+        // Output is captured so that tests running in parallel don't interleave
+        let results = tests
+            .par_iter()
+            .map(|(name, executable)| {
+                let output = Command::new(executable)
+                    .args(
+                        project
+                            .test_arguments
+                            .get(name)
+                            .map(Vec::as_slice)
+                            .unwrap_or_default(),
+                    )
+                    .env(LIBRARY_PATH_VARIABLE, &library_path)
+                    .output();
+
+                (name, output)
+            })
+            .collect::<Vec<_>>();
+
+        let mut failed_tests = Vec::new();
+
+        for (name, output) in &results {
+            match output {
+                Ok(output) if output.status.success() => println!("test {} ... ok", name),
+                _ => {
+                    println!("test {} ... FAILED", name);
+
+                    failed_tests.push((name, output));
+                }
+            }
+        }
+
+        for (name, output) in &failed_tests {
+            println!();
+            println!("---- {} ----", name);
+
+            match output {
+                Ok(Output {
+                    status,
+                    stdout,
+                    stderr,
+                }) => {
+                    print!("{}", String::from_utf8_lossy(stdout));
+                    eprint!("{}", String::from_utf8_lossy(stderr));
+                    println!("{}", status);
+                }
+                Err(err) => println!("Failed to run the test: {}", err),
+            }
+        }
+
+        println!();
+        println!(
+            "test result: {}. {} passed; {} failed",
+            if failed_tests.is_empty() {
+                "ok"
+            } else {
+                "FAILED"
+            },
+            results.len() - failed_tests.len(),
+            failed_tests.len()
+        );
+
+        if !failed_tests.is_empty() {
+            bail!("{} of {} tests failed", failed_tests.len(), results.len());
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,119 @@
+use super::{Build, Task, TaskContext};
+use crate::{
+    config::Language,
+    project::Project,
+    tools::{is_assembly_source, ClangTidy},
+};
+use eyre::bail;
+use rayon::prelude::*;
+use std::path::Path;
+
+pub struct Tidy {}
+
+impl Tidy {
+    pub fn new() -> Self {
+        Self {}
+    }
+
+    /// Mirrors the language, standard, includes and defines the build passes to the compiler.
+    fn collect_compiler_arguments(
+        &self,
+        build: &Build,
+        project: &Project,
+        source: &str,
+    ) -> Vec<String> {
+        let mut arguments = match build.get_source_language(project, source) {
+            Language::C => vec![
+                String::from("-xc"),
+                format!(
+                    "-std={}",
+                    build
+                        .get_c_standard(project)
+                        .dialect_name(build.get_c_gnu_extensions(project))
+                ),
+            ],
+            Language::Cpp => vec![
+                String::from("-xc++"),
+                format!(
+                    "-std={}",
+                    build
+                        .get_cpp_standard(project)
+                        .dialect_name(build.get_cpp_gnu_extensions(project))
+                ),
+            ],
+        };
+
+        arguments.extend(build.collect_pkg_config_compile_flags(project));
+
+        for include in build.collect_includes(project) {
+            arguments.push(format!("-I{}", include));
+        }
+
+        for (name, value) in build.get_defines(project) {
+            if value.is_empty() {
+                arguments.push(format!("-D{}", name));
+            } else {
+                arguments.push(format!("-D{}={}", name, value));
+            }
+        }
+
+        arguments
+    }
+}
+
+impl Task for Tidy {
+    fn id(&self) -> &'static str {
+        "tidy"
+    }
+
+    fn dependencies(&self) -> &[&'static str] {
+        &[]
+    }
+
+    fn on_execute(&mut self, context: &TaskContext) -> eyre::Result<()> {
+        let project = &context.project;
+
+        let clang_tidy = match context
+            .toolchain_configuration
+            .clang_tidy_location
+            .clone()
+            .or_else(|| context.toolchain_configuration.locate("clang-tidy"))
+        {
+            Some(clang_tidy_location) => ClangTidy::new(clang_tidy_location),
+            None => bail!(context
+                .toolchain_configuration
+                .tool_not_found("clang-tidy", "clang_tidy_location in config.toml")),
+        };
+
+        let build = Build::new();
+
+        let findings = project
+            .sources
+            .par_iter()
+            .filter(|source| !is_assembly_source(Path::new(source)))
+            .filter_map(|source| {
+                let compiler_arguments = self.collect_compiler_arguments(&build, project, source);
+
+                match clang_tidy
+                    .analyze_source_file(&project.base_path.join(source), &compiler_arguments)
+                {
+                    Ok(diagnostics) if diagnostics.trim().is_empty() => None,
+                    Ok(diagnostics) | Err(diagnostics) => Some((source, diagnostics)),
+                }
+            })
+            .collect::<Vec<_>>();
+
+        if findings.is_empty() {
+            println!("No findings");
+
+            return Ok(());
+        }
+
+        for (source, diagnostics) in &findings {
+            eprintln!("{}:", source);
+            eprintln!("{}", diagnostics.trim_end());
+        }
+
+        bail!("Found issues in {} source files", findings.len());
+    }
+}
@@ -0,0 +1,165 @@
+use super::{Task, TaskContext};
+use crate::{project::Project, Dependency};
+use notify_debouncer_mini::{new_debouncer, notify::RecursiveMode};
+use std::{
+    collections::HashSet,
+    fs,
+    path::{Path, PathBuf},
+    sync::mpsc,
+    time::Duration,
+};
+
+const DEBOUNCE_TIMEOUT: Duration = Duration::from_millis(200);
+
+#[derive(Default)]
+struct WatchedPaths {
+    directories: HashSet<PathBuf>,
+    files: HashSet<PathBuf>,
+    include_directories: HashSet<PathBuf>,
+}
+
+impl WatchedPaths {
+    fn is_relevant(&self, path: &Path) -> bool {
+        !is_temporary_file(path)
+            && (self.files.contains(path)
+                || self
+                    .include_directories
+                    .iter()
+                    .any(|include_directory| path.starts_with(include_directory)))
+    }
+}
+
+/// Editors write swap and backup files next to the edited ones, e.g. `.main.c.swp`, `main.c~`,
+/// `.#main.c` or vim's `4913` probe.
+fn is_temporary_file(path: &Path) -> bool {
+    let Some(name) = path.file_name().map(|name| name.to_string_lossy()) else {
+        return false;
+    };
+
+    name.ends_with('~')
+        || name.ends_with(".swp")
+        || name.ends_with(".swx")
+        || name.ends_with(".tmp")
+        || name.starts_with(".#")
+        || name == "4913"
+}
+
+pub struct Watch {}
+
+impl Watch {
+    pub fn new() -> Self {
+        Self {}
+    }
+
+    fn collect_watched_paths(&self, project: &Project, watched_paths: &mut WatchedPaths) {
+        let Ok(base_path) = fs::canonicalize(&project.base_path) else {
+            return;
+        };
+
+        if !watched_paths.directories.insert(base_path.clone()) {
+            return;
+        }
+
+        watched_paths
+            .files
+            .extend(fs::canonicalize(&project.manifest));
+        watched_paths.files.extend(
+            project
+                .sources
+                .iter()
+                .filter_map(|source| fs::canonicalize(project.base_path.join(source)).ok()),
+        );
+
+        for include in &project.includes {
+            if let Ok(include_directory) = fs::canonicalize(include) {
+                if !include_directory.starts_with(&base_path) {
+                    watched_paths.directories.insert(include_directory.clone());
+                }
+
+                watched_paths.include_directories.insert(include_directory);
+            }
+        }
+
+        for dependency in &project.dependencies {
+            if let Dependency::Project(subproject) = dependency {
+                self.collect_watched_paths(subproject, watched_paths);
+            }
+        }
+    }
+
+    /// Blocks until one of the watched files changes.
+    fn wait_for_changes(&self, watched_paths: &WatchedPaths) {
+        let (sender, receiver) = mpsc::channel();
+
+        let mut debouncer = match new_debouncer(DEBOUNCE_TIMEOUT, sender) {
+            Ok(debouncer) => debouncer,
+            Err(err) => {
+                eprintln!("Failed to create a file watcher: {}", err);
+
+                return;
+            }
+        };
+
+        for directory in &watched_paths.directories {
+            if let Err(err) = debouncer
+                .watcher()
+                .watch(directory, RecursiveMode::Recursive)
+            {
+                eprintln!("Failed to watch {}: {}", directory.display(), err);
+            }
+        }
+
+        println!("Watching for changes");
+
+        for result in receiver {
+            match result {
+                Ok(events) => {
+                    if let Some(event) = events
+                        .iter()
+                        .find(|event| watched_paths.is_relevant(&event.path))
+                    {
+                        println!("Detected a change in {}", event.path.display());
+
+                        return;
+                    }
+                }
+                Err(err) => eprintln!("File watcher failed: {}", err),
+            }
+        }
+    }
+
+    /// Used when the project can't be opened, so only its build configuration can be watched.
+    pub(crate) fn wait_for_build_configuration_change(&self, manifest: &Path) {
+        let mut watched_paths = WatchedPaths::default();
+
+        if let (Some(manifest_name), Ok(base_path)) = (
+            manifest.file_name(),
+            fs::canonicalize(manifest.parent().unwrap_or(Path::new("."))),
+        ) {
+            watched_paths.files.insert(base_path.join(manifest_name));
+            watched_paths.directories.insert(base_path);
+        }
+
+        self.wait_for_changes(&watched_paths);
+    }
+}
+
+impl Task for Watch {
+    fn id(&self) -> &'static str {
+        "watch"
+    }
+
+    fn dependencies(&self) -> &[&'static str] {
+        &[]
+    }
+
+    fn on_execute(&mut self, context: &TaskContext) -> eyre::Result<()> {
+        let mut watched_paths = WatchedPaths::default();
+
+        self.collect_watched_paths(&context.project, &mut watched_paths);
+
+        self.wait_for_changes(&watched_paths);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,43 @@
+use std::{path::PathBuf, process::Command};
+
+pub(crate) struct ClangFormat {
+    location: String,
+}
+
+impl ClangFormat {
+    pub(crate) fn new(location: String) -> ClangFormat {
+        ClangFormat { location }
+    }
+
+    pub(crate) fn format_files(
+        &self,
+        files: &[PathBuf],
+        style: Option<&str>,
+        check: bool,
+    ) -> Result<(), String> {
+        let mut command = Command::new(&self.location);
+
+        if check {
+            command.arg("--dry-run");
+            command.arg("--Werror");
+        } else {
+            command.arg("-i");
+        }
+
+        if let Some(style) = style {
+            command.arg(format!("--style={}", style));
+        }
+
+        for file in files {
+            command.arg(file);
+        }
+
+        let output = command.output().map_err(|err| err.to_string())?;
+
+        if output.status.success() {
+            Ok(())
+        } else {
+            Err(String::from_utf8_lossy(&output.stderr).into_owned())
+        }
+    }
+}
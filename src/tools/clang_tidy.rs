@@ -0,0 +1,37 @@
+use std::{path::Path, process::Command};
+
+pub(crate) struct ClangTidy {
+    location: String,
+}
+
+impl ClangTidy {
+    pub(crate) fn new(location: String) -> ClangTidy {
+        ClangTidy { location }
+    }
+
+    /// Returns the diagnostics reported for the source file, empty if there are none.
+    pub(crate) fn analyze_source_file(
+        &self,
+        source_file: &Path,
+        compiler_arguments: &[String],
+    ) -> Result<String, String> {
+        let mut command = Command::new(&self.location);
+
+        command.arg("--quiet");
+        command.arg(source_file);
+        command.arg("--");
+
+        for compiler_argument in compiler_arguments {
+            command.arg(compiler_argument);
+        }
+
+        let output = command.output().map_err(|err| err.to_string())?;
+        let diagnostics = String::from_utf8_lossy(&output.stdout).into_owned();
+
+        if output.status.success() {
+            Ok(diagnostics)
+        } else {
+            Err(diagnostics + &String::from_utf8_lossy(&output.stderr))
+        }
+    }
+}
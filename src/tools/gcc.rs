@@ -1,17 +1,296 @@
-use super::{Archiver, CCompiler, CppCompiler};
-use crate::config::{CStandard, CppStandard, Distribution, OptimizationLevel};
+use super::{is_assembly_source, is_objective_source, Archiver, CCompiler, CppCompiler, Flavor};
+use crate::config::{CStandard, CppStandard, DebugInfoLevel, Distribution, Lto, OptimizationLevel};
 use std::{
+    env,
+    fmt::{self, Display},
+    fs, io,
     path::{Path, PathBuf},
-    process::Command,
+    process::{self, Command, Output},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        OnceLock,
+    },
 };
 
+/// Windows limits a command line to 32767 characters (8191 through cmd), so long argument lists
+/// are passed through a response file instead.
+const RESPONSE_FILE_THRESHOLD: usize = 8000;
+
+/// Static libraries are archived this many objects at a time, so that neither the command line
+/// nor the response file grows with the size of the library.
+const ARCHIVE_BATCH_SIZE: usize = 512;
+
+static RESPONSE_FILE_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+/// Runs the command, moving its arguments into an `@file` when they would be too long.
+fn output_with_response_file(command: &mut Command) -> Result<Output, io::Error> {
+    let arguments_length = command
+        .get_args()
+        .map(|argument| argument.len() + 1)
+        .sum::<usize>();
+
+    if arguments_length <= RESPONSE_FILE_THRESHOLD {
+        return command.output();
+    }
+
+    let response_file = env::temp_dir().join(format!(
+        "bakery-{}-{}.rsp",
+        process::id(),
+        RESPONSE_FILE_COUNTER.fetch_add(1, Ordering::Relaxed)
+    ));
+
+    let response_file_content = command
+        .get_args()
+        .map(|argument| {
+            format!(
+                "\"{}\"",
+                argument
+                    .to_string_lossy()
+                    .replace('\\', "\\\\")
+                    .replace('"', "\\\"")
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    fs::write(&response_file, response_file_content)?;
+
+    let output = Command::new(command.get_program())
+        .arg(format!("@{}", response_file.display()))
+        .output();
+
+    let _ = fs::remove_file(&response_file);
+
+    output
+}
+
+/// What a compilation produces, which only changes the arguments controlling its output.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum CompilationOutput {
+    Object,
+    Assembly { intel_syntax: bool },
+    Preprocessed,
+}
+
+impl CompilationOutput {
+    fn add_arguments(&self, command: &mut Command) {
+        match self {
+            CompilationOutput::Object => {
+                command.arg("-c");
+
+                // Lets the next build tell which header made an object stale
+                command.arg("-MMD");
+            }
+            CompilationOutput::Assembly { intel_syntax } => {
+                command.arg("-S");
+
+                if *intel_syntax {
+                    command.arg("-masm=intel");
+                }
+            }
+            CompilationOutput::Preprocessed => {
+                command.arg("-E");
+            }
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+struct CompilerVersion {
+    major: u32,
+    minor: u32,
+}
+
+impl CompilerVersion {
+    const fn new(major: u32, minor: u32) -> CompilerVersion {
+        CompilerVersion { major, minor }
+    }
+
+    /// Parses the leading `major.minor` of a token such as `13.2.0` or `13.2.0-23ubuntu4)`.
+    fn parse(token: &str) -> Option<CompilerVersion> {
+        let mut parts = token.split('.');
+        let major = parts.next()?.parse().ok()?;
+        let minor = parts
+            .next()?
+            .chars()
+            .take_while(|character| character.is_ascii_digit())
+            .collect::<String>()
+            .parse()
+            .ok()?;
+
+        Some(CompilerVersion { major, minor })
+    }
+}
+
+impl Display for CompilerVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}", self.major, self.minor)
+    }
+}
+
+#[derive(Clone, Copy)]
+struct DetectedVersion {
+    is_clang: bool,
+    version: CompilerVersion,
+}
+
+impl DetectedVersion {
+    fn compiler_name(&self) -> &'static str {
+        if self.is_clang {
+            "clang"
+        } else {
+            "gcc"
+        }
+    }
+}
+
+/// Only compilation goes through the launcher (e.g. ccache), linking always runs the compiler itself.
+fn create_compile_command(location: &str, launcher: Option<&str>) -> Command {
+    match launcher {
+        Some(launcher) => {
+            let mut command = Command::new(launcher);
+
+            command.arg(location);
+
+            command
+        }
+        None => Command::new(location),
+    }
+}
+
+/// GCC has no thin LTO, so both modes map to its regular `-flto` there.
+fn lto_argument(location: &str, lto: Lto) -> Option<&'static str> {
+    match (lto, Flavor::detect(location)) {
+        (Lto::Off, _) => None,
+        (Lto::Thin, Flavor::Clang) => Some("-flto=thin"),
+        (Lto::Thin | Lto::Full, _) => Some("-flto"),
+    }
+}
+
+/// Clang has no JSON format, its SARIF output is used instead and normalized by the build.
+fn diagnostics_format_arguments(location: &str) -> &'static [&'static str] {
+    match Flavor::detect(location) {
+        Flavor::Gcc => &["-fdiagnostics-format=json"],
+        Flavor::Clang => &["-fdiagnostics-format=sarif", "-Wno-sarif-format-unstable"],
+    }
+}
+
+/// `-Bstatic` stays in effect until `-Bdynamic`, so it has to be reset right after the library
+/// or every library following it would be linked statically too.
+fn library_arguments(library: &str, static_libraries: &[String]) -> Vec<String> {
+    if cfg!(target_os = "linux") && static_libraries.iter().any(|name| name == library) {
+        vec![
+            String::from("-Wl,-Bstatic"),
+            format!("-l{}", library),
+            String::from("-Wl,-Bdynamic"),
+        ]
+    } else {
+        vec![format!("-l{}", library)]
+    }
+}
+
+/// The full `--version` output, which also changes when a compiler is upgraded in place.
+fn compiler_identity(location: &str) -> String {
+    let version = Command::new(location)
+        .arg("--version")
+        .output()
+        .map(|output| String::from_utf8_lossy(&output.stdout).into_owned())
+        .unwrap_or_default();
+
+    format!("{}\n{}", location, version)
+}
+
+/// Runs `<compiler> --version` and extracts the version from the first line of its output.
+fn detect_version(location: &str) -> Option<DetectedVersion> {
+    let output = Command::new(location).arg("--version").output().ok()?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let first_line = stdout.lines().next()?;
+
+    let is_clang = Flavor::detect(location) == Flavor::Clang;
+    let version = first_line
+        .split_whitespace()
+        .find_map(CompilerVersion::parse)?;
+
+    Some(DetectedVersion { is_clang, version })
+}
+
+fn minimum_c_standard_version(standard: &CStandard, is_clang: bool) -> Option<CompilerVersion> {
+    match (standard, is_clang) {
+        (CStandard::EightyNine | CStandard::NinetyNine, _) => None,
+        (CStandard::Eleven, false) => Some(CompilerVersion::new(4, 7)),
+        (CStandard::Eleven, true) => Some(CompilerVersion::new(3, 1)),
+        (CStandard::Seventeen, false) => Some(CompilerVersion::new(8, 0)),
+        (CStandard::Seventeen, true) => Some(CompilerVersion::new(6, 0)),
+        (CStandard::Twenty, _) => None,
+        (CStandard::TwentyThree, false) => Some(CompilerVersion::new(14, 0)),
+        (CStandard::TwentyThree, true) => Some(CompilerVersion::new(18, 0)),
+    }
+}
+
+fn minimum_cpp_standard_version(standard: &CppStandard, is_clang: bool) -> Option<CompilerVersion> {
+    match (standard, is_clang) {
+        (CppStandard::NinetyEight | CppStandard::Three, _) => None,
+        (CppStandard::Eleven, false) => Some(CompilerVersion::new(4, 7)),
+        (CppStandard::Eleven, true) => Some(CompilerVersion::new(3, 3)),
+        (CppStandard::Fourteen, false) => Some(CompilerVersion::new(5, 0)),
+        (CppStandard::Fourteen, true) => Some(CompilerVersion::new(3, 5)),
+        (CppStandard::Seventeen, false) => Some(CompilerVersion::new(7, 0)),
+        (CppStandard::Seventeen, true) => Some(CompilerVersion::new(5, 0)),
+        (CppStandard::Twenty, false) => Some(CompilerVersion::new(10, 0)),
+        (CppStandard::Twenty, true) => Some(CompilerVersion::new(10, 0)),
+        (CppStandard::TwentyThree, false) => Some(CompilerVersion::new(11, 0)),
+        (CppStandard::TwentyThree, true) => Some(CompilerVersion::new(17, 0)),
+        (CppStandard::TwentySix, false) => Some(CompilerVersion::new(14, 0)),
+        (CppStandard::TwentySix, true) => Some(CompilerVersion::new(17, 0)),
+    }
+}
+
+fn minimum_optimization_version(
+    optimization: &OptimizationLevel,
+    is_clang: bool,
+) -> Option<CompilerVersion> {
+    match (optimization, is_clang) {
+        (OptimizationLevel::SizeAggressive, false) => Some(CompilerVersion::new(12, 1)),
+        (OptimizationLevel::Debug, false) => Some(CompilerVersion::new(4, 8)),
+        _ => None,
+    }
+}
+
+fn check_minimum_version(
+    location: &str,
+    detected_version: Option<DetectedVersion>,
+    flag: &str,
+    minimum_version: impl FnOnce(bool) -> Option<CompilerVersion>,
+) -> Result<(), String> {
+    // An unrecognized compiler is given the benefit of the doubt
+    let Some(detected_version) = detected_version else {
+        return Ok(());
+    };
+
+    match minimum_version(detected_version.is_clang) {
+        Some(minimum_version) if detected_version.version < minimum_version => Err(format!(
+            "{} requires {} {} or newer, but {} is version {}",
+            flag,
+            detected_version.compiler_name(),
+            minimum_version,
+            location,
+            detected_version.version
+        )),
+        _ => Ok(()),
+    }
+}
+
 pub(crate) struct GccFlavorArchiver {
     location: String,
+    lto_location: Option<String>,
 }
 
 impl GccFlavorArchiver {
-    pub(crate) fn new(location: String) -> GccFlavorArchiver {
-        GccFlavorArchiver { location }
+    pub(crate) fn new(location: String, lto_location: Option<String>) -> GccFlavorArchiver {
+        GccFlavorArchiver {
+            location,
+            lto_location,
+        }
     }
 }
 
@@ -20,81 +299,131 @@ impl Archiver for GccFlavorArchiver {
         &self,
         object_files: &[PathBuf],
         output_file: &Path,
+        lto: Lto,
     ) -> Result<(), String> {
-        let mut command = Command::new(&self.location);
+        let location = match lto {
+            Lto::Off => &self.location,
+            Lto::Thin | Lto::Full => self.lto_location.as_ref().unwrap_or(&self.location),
+        };
 
-        command.arg("rcs");
-        command.arg(output_file);
-
-        for object_file in object_files {
-            command.arg(object_file);
+        // The archive is created anew, replacing members in place would keep the objects of
+        // sources removed since the previous build
+        match fs::remove_file(output_file) {
+            Err(err) if err.kind() != io::ErrorKind::NotFound => return Err(err.to_string()),
+            _ => {}
         }
 
-        let output = command.output().unwrap();
+        // Appending doesn't look for members to replace, which keeps each batch as fast as the
+        // first one, and the symbol index is only written along with the last batch
+        let mut batches = object_files.chunks(ARCHIVE_BATCH_SIZE).peekable();
 
-        if output.status.success() {
-            Ok(())
-        } else {
-            Err(String::from_utf8_lossy(&output.stderr).into_owned())
+        loop {
+            let batch = batches.next().unwrap_or_default();
+            let is_last_batch = batches.peek().is_none();
+            let mut command = Command::new(location);
+
+            command.arg(if is_last_batch { "qcs" } else { "qc" });
+            command.arg(output_file);
+            command.args(batch);
+
+            let output = output_with_response_file(&mut command).map_err(|err| err.to_string())?;
+
+            if !output.status.success() {
+                return Err(String::from_utf8_lossy(&output.stderr).into_owned());
+            }
+
+            if is_last_batch {
+                return Ok(());
+            }
         }
     }
 }
 
 pub(crate) struct GccFlavorCCompiler {
     location: String,
+    launcher: Option<String>,
+    version: OnceLock<Option<DetectedVersion>>,
 }
 
 impl GccFlavorCCompiler {
-    pub(crate) fn new(location: String) -> GccFlavorCCompiler {
-        GccFlavorCCompiler { location }
+    pub(crate) fn new(location: String, launcher: Option<String>) -> GccFlavorCCompiler {
+        GccFlavorCCompiler {
+            location,
+            launcher,
+            version: OnceLock::new(),
+        }
     }
-}
 
-impl CCompiler for GccFlavorCCompiler {
-    fn compile_source_file(
+    fn create_compile_command(&self) -> Command {
+        create_compile_command(&self.location, self.launcher.as_deref())
+    }
+
+    fn source_language(source_file: &Path) -> &'static str {
+        match source_file
+            .extension()
+            .and_then(|extension| extension.to_str())
+        {
+            Some("S") => "-xassembler-with-cpp",
+            Some("s") => "-xassembler",
+            _ if is_objective_source(source_file) => "-xobjective-c",
+            _ => "-xc",
+        }
+    }
+
+    /// Shared by sources, precompiled headers, assembly listings and preprocessed sources, which
+    /// only differ in the `-x` language and what is written to the output file.
+    fn compile(
         &self,
-        source_file: &Path,
+        input_file: &Path,
         output_file: &Path,
+        language: &str,
+        output: CompilationOutput,
         settings: &super::CCompilationSettings<'_>,
-    ) -> Result<(), String> {
-        let mut command = Command::new(&self.location);
+    ) -> Result<String, String> {
+        let mut command = self.create_compile_command();
 
         for additional_pre_argument in settings.additional_pre_arguments {
             command.arg(additional_pre_argument);
         }
 
-        command.arg("-c");
+        output.add_arguments(&mut command);
 
         if settings.distribution == Distribution::DynamicLibrary {
             command.arg("-fPIC");
         }
 
-        command.arg("-xc");
+        command.arg(language);
 
         command.arg(format!(
             "-std={}",
-            match settings.standard {
-                CStandard::EightyNine => "c89",
-                CStandard::NinetyNine => "c99",
-                CStandard::Eleven => "c11",
-                CStandard::Seventeen => "c17",
-                CStandard::Twenty => "c20",
-                CStandard::TwentyThree => "c23",
-            }
+            settings.standard.dialect_name(settings.gnu_extensions)
         ));
 
-        command.arg(format!(
-            "-O{}",
-            match settings.optimization {
-                OptimizationLevel::Zero => "0",
-                OptimizationLevel::One => "1",
-                OptimizationLevel::Two => "2",
-                OptimizationLevel::Three => "3",
-                OptimizationLevel::Four => "fast",
-                OptimizationLevel::Size => "s",
-                OptimizationLevel::Debug => "g",
-            }
-        ));
+        command.arg(format!("-O{}", settings.optimization.name()));
+
+        if let Some(debug_info) = &settings.debug_info {
+            command.arg(format!(
+                "-g{}",
+                match debug_info {
+                    DebugInfoLevel::Zero => "0",
+                    DebugInfoLevel::One => "1",
+                    DebugInfoLevel::Two => "2",
+                    DebugInfoLevel::Three => "3",
+                }
+            ));
+        }
+
+        if let Some(target_cpu) = settings.target_cpu {
+            command.arg(format!("-march={}", target_cpu));
+        }
+
+        if let Some(tune_cpu) = settings.tune_cpu {
+            command.arg(format!("-mtune={}", tune_cpu));
+        }
+
+        if let Some(lto_argument) = lto_argument(&self.location, settings.lto) {
+            command.arg(lto_argument);
+        }
 
         if settings.enable_all_warnings {
             command.arg("-Wall");
@@ -105,7 +434,37 @@ impl CCompiler for GccFlavorCCompiler {
             command.arg("-Werror");
         }
 
-        command.arg(source_file);
+        if settings.json_diagnostics {
+            command.args(diagnostics_format_arguments(&self.location));
+        } else if settings.diagnostics_color {
+            command.arg("-fdiagnostics-color=always");
+        }
+
+        for warning in &settings.warnings.enable {
+            command.arg(format!("-W{}", warning));
+        }
+
+        for warning in &settings.warnings.disable {
+            command.arg(format!("-Wno-{}", warning));
+        }
+
+        for warning in &settings.warnings.errors {
+            command.arg(format!("-Werror={}", warning));
+        }
+
+        for sanitizer in settings.sanitizers {
+            command.arg(format!("-fsanitize={}", sanitizer.name()));
+        }
+
+        if settings.coverage {
+            command.arg("--coverage");
+        }
+
+        if settings.threads {
+            command.arg("-pthread");
+        }
+
+        command.arg(input_file);
 
         command.arg(format!("-o{}", output_file.display()));
 
@@ -113,18 +472,145 @@ impl CCompiler for GccFlavorCCompiler {
             command.arg(format!("-I{}", include));
         }
 
+        // The header is C, which the assembler can't read
+        if let Some(precompiled_header) = settings
+            .precompiled_header
+            .filter(|_| !is_assembly_source(input_file))
+        {
+            command.arg("-include");
+            command.arg(precompiled_header);
+        }
+
+        for (name, value) in settings.defines {
+            if value.is_empty() {
+                command.arg(format!("-D{}", name));
+            } else {
+                command.arg(format!("-D{}={}", name, value));
+            }
+        }
+
         for additional_post_argument in settings.additional_post_arguments {
             command.arg(additional_post_argument);
         }
 
-        let output = command.output().unwrap();
+        // Either the compiler or its launcher may be missing
+        let output = command.output().map_err(|err| {
+            format!(
+                "Failed to run {}: {}",
+                command.get_program().to_string_lossy(),
+                err
+            )
+        })?;
+        let diagnostics = String::from_utf8_lossy(&output.stdout).into_owned()
+            + &String::from_utf8_lossy(&output.stderr);
 
         if output.status.success() {
-            Ok(())
+            Ok(diagnostics)
         } else {
-            Err(String::from_utf8_lossy(&output.stderr).into_owned())
+            Err(diagnostics)
         }
     }
+}
+
+impl CCompiler for GccFlavorCCompiler {
+    fn check_standard_support(&self, standard: &CStandard) -> Result<(), String> {
+        let detected_version = *self.version.get_or_init(|| detect_version(&self.location));
+
+        check_minimum_version(
+            &self.location,
+            detected_version,
+            &format!("-std={}", standard.name()),
+            |is_clang| minimum_c_standard_version(standard, is_clang),
+        )
+    }
+
+    fn check_optimization_support(&self, optimization: &OptimizationLevel) -> Result<(), String> {
+        let detected_version = *self.version.get_or_init(|| detect_version(&self.location));
+
+        check_minimum_version(
+            &self.location,
+            detected_version,
+            &format!("-O{}", optimization.name()),
+            |is_clang| minimum_optimization_version(optimization, is_clang),
+        )
+    }
+
+    fn compile_source_file(
+        &self,
+        source_file: &Path,
+        output_file: &Path,
+        settings: &super::CCompilationSettings<'_>,
+    ) -> Result<String, String> {
+        self.compile(
+            source_file,
+            output_file,
+            Self::source_language(source_file),
+            CompilationOutput::Object,
+            settings,
+        )
+    }
+
+    fn generate_assembly(
+        &self,
+        source_file: &Path,
+        output_file: &Path,
+        intel_syntax: bool,
+        settings: &super::CCompilationSettings<'_>,
+    ) -> Result<String, String> {
+        self.compile(
+            source_file,
+            output_file,
+            Self::source_language(source_file),
+            CompilationOutput::Assembly { intel_syntax },
+            settings,
+        )
+    }
+
+    fn preprocess(
+        &self,
+        source_file: &Path,
+        output_file: &Path,
+        settings: &super::CCompilationSettings<'_>,
+    ) -> Result<String, String> {
+        self.compile(
+            source_file,
+            output_file,
+            Self::source_language(source_file),
+            CompilationOutput::Preprocessed,
+            settings,
+        )
+    }
+
+    fn precompiled_header_extension(&self) -> &'static str {
+        match Flavor::detect(&self.location) {
+            Flavor::Gcc => "gch",
+            Flavor::Clang => "pch",
+        }
+    }
+
+    fn identity(&self) -> String {
+        compiler_identity(&self.location)
+    }
+
+    fn precompile_header(
+        &self,
+        header_file: &Path,
+        output_file: &Path,
+        settings: &super::CCompilationSettings<'_>,
+    ) -> Result<String, String> {
+        let settings = super::CCompilationSettings {
+            precompiled_header: None,
+            ..settings.clone()
+        };
+
+        self.compile(
+            header_file,
+            output_file,
+            "-xc-header",
+            CompilationOutput::Object,
+            &settings,
+        )
+    }
 
     fn link_object_files(
         &self,
@@ -135,7 +621,11 @@ impl CCompiler for GccFlavorCCompiler {
         let mut command = Command::new(&self.location);
 
         if settings.distribution == Distribution::DynamicLibrary {
-            command.arg("-shared");
+            if cfg!(target_os = "macos") {
+                command.arg("-dynamiclib");
+            } else {
+                command.arg("-shared");
+            }
         }
 
         for object_file in object_files {
@@ -144,6 +634,22 @@ impl CCompiler for GccFlavorCCompiler {
 
         command.arg(format!("-o{}", output_file.display()));
 
+        for sanitizer in settings.sanitizers {
+            command.arg(format!("-fsanitize={}", sanitizer.name()));
+        }
+
+        if settings.coverage {
+            command.arg("--coverage");
+        }
+
+        if settings.threads {
+            command.arg("-pthread");
+        }
+
+        if let Some(lto_argument) = lto_argument(&self.location, settings.lto) {
+            command.arg(lto_argument);
+        }
+
         for include in settings.includes {
             command.arg(format!("-I{}", include));
         }
@@ -153,10 +659,58 @@ impl CCompiler for GccFlavorCCompiler {
         }
 
         for library in settings.libraries {
-            command.arg(format!("-l{}", library));
+            command.args(library_arguments(library, settings.static_libraries));
+        }
+
+        if settings.math {
+            command.arg("-lm");
+        }
+
+        if settings.static_runtime {
+            command.args(["-static-libgcc", "-static-libstdc++"]);
         }
 
-        let output = command.output().unwrap();
+        if settings.fully_static
+            && settings.distribution == Distribution::Executable
+            && !cfg!(target_os = "macos")
+        {
+            command.arg("-static");
+        }
+
+        for framework in settings.frameworks {
+            command.arg("-framework");
+            command.arg(framework);
+        }
+
+        if let Some(soname) = settings.soname {
+            if cfg!(target_os = "macos") {
+                command.arg(format!("-Wl,-install_name,{}", soname));
+            } else {
+                command.arg(format!("-Wl,-soname,{}", soname));
+            }
+        }
+
+        if settings.rpath_origin {
+            if cfg!(target_os = "macos") {
+                command.arg("-Wl,-rpath,@loader_path");
+            } else if cfg!(not(target_os = "windows")) {
+                command.arg("-Wl,-rpath,$ORIGIN");
+            }
+        }
+
+        if let Some(linker) = settings.linker {
+            if Path::new(linker).is_absolute() {
+                command.arg(format!("-B{}", linker));
+            } else {
+                command.arg(format!("-fuse-ld={}", linker));
+            }
+        }
+
+        for link_flag in settings.link_flags {
+            command.arg(link_flag);
+        }
+
+        let output = output_with_response_file(&mut command).map_err(|err| err.to_string())?;
 
         if output.status.success() {
             Ok(())
@@ -168,61 +722,85 @@ impl CCompiler for GccFlavorCCompiler {
 
 pub(crate) struct GccFlavorCppCompiler {
     location: String,
+    launcher: Option<String>,
+    version: OnceLock<Option<DetectedVersion>>,
 }
 
 impl GccFlavorCppCompiler {
-    pub(crate) fn new(location: String) -> GccFlavorCppCompiler {
-        GccFlavorCppCompiler { location }
+    pub(crate) fn new(location: String, launcher: Option<String>) -> GccFlavorCppCompiler {
+        GccFlavorCppCompiler {
+            location,
+            launcher,
+            version: OnceLock::new(),
+        }
     }
-}
 
-impl CppCompiler for GccFlavorCppCompiler {
-    fn compile_source_file(
+    fn create_compile_command(&self) -> Command {
+        create_compile_command(&self.location, self.launcher.as_deref())
+    }
+
+    fn source_language(source_file: &Path) -> &'static str {
+        if is_objective_source(source_file) {
+            "-xobjective-c++"
+        } else {
+            "-xc++"
+        }
+    }
+
+    /// Shared by sources, precompiled headers, assembly listings and preprocessed sources, which
+    /// only differ in the `-x` language and what is written to the output file.
+    fn compile(
         &self,
-        source_file: &Path,
+        input_file: &Path,
         output_file: &Path,
+        language: &str,
+        output: CompilationOutput,
         settings: &super::CppCompilationSettings<'_>,
-    ) -> Result<(), String> {
-        let mut command = Command::new(&self.location);
+    ) -> Result<String, String> {
+        let mut command = self.create_compile_command();
 
         for additional_pre_argument in settings.additional_pre_arguments {
             command.arg(additional_pre_argument);
         }
 
-        command.arg("-c");
+        output.add_arguments(&mut command);
 
         if settings.distribution == Distribution::DynamicLibrary {
             command.arg("-fPIC");
         }
 
-        command.arg("-xc++");
+        command.arg(language);
 
         command.arg(format!(
             "-std={}",
-            match settings.standard {
-                CppStandard::NinetyEight => "c++98",
-                CppStandard::Three => "c++3",
-                CppStandard::Eleven => "c++11",
-                CppStandard::Fourteen => "c++14",
-                CppStandard::Seventeen => "c++17",
-                CppStandard::Twenty => "c++20",
-                CppStandard::TwentyThree => "c++23",
-                CppStandard::TwentySix => "c++26",
-            }
+            settings.standard.dialect_name(settings.gnu_extensions)
         ));
 
-        command.arg(format!(
-            "-O{}",
-            match settings.optimization {
-                OptimizationLevel::Zero => "0",
-                OptimizationLevel::One => "1",
-                OptimizationLevel::Two => "2",
-                OptimizationLevel::Three => "3",
-                OptimizationLevel::Four => "fast",
-                OptimizationLevel::Size => "s",
-                OptimizationLevel::Debug => "g",
-            }
-        ));
+        command.arg(format!("-O{}", settings.optimization.name()));
+
+        if let Some(debug_info) = &settings.debug_info {
+            command.arg(format!(
+                "-g{}",
+                match debug_info {
+                    DebugInfoLevel::Zero => "0",
+                    DebugInfoLevel::One => "1",
+                    DebugInfoLevel::Two => "2",
+                    DebugInfoLevel::Three => "3",
+                }
+            ));
+        }
+
+        if let Some(target_cpu) = settings.target_cpu {
+            command.arg(format!("-march={}", target_cpu));
+        }
+
+        if let Some(tune_cpu) = settings.tune_cpu {
+            command.arg(format!("-mtune={}", tune_cpu));
+        }
+
+        if let Some(lto_argument) = lto_argument(&self.location, settings.lto) {
+            command.arg(lto_argument);
+        }
 
         if settings.enable_all_warnings {
             command.arg("-Wall");
@@ -233,7 +811,37 @@ impl CppCompiler for GccFlavorCppCompiler {
             command.arg("-Werror");
         }
 
-        command.arg(source_file);
+        if settings.json_diagnostics {
+            command.args(diagnostics_format_arguments(&self.location));
+        } else if settings.diagnostics_color {
+            command.arg("-fdiagnostics-color=always");
+        }
+
+        for warning in &settings.warnings.enable {
+            command.arg(format!("-W{}", warning));
+        }
+
+        for warning in &settings.warnings.disable {
+            command.arg(format!("-Wno-{}", warning));
+        }
+
+        for warning in &settings.warnings.errors {
+            command.arg(format!("-Werror={}", warning));
+        }
+
+        for sanitizer in settings.sanitizers {
+            command.arg(format!("-fsanitize={}", sanitizer.name()));
+        }
+
+        if settings.coverage {
+            command.arg("--coverage");
+        }
+
+        if settings.threads {
+            command.arg("-pthread");
+        }
+
+        command.arg(input_file);
 
         command.arg(format!("-o{}", output_file.display()));
 
@@ -241,19 +849,142 @@ impl CppCompiler for GccFlavorCppCompiler {
             command.arg(format!("-I{}", include));
         }
 
+        if let Some(precompiled_header) = settings.precompiled_header {
+            command.arg("-include");
+            command.arg(precompiled_header);
+        }
+
+        for (name, value) in settings.defines {
+            if value.is_empty() {
+                command.arg(format!("-D{}", name));
+            } else {
+                command.arg(format!("-D{}={}", name, value));
+            }
+        }
+
         for additional_post_argument in settings.additional_post_arguments {
             command.arg(additional_post_argument);
         }
 
-        let output = command.output().unwrap();
+        // Either the compiler or its launcher may be missing
+        let output = command.output().map_err(|err| {
+            format!(
+                "Failed to run {}: {}",
+                command.get_program().to_string_lossy(),
+                err
+            )
+        })?;
+        let diagnostics = String::from_utf8_lossy(&output.stdout).into_owned()
+            + &String::from_utf8_lossy(&output.stderr);
 
         if output.status.success() {
-            Ok(())
+            Ok(diagnostics)
         } else {
-            Err(String::from_utf8_lossy(&output.stderr).into_owned())
+            Err(diagnostics)
+        }
+    }
+}
+
+impl CppCompiler for GccFlavorCppCompiler {
+    fn check_standard_support(&self, standard: &CppStandard) -> Result<(), String> {
+        let detected_version = *self.version.get_or_init(|| detect_version(&self.location));
+
+        check_minimum_version(
+            &self.location,
+            detected_version,
+            &format!("-std={}", standard.name()),
+            |is_clang| minimum_cpp_standard_version(standard, is_clang),
+        )
+    }
+
+    fn check_optimization_support(&self, optimization: &OptimizationLevel) -> Result<(), String> {
+        let detected_version = *self.version.get_or_init(|| detect_version(&self.location));
+
+        check_minimum_version(
+            &self.location,
+            detected_version,
+            &format!("-O{}", optimization.name()),
+            |is_clang| minimum_optimization_version(optimization, is_clang),
+        )
+    }
+
+    fn compile_source_file(
+        &self,
+        source_file: &Path,
+        output_file: &Path,
+        settings: &super::CppCompilationSettings<'_>,
+    ) -> Result<String, String> {
+        self.compile(
+            source_file,
+            output_file,
+            Self::source_language(source_file),
+            CompilationOutput::Object,
+            settings,
+        )
+    }
+
+    fn generate_assembly(
+        &self,
+        source_file: &Path,
+        output_file: &Path,
+        intel_syntax: bool,
+        settings: &super::CppCompilationSettings<'_>,
+    ) -> Result<String, String> {
+        self.compile(
+            source_file,
+            output_file,
+            Self::source_language(source_file),
+            CompilationOutput::Assembly { intel_syntax },
+            settings,
+        )
+    }
+
+    fn preprocess(
+        &self,
+        source_file: &Path,
+        output_file: &Path,
+        settings: &super::CppCompilationSettings<'_>,
+    ) -> Result<String, String> {
+        self.compile(
+            source_file,
+            output_file,
+            Self::source_language(source_file),
+            CompilationOutput::Preprocessed,
+            settings,
+        )
+    }
+
+    fn precompiled_header_extension(&self) -> &'static str {
+        match Flavor::detect(&self.location) {
+            Flavor::Gcc => "gch",
+            Flavor::Clang => "pch",
         }
     }
 
+    fn identity(&self) -> String {
+        compiler_identity(&self.location)
+    }
+
+    fn precompile_header(
+        &self,
+        header_file: &Path,
+        output_file: &Path,
+        settings: &super::CppCompilationSettings<'_>,
+    ) -> Result<String, String> {
+        let settings = super::CppCompilationSettings {
+            precompiled_header: None,
+            ..settings.clone()
+        };
+
+        self.compile(
+            header_file,
+            output_file,
+            "-xc++-header",
+            CompilationOutput::Object,
+            &settings,
+        )
+    }
+
     fn link_object_files(
         &self,
         object_files: &[PathBuf],
@@ -263,7 +994,11 @@ impl CppCompiler for GccFlavorCppCompiler {
         let mut command = Command::new(&self.location);
 
         if settings.distribution == Distribution::DynamicLibrary {
-            command.arg("-shared");
+            if cfg!(target_os = "macos") {
+                command.arg("-dynamiclib");
+            } else {
+                command.arg("-shared");
+            }
         }
 
         for object_file in object_files {
@@ -272,6 +1007,22 @@ impl CppCompiler for GccFlavorCppCompiler {
 
         command.arg(format!("-o{}", output_file.display()));
 
+        for sanitizer in settings.sanitizers {
+            command.arg(format!("-fsanitize={}", sanitizer.name()));
+        }
+
+        if settings.coverage {
+            command.arg("--coverage");
+        }
+
+        if settings.threads {
+            command.arg("-pthread");
+        }
+
+        if let Some(lto_argument) = lto_argument(&self.location, settings.lto) {
+            command.arg(lto_argument);
+        }
+
         for include in settings.includes {
             command.arg(format!("-I{}", include));
         }
@@ -281,10 +1032,58 @@ impl CppCompiler for GccFlavorCppCompiler {
         }
 
         for library in settings.libraries {
-            command.arg(format!("-l{}", library));
+            command.args(library_arguments(library, settings.static_libraries));
+        }
+
+        if settings.math {
+            command.arg("-lm");
+        }
+
+        if settings.static_runtime {
+            command.args(["-static-libgcc", "-static-libstdc++"]);
+        }
+
+        if settings.fully_static
+            && settings.distribution == Distribution::Executable
+            && !cfg!(target_os = "macos")
+        {
+            command.arg("-static");
+        }
+
+        for framework in settings.frameworks {
+            command.arg("-framework");
+            command.arg(framework);
+        }
+
+        if let Some(soname) = settings.soname {
+            if cfg!(target_os = "macos") {
+                command.arg(format!("-Wl,-install_name,{}", soname));
+            } else {
+                command.arg(format!("-Wl,-soname,{}", soname));
+            }
+        }
+
+        if settings.rpath_origin {
+            if cfg!(target_os = "macos") {
+                command.arg("-Wl,-rpath,@loader_path");
+            } else if cfg!(not(target_os = "windows")) {
+                command.arg("-Wl,-rpath,$ORIGIN");
+            }
+        }
+
+        if let Some(linker) = settings.linker {
+            if Path::new(linker).is_absolute() {
+                command.arg(format!("-B{}", linker));
+            } else {
+                command.arg(format!("-fuse-ld={}", linker));
+            }
+        }
+
+        for link_flag in settings.link_flags {
+            command.arg(link_flag);
         }
 
-        let output = command.output().unwrap();
+        let output = output_with_response_file(&mut command).map_err(|err| err.to_string())?;
 
         if output.status.success() {
             Ok(())
@@ -1,37 +1,194 @@
+mod clang_format;
+mod clang_tidy;
 mod gcc;
+mod strip;
 
-use std::path::{Path, PathBuf};
+use lazy_static::lazy_static;
+use std::{
+    collections::{BTreeMap, HashMap},
+    path::{Path, PathBuf},
+    process::Command,
+    sync::Mutex,
+};
 
+pub(crate) use clang_format::*;
+pub(crate) use clang_tidy::*;
 pub(crate) use gcc::*;
+pub(crate) use strip::*;
 
-use crate::config::{CStandard, CppStandard, Distribution, OptimizationLevel};
+use crate::config::{
+    CStandard, CppStandard, DebugInfoLevel, Distribution, Lto, OptimizationLevel, Sanitizer,
+    WarningsConfiguration,
+};
 
-pub trait Archiver {
+/// Objective-C(++) sources are compiled by the C and C++ compilers respectively, told apart by
+/// their extension.
+pub(crate) fn is_objective_source(source: &Path) -> bool {
+    matches!(
+        source.extension().and_then(|extension| extension.to_str()),
+        Some("m" | "mm")
+    )
+}
+
+/// Assembly sources are assembled by the C compiler, `.S` ones after going through the
+/// preprocessor.
+pub(crate) fn is_assembly_source(source: &Path) -> bool {
+    matches!(
+        source.extension().and_then(|extension| extension.to_str()),
+        Some("s" | "S")
+    )
+}
+
+pub(crate) fn locate(name: &str) -> Option<String> {
+    which::which(name)
+        .ok()
+        .map(|path| path.to_string_lossy().into_owned())
+}
+
+lazy_static! {
+    static ref DETECTED_FLAVORS: Mutex<HashMap<String, Flavor>> = Mutex::new(HashMap::new());
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Flavor {
+    Gcc,
+    Clang,
+}
+
+impl Flavor {
+    /// Guesses the flavor from the binary name, e.g. `clang-18`, `x86_64-linux-gnu-gcc` or `llvm-ar`.
+    pub(crate) fn from_location(location: &str) -> Flavor {
+        let name = Path::new(location)
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+
+        if name.contains("clang") || name.starts_with("llvm-") {
+            Flavor::Clang
+        } else {
+            Flavor::Gcc
+        }
+    }
+
+    /// Asks the binary itself with `--version`, as `cc` or a configured `gcc` may well be clang,
+    /// and only guesses from its name when the output isn't recognized. The result is cached
+    /// per location.
+    pub(crate) fn detect(location: &str) -> Flavor {
+        if let Some(flavor) = DETECTED_FLAVORS.lock().unwrap().get(location) {
+            return *flavor;
+        }
+
+        let flavor = Command::new(location)
+            .arg("--version")
+            .output()
+            .ok()
+            .and_then(|output| Self::from_version_output(&String::from_utf8_lossy(&output.stdout)))
+            .unwrap_or_else(|| Self::from_location(location));
+
+        DETECTED_FLAVORS
+            .lock()
+            .unwrap()
+            .insert(location.to_owned(), flavor);
+
+        flavor
+    }
+
+    /// Both print their name on the first line, e.g. `Apple clang version 15.0.0` or
+    /// `gcc (Debian 12.2.0-14) 12.2.0`, though renamed GCC builds only show it in the copyright.
+    fn from_version_output(output: &str) -> Option<Flavor> {
+        let first_line = output.lines().next()?;
+
+        if first_line.contains("clang") {
+            Some(Flavor::Clang)
+        } else if first_line.contains("gcc")
+            || first_line.contains("g++")
+            || output.contains("Free Software Foundation")
+        {
+            Some(Flavor::Gcc)
+        } else {
+            None
+        }
+    }
+}
+
+pub trait Archiver: Send + Sync {
+    /// LTO objects carry compiler IR, so they need an archiver that can index them.
     fn archive_object_files(
         &self,
         object_files: &[PathBuf],
         output_file: &Path,
+        lto: Lto,
     ) -> Result<(), String>;
 }
 
+#[derive(Clone)]
 pub struct CCompilationSettings<'a> {
     pub distribution: Distribution,
     pub standard: CStandard,
+    pub gnu_extensions: bool,
     pub optimization: OptimizationLevel,
+    pub debug_info: Option<DebugInfoLevel>,
+    pub target_cpu: Option<&'a str>,
+    pub tune_cpu: Option<&'a str>,
+    pub lto: Lto,
     pub includes: &'a [String],
+    pub defines: &'a BTreeMap<String, String>,
     pub enable_all_warnings: bool,
     pub treat_all_warnings_as_errors: bool,
+    pub diagnostics_color: bool,
+    pub json_diagnostics: bool,
+    pub warnings: &'a WarningsConfiguration,
+    pub sanitizers: &'a [Sanitizer],
+    pub coverage: bool,
+    pub threads: bool,
+    pub precompiled_header: Option<&'a Path>,
     pub additional_pre_arguments: &'a [String],
     pub additional_post_arguments: &'a [String],
 }
 
 pub trait CCompiler: Send + Sync {
+    fn check_standard_support(&self, standard: &CStandard) -> Result<(), String>;
+    fn check_optimization_support(&self, optimization: &OptimizationLevel) -> Result<(), String>;
+
+    /// Returns the compiler's diagnostics output, which may contain warnings even on success.
     fn compile_source_file(
         &self,
         source_file: &Path,
         output_file: &Path,
         settings: &CCompilationSettings<'_>,
-    ) -> Result<(), String>;
+    ) -> Result<String, String>;
+
+    /// Compiles the source only as far as assembly, in Intel rather than AT&T syntax on x86 when
+    /// asked to.
+    fn generate_assembly(
+        &self,
+        source_file: &Path,
+        output_file: &Path,
+        intel_syntax: bool,
+        settings: &CCompilationSettings<'_>,
+    ) -> Result<String, String>;
+
+    /// Only runs the preprocessor, writing the expanded source to the output file.
+    fn preprocess(
+        &self,
+        source_file: &Path,
+        output_file: &Path,
+        settings: &CCompilationSettings<'_>,
+    ) -> Result<String, String>;
+
+    /// Extension the compiler expects next to a header passed through `-include`.
+    fn precompiled_header_extension(&self) -> &'static str;
+
+    /// Tells compilers apart beyond their location, so that objects are only shared between
+    /// builds using the same one.
+    fn identity(&self) -> String;
+
+    fn precompile_header(
+        &self,
+        header_file: &Path,
+        output_file: &Path,
+        settings: &CCompilationSettings<'_>,
+    ) -> Result<String, String>;
 
     fn link_object_files(
         &self,
@@ -41,24 +198,74 @@ pub trait CCompiler: Send + Sync {
     ) -> Result<(), String>;
 }
 
+#[derive(Clone)]
 pub struct CppCompilationSettings<'a> {
     pub distribution: Distribution,
     pub standard: CppStandard,
+    pub gnu_extensions: bool,
     pub optimization: OptimizationLevel,
+    pub debug_info: Option<DebugInfoLevel>,
+    pub target_cpu: Option<&'a str>,
+    pub tune_cpu: Option<&'a str>,
+    pub lto: Lto,
     pub includes: &'a [String],
+    pub defines: &'a BTreeMap<String, String>,
     pub enable_all_warnings: bool,
     pub treat_all_warnings_as_errors: bool,
+    pub diagnostics_color: bool,
+    pub json_diagnostics: bool,
+    pub warnings: &'a WarningsConfiguration,
+    pub sanitizers: &'a [Sanitizer],
+    pub coverage: bool,
+    pub threads: bool,
+    pub precompiled_header: Option<&'a Path>,
     pub additional_pre_arguments: &'a [String],
     pub additional_post_arguments: &'a [String],
 }
 
 pub trait CppCompiler: Send + Sync {
+    fn check_standard_support(&self, standard: &CppStandard) -> Result<(), String>;
+    fn check_optimization_support(&self, optimization: &OptimizationLevel) -> Result<(), String>;
+
+    /// Returns the compiler's diagnostics output, which may contain warnings even on success.
     fn compile_source_file(
         &self,
         source_file: &Path,
         output_file: &Path,
         settings: &CppCompilationSettings<'_>,
-    ) -> Result<(), String>;
+    ) -> Result<String, String>;
+
+    /// Compiles the source only as far as assembly, in Intel rather than AT&T syntax on x86 when
+    /// asked to.
+    fn generate_assembly(
+        &self,
+        source_file: &Path,
+        output_file: &Path,
+        intel_syntax: bool,
+        settings: &CppCompilationSettings<'_>,
+    ) -> Result<String, String>;
+
+    /// Only runs the preprocessor, writing the expanded source to the output file.
+    fn preprocess(
+        &self,
+        source_file: &Path,
+        output_file: &Path,
+        settings: &CppCompilationSettings<'_>,
+    ) -> Result<String, String>;
+
+    /// Extension the compiler expects next to a header passed through `-include`.
+    fn precompiled_header_extension(&self) -> &'static str;
+
+    /// Tells compilers apart beyond their location, so that objects are only shared between
+    /// builds using the same one.
+    fn identity(&self) -> String;
+
+    fn precompile_header(
+        &self,
+        header_file: &Path,
+        output_file: &Path,
+        settings: &CppCompilationSettings<'_>,
+    ) -> Result<String, String>;
 
     fn link_object_files(
         &self,
@@ -72,5 +279,53 @@ pub struct LinkingSettings<'a> {
     pub distribution: Distribution,
     pub includes: &'a [String],
     pub libraries: &'a [String],
+    /// Libraries wrapped in `-Bstatic`/`-Bdynamic` on Linux, forcing the static version.
+    pub static_libraries: &'a [String],
     pub library_search_paths: &'a [String],
+    pub link_flags: &'a [String],
+    pub frameworks: &'a [String],
+    pub sanitizers: &'a [Sanitizer],
+    pub coverage: bool,
+    pub lto: Lto,
+    pub threads: bool,
+    pub math: bool,
+    pub static_runtime: bool,
+    /// Only applies to executables.
+    pub fully_static: bool,
+    /// Lets the output find dynamic libraries placed next to it.
+    pub rpath_origin: bool,
+    /// Recorded by dynamic libraries as the name dependents load them by.
+    pub soname: Option<&'a str>,
+    pub linker: Option<&'a str>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_flavor_from_version_output() {
+        assert_eq!(
+            Flavor::from_version_output(
+                "Apple clang version 15.0.0 (clang-1500.3.9.4)\nTarget: arm64-apple-darwin23.4.0"
+            ),
+            Some(Flavor::Clang)
+        );
+        assert_eq!(
+            Flavor::from_version_output(
+                "gcc (Debian 12.2.0-14) 12.2.0\nCopyright (C) 2022 Free Software Foundation, Inc."
+            ),
+            Some(Flavor::Gcc)
+        );
+        assert_eq!(
+            Flavor::from_version_output(
+                "cc (GCC) 13.2.0\nCopyright (C) 2023 Free Software Foundation, Inc."
+            ),
+            Some(Flavor::Gcc)
+        );
+        assert_eq!(
+            Flavor::from_version_output("tcc version 0.9.27 (x86_64 Linux)"),
+            None
+        );
+    }
 }
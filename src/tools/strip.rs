@@ -0,0 +1,24 @@
+use std::{path::Path, process::Command};
+
+pub(crate) struct Strip {
+    location: String,
+}
+
+impl Strip {
+    pub(crate) fn new(location: String) -> Strip {
+        Strip { location }
+    }
+
+    pub(crate) fn strip_file(&self, file: &Path) -> Result<(), String> {
+        let output = Command::new(&self.location)
+            .arg(file)
+            .output()
+            .map_err(|err| err.to_string())?;
+
+        if output.status.success() {
+            Ok(())
+        } else {
+            Err(String::from_utf8_lossy(&output.stderr).into_owned())
+        }
+    }
+}
@@ -0,0 +1,192 @@
+use crate::{
+    config::{BuildConfiguration, WorkspaceBuildConfiguration},
+    BuildConfigurationError, Dependency, Project, ProjectOpenError, BAKERY_DIRECTORY,
+    BUILD_CONFIGURATION_FILE, WORKSPACE_CONFIGURATION_FILE,
+};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::{env, fs, io};
+use thiserror::Error;
+
+/// Where a member of the workspace lives and keeps its artifacts.
+pub(crate) struct WorkspaceMember {
+    /// The member's directory as listed in the workspace.
+    pub(crate) path: PathBuf,
+    pub(crate) manifest: PathBuf,
+    pub(crate) output_directory: PathBuf,
+}
+
+pub(crate) struct Workspace {
+    /// Every member comes after the members it depends on.
+    pub(crate) members: Vec<Arc<Project>>,
+    /// Where each member lives, keyed by name.
+    locations: HashMap<String, WorkspaceMember>,
+}
+
+impl Workspace {
+    /// Opens the workspace in the current directory. Members keep their artifacts in a
+    /// directory of their own inside the workspace's output directory, `.bakery` unless another
+    /// one is given.
+    pub(crate) fn open(
+        profile: Option<&str>,
+        output_directory: Option<&Path>,
+    ) -> Result<Workspace, WorkspaceOpenError> {
+        let workspace_configuration = toml::from_str::<WorkspaceBuildConfiguration>(
+            &fs::read_to_string(WORKSPACE_CONFIGURATION_FILE)
+                .map_err(WorkspaceOpenError::InvalidWorkspacePath)?,
+        )
+        .map_err(|err| WorkspaceOpenError::SyntaxError(err.to_string()))?;
+        // Member configurations resolve relative output directories against themselves
+        let output_directory = env::current_dir()
+            .map_err(WorkspaceOpenError::InvalidWorkspacePath)?
+            .join(output_directory.unwrap_or(Path::new(BAKERY_DIRECTORY)));
+
+        let mut members = HashMap::new();
+        let mut names = Vec::new();
+
+        // Names have to be known up front, as members depend on each other by name
+        for path in &workspace_configuration.workspace.members {
+            let name = Self::read_member_name(Path::new(path))
+                .map_err(|err| WorkspaceOpenError::InvalidMember(path.clone(), err))?;
+
+            if members.contains_key(&name) {
+                return Err(WorkspaceOpenError::DuplicateMember(name));
+            }
+
+            let member = WorkspaceMember {
+                path: PathBuf::from(path),
+                manifest: Path::new(path)
+                    .canonicalize()
+                    .map_err(|err| {
+                        WorkspaceOpenError::InvalidMember(
+                            path.clone(),
+                            ProjectOpenError::InvalidProjectPath(err),
+                        )
+                    })?
+                    .join(BUILD_CONFIGURATION_FILE),
+                output_directory: output_directory.join(&name),
+            };
+
+            members.insert(name.clone(), member);
+            names.push(name);
+        }
+
+        let mut opened = HashMap::new();
+        let mut pkg_config_packages = HashMap::new();
+        let mut projects = Vec::new();
+
+        for name in names {
+            let member = &members[&name];
+            let project = Project::open_member(
+                member,
+                profile,
+                &members,
+                &mut opened,
+                &mut pkg_config_packages,
+            )
+            .map_err(|err| {
+                WorkspaceOpenError::InvalidMember(member.path.display().to_string(), err)
+            })?;
+
+            projects.push(project);
+        }
+
+        let mut ordered_members = Vec::new();
+
+        for project in &projects {
+            Self::push_in_dependency_order(project, &projects, &mut ordered_members);
+        }
+
+        Ok(Workspace {
+            members: ordered_members,
+            locations: members,
+        })
+    }
+
+    /// Opens the named member again, along with the members it depends on, so that it sees the
+    /// hashes saved by the builds that ran since the workspace was opened.
+    pub(crate) fn reopen_member(
+        &self,
+        name: &str,
+        profile: Option<&str>,
+    ) -> Result<Arc<Project>, WorkspaceOpenError> {
+        let member = self
+            .locations
+            .get(name)
+            .ok_or_else(|| WorkspaceOpenError::MissingMember(name.to_owned()))?;
+
+        Project::open_member(
+            member,
+            profile,
+            &self.locations,
+            &mut HashMap::new(),
+            &mut HashMap::new(),
+        )
+        .map_err(|err| WorkspaceOpenError::InvalidMember(member.path.display().to_string(), err))
+    }
+
+    pub(crate) fn member(&self, name: &str) -> Option<&Arc<Project>> {
+        self.members.iter().find(|member| member.name == name)
+    }
+
+    /// Whether the project depends on the named one, directly or through its dependencies.
+    pub(crate) fn depends_on(project: &Project, name: &str) -> bool {
+        project
+            .dependencies
+            .iter()
+            .any(|dependency| match dependency {
+                Dependency::Project(dependency) => {
+                    dependency.name == name || Self::depends_on(dependency, name)
+                }
+                _ => false,
+            })
+    }
+
+    fn read_member_name(path: &Path) -> Result<String, ProjectOpenError> {
+        let build_configuration_content = fs::read_to_string(path.join(BUILD_CONFIGURATION_FILE))
+            .map_err(ProjectOpenError::InvalidProjectPath)?;
+
+        toml::from_str::<BuildConfiguration>(&build_configuration_content)
+            .map(|build_configuration| build_configuration.project.name)
+            .map_err(|err| {
+                ProjectOpenError::InvalidBuildConfiguration(BuildConfigurationError::SyntaxError(
+                    err.to_string(),
+                ))
+            })
+    }
+
+    fn push_in_dependency_order(
+        project: &Arc<Project>,
+        members: &[Arc<Project>],
+        ordered_members: &mut Vec<Arc<Project>>,
+    ) {
+        for dependency in &project.dependencies {
+            if let Dependency::Project(dependency) = dependency {
+                Self::push_in_dependency_order(dependency, members, ordered_members);
+            }
+        }
+
+        if members.iter().any(|member| Arc::ptr_eq(member, project))
+            && !ordered_members
+                .iter()
+                .any(|member| Arc::ptr_eq(member, project))
+        {
+            ordered_members.push(Arc::clone(project));
+        }
+    }
+}
+
+#[derive(Error, Debug)]
+pub(crate) enum WorkspaceOpenError {
+    #[error("failed to read {WORKSPACE_CONFIGURATION_FILE}: {0}")]
+    InvalidWorkspacePath(io::Error),
+    #[error("found a syntax error: {0}")]
+    SyntaxError(String),
+    #[error("two members are named {0}")]
+    DuplicateMember(String),
+    #[error("failed to open member {0}: {1}")]
+    InvalidMember(String, ProjectOpenError),
+    #[error("there is no member named {0}")]
+    MissingMember(String),
+}